@@ -3,7 +3,8 @@
 Saytype Transcription Server
 
 A sidecar process that uses parakeet-mlx for offline speech-to-text transcription.
-Communicates with the Tauri app via command-line arguments and stdout JSON.
+Communicates with the Tauri app over a JSON-lines protocol on stdin/stdout
+(see src-tauri/src/sidecar_protocol.rs for the specification).
 
 Usage:
     # Server mode (waits for stdin commands)
@@ -14,6 +15,7 @@ Usage:
 """
 
 import argparse
+import base64
 import json
 import sys
 from pathlib import Path
@@ -22,10 +24,38 @@ import soundfile as sf
 import mlx.core as mx
 from parakeet_mlx.audio import get_logmel
 
+# Version of the stdin/stdout protocol - must match PROTOCOL_VERSION in sidecar_protocol.rs
+PROTOCOL_VERSION = 2
+
+# Features this server supports, reported in the hello handshake
+CAPABILITIES = ["transcribe_file", "stream_pcm", "partial_results"]
+
+# Sample rate the model expects; streams at other rates are rejected
+SAMPLE_RATE = 16000
+
+# Audio collected before the streaming decoder runs and a partial is reported
+PARTIAL_STEP_SAMPLES = SAMPLE_RATE // 2
+
+# Left/right attention context (in encoder frames) of the streaming decoder
+STREAM_CONTEXT_SIZE = (256, 256)
+
 # Global model cache
 _model = None
 _model_name = None
 
+# Open audio streams: stream id -> AudioStream
+_streams = {}
+
+# Real stdout, reserved for protocol messages. Everything else printed to
+# sys.stdout (e.g. library progress output) is redirected to stderr.
+_protocol_out = sys.stdout
+
+
+def send(message: dict):
+    """Write one protocol message as a single JSON line."""
+    _protocol_out.write(json.dumps(message) + "\n")
+    _protocol_out.flush()
+
 
 def load_model(model_name: str):
     """Load the parakeet-mlx model."""
@@ -40,10 +70,11 @@ def load_model(model_name: str):
         _model_name = model_name
         return _model
     except ImportError as e:
-        print(json.dumps({
-            "success": False,
+        send({
+            "type": "error",
+            "id": None,
             "error": f"Failed to import parakeet_mlx: {e}"
-        }), flush=True)
+        })
         sys.exit(1)
 
 
@@ -74,6 +105,38 @@ def warmup_model(model_name: str):
         _ = result[0].text
 
 
+def transcribe_samples(audio_data, model_name: str) -> dict:
+    """
+    Transcribe 16kHz mono float32 samples using parakeet-mlx.
+
+    Returns:
+        Dictionary with 'success', 'text' or 'error' keys
+    """
+    try:
+        model = load_model(model_name)
+
+        # Convert to mlx array (matching load_audio output format)
+        audio_mx = mx.array(audio_data.astype('float32'))
+
+        # Get mel spectrogram
+        mel = get_logmel(audio_mx, model.preprocessor_config)
+
+        # Generate transcription
+        result = model.generate(mel)[0]
+        text = result.text.strip()
+
+        return {
+            "success": True,
+            "text": text
+        }
+
+    except Exception as e:
+        return {
+            "success": False,
+            "error": str(e)
+        }
+
+
 def transcribe_audio(audio_path: str, model_name: str) -> dict:
     """
     Transcribe an audio file using parakeet-mlx.
@@ -96,9 +159,6 @@ def transcribe_audio(audio_path: str, model_name: str) -> dict:
                 "error": f"Audio file not found: {audio_path}"
             }
 
-        # Load model
-        model = load_model(model_name)
-
         # Load audio with soundfile (no FFmpeg required)
         audio_data, sr = sf.read(audio_path)
 
@@ -106,41 +166,189 @@ def transcribe_audio(audio_path: str, model_name: str) -> dict:
         if len(audio_data.shape) > 1:
             audio_data = audio_data.mean(axis=1)
 
-        # Convert to mlx array (matching load_audio output format)
-        audio_mx = mx.array(audio_data.astype('float32'))
-
-        # Get mel spectrogram
-        mel = get_logmel(audio_mx, model.preprocessor_config)
-
-        # Generate transcription
-        result = model.generate(mel)[0]
-        text = result.text.strip()
-
-        return {
-            "success": True,
-            "text": text
-        }
-
     except Exception as e:
         return {
             "success": False,
             "error": str(e)
         }
 
+    return transcribe_samples(audio_data, model_name)
 
-def server_mode(model_name: str):
+
+def decode_pcm16(data: str):
+    """Decode base64 16-bit little-endian PCM into float32 samples."""
+    import numpy as np
+
+    pcm = np.frombuffer(base64.b64decode(data), dtype="<i2")
+    return pcm.astype(np.float32) / 32767.0
+
+
+class AudioStream:
+    """
+    An open audio stream, decoded incrementally with parakeet-mlx's streaming
+    transcriber so only the tail is left to decode when the stream ends.
     """
-    Run in server mode, reading JSON commands from stdin.
 
-    Commands:
-        {"command": "ping"} -> {"success": true, "message": "pong"}
-        {"command": "transcribe", "audio_path": "/path/to/file.wav"} -> {"success": true, "text": "..."}
+    def __init__(self, model_name: str):
+        # First failure; reported when the stream ends
+        self.error = None
+        self.text = ""
+        self._pending = []
+        self._pending_samples = 0
+        self._context = None
+        try:
+            model = load_model(model_name)
+            self._context = model.transcribe_stream(context_size=STREAM_CONTEXT_SIZE)
+            self._transcriber = self._context.__enter__()
+        except Exception as e:
+            self.error = f"Failed to open stream: {e}"
+
+    def add(self, samples):
+        """Queue samples. Returns the transcript so far when it changed, else None."""
+        self._pending.append(samples)
+        self._pending_samples += len(samples)
+        if self._pending_samples < PARTIAL_STEP_SAMPLES:
+            return None
+
+        self._decode_pending()
+        text = self._transcriber.result.text.strip()
+        if text == self.text:
+            return None
+        self.text = text
+        return text
+
+    def finish(self) -> str:
+        """Decode the remaining audio and return the full transcript."""
+        try:
+            self._decode_pending()
+            return self._transcriber.result.text.strip()
+        finally:
+            self.close()
+
+    def close(self):
+        if self._context is not None:
+            self._context.__exit__(None, None, None)
+            self._context = None
+
+    def _decode_pending(self):
+        import numpy as np
+
+        if not self._pending:
+            return
+        self._transcriber.add_audio(mx.array(np.concatenate(self._pending)))
+        self._pending = []
+        self._pending_samples = 0
+
+
+def send_result(request_id, result: dict):
+    """Answer a request with a transcription dict from transcribe_samples/transcribe_audio."""
+    if result["success"]:
+        send({"type": "transcription", "id": request_id, "text": result["text"]})
+    else:
+        send({"type": "error", "id": request_id, "error": result["error"]})
+
+
+def handle_request(request: dict, model_name: str) -> bool:
     """
+    Answer a single request. Returns False when the server should exit.
+    """
+    command = request.get("command", "")
+    request_id = request.get("id")
+
+    if command == "hello":
+        send({
+            "type": "hello",
+            "id": request_id,
+            "protocol_version": PROTOCOL_VERSION,
+            "model": model_name,
+            "capabilities": CAPABILITIES,
+        })
+
+    elif command == "ping":
+        send({"type": "pong", "id": request_id})
+
+    elif command == "transcribe":
+        audio_path = request.get("audio_path", "")
+        if not audio_path:
+            send({"type": "error", "id": request_id, "error": "Missing audio_path"})
+        else:
+            send_result(request_id, transcribe_audio(audio_path, model_name))
+
+    elif command == "stream_begin":
+        stream = AudioStream(model_name)
+        sample_rate = request.get("sample_rate", SAMPLE_RATE)
+        if stream.error is None and sample_rate != SAMPLE_RATE:
+            stream.error = f"Unsupported sample rate: {sample_rate}"
+        _streams[request_id] = stream
+
+    elif command == "stream_audio":
+        stream = _streams.get(request_id)
+        if stream is not None and stream.error is None:
+            try:
+                text = stream.add(decode_pcm16(request.get("data", "")))
+                if text is not None:
+                    send({"type": "partial", "id": request_id, "text": text})
+            except Exception as e:
+                stream.error = f"Failed to decode audio: {e}"
+
+    elif command == "stream_end":
+        stream = _streams.pop(request_id, None)
+        if stream is None:
+            send({"type": "error", "id": request_id, "error": "Unknown stream"})
+        elif stream.error is not None:
+            stream.close()
+            send({"type": "error", "id": request_id, "error": stream.error})
+        else:
+            try:
+                send({"type": "transcription", "id": request_id, "text": stream.finish()})
+            except Exception as e:
+                send({"type": "error", "id": request_id, "error": str(e)})
+
+    elif command == "stream_cancel":
+        stream = _streams.pop(request_id, None)
+        if stream is not None:
+            stream.close()
+
+    elif command == "quit":
+        return False
+
+    else:
+        send({
+            "type": "error",
+            "id": request_id,
+            "error": f"Unknown command: {command}"
+        })
+
+    return True
+
+
+def server_mode(model_name: str):
+    """
+    Run in server mode, reading JSON-lines requests from stdin.
+
+    Requests:
+        {"command": "hello", "id": 1, "protocol_version": 2}
+            -> {"type": "hello", "id": 1, "protocol_version": 2, "model": "...", "capabilities": [...]}
+        {"command": "ping", "id": 2} -> {"type": "pong", "id": 2}
+        {"command": "transcribe", "id": 3, "audio_path": "/path/to/file.wav"}
+            -> {"type": "transcription", "id": 3, "text": "..."}
+        {"command": "stream_begin", "id": 4, "sample_rate": 16000}
+        {"command": "stream_audio", "id": 4, "data": "<base64 PCM16 LE>"}  (repeated)
+            -> {"type": "partial", "id": 4, "text": "..."}  (whenever the transcript grows)
+        {"command": "stream_end", "id": 4} -> {"type": "transcription", "id": 4, "text": "..."}
+        {"command": "stream_cancel", "id": 4}  (instead of stream_end, not answered)
+        {"command": "quit", "id": 5}
+
+    Failures are answered with {"type": "error", "id": <id or null>, "error": "..."}.
+    """
+    # Keep stray prints from libraries off the protocol channel
+    sys.stdout = sys.stderr
+
     # Load model and warm up BEFORE reporting ready
-    print(json.dumps({"status": "loading"}), flush=True)
+    send({"type": "status", "status": "loading"})
     load_model(model_name)
     warmup_model(model_name)
-    print(json.dumps({"status": "ready"}), flush=True)
+    send({"type": "status", "status": "ready"})
 
     for line in sys.stdin:
         line = line.strip()
@@ -150,40 +358,16 @@ def server_mode(model_name: str):
         try:
             request = json.loads(line)
         except json.JSONDecodeError as e:
-            print(json.dumps({
-                "success": False,
+            send({
+                "type": "error",
+                "id": None,
                 "error": f"Invalid JSON: {e}"
-            }), flush=True)
+            })
             continue
 
-        command = request.get("command", "")
-
-        if command == "ping":
-            print(json.dumps({
-                "success": True,
-                "message": "pong"
-            }), flush=True)
-
-        elif command == "transcribe":
-            audio_path = request.get("audio_path", "")
-            if not audio_path:
-                print(json.dumps({
-                    "success": False,
-                    "error": "Missing audio_path"
-                }), flush=True)
-            else:
-                result = transcribe_audio(audio_path, model_name)
-                print(json.dumps(result), flush=True)
-
-        elif command == "quit":
+        if not handle_request(request, model_name):
             break
 
-        else:
-            print(json.dumps({
-                "success": False,
-                "error": f"Unknown command: {command}"
-            }), flush=True)
-
 
 def main():
     parser = argparse.ArgumentParser(description="Saytype Transcription Server")
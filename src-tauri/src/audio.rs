@@ -1,266 +1,753 @@
 use cpal::traits::{DeviceTrait, HostTrait, StreamTrait};
-use cpal::{SampleFormat, Stream};
-use hound::{WavSpec, WavWriter};
-use rubato::{FftFixedInOut, Resampler};
+use cpal::{
+    Device, FromSample, SampleFormat, SampleRate, SizedSample, Stream, StreamConfig,
+    SupportedStreamConfig, SupportedStreamConfigRange,
+};
+use serde::Serialize;
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::{Arc, Mutex as StdMutex};
+use std::thread::JoinHandle;
+use std::time::Duration;
+use tauri::Emitter;
+use tokio::sync::mpsc::UnboundedSender;
+
+use crate::channels::ChannelMixer;
+use crate::config::{AudioConfig, ChannelSelection, DspConfig, VadConfig};
+use crate::dsp::DspChain;
+use crate::meter::{LevelCallback, LevelEvent, LevelMeter};
+use crate::resample::ChunkResampler;
+use crate::ring_buffer::{ring_buffer, Consumer, Producer};
+use crate::vad::SpeechGate;
+
+pub const TARGET_SAMPLE_RATE: u32 = 16000; // 16kHz for Whisper/Parakeet
+
+/// How often captured audio is resampled and forwarded to the backend
+const FORWARD_INTERVAL: Duration = Duration::from_millis(100);
+
+/// Receives 16kHz mono frames while a recording is in progress
+pub type FrameSink = UnboundedSender<Vec<f32>>;
+
+/// An input device as shown in the settings window
+#[derive(Debug, Clone, Serialize)]
+pub struct InputDeviceInfo {
+    pub name: String,
+    pub is_default: bool,
+    pub configs: Vec<SupportedInputConfig>,
+}
 
-const TARGET_SAMPLE_RATE: u32 = 16000; // 16kHz for Whisper/Parakeet
+/// One range of stream configurations an input device supports
+#[derive(Debug, Clone, Serialize)]
+pub struct SupportedInputConfig {
+    pub channels: u16,
+    pub min_sample_rate: u32,
+    pub max_sample_rate: u32,
+    pub sample_format: String,
+}
 
-/// Push-to-talk recorder using native CPAL for audio capture
-pub struct PushToTalkRecorder {
-    stream: Option<Stream>,
-    samples: Arc<StdMutex<Vec<f32>>>,
-    sample_rate: u32,
-    output_path: String,
+/// Payload for the `device-fallback` event: the configured device wasn't available,
+/// or the device being recorded from disappeared
+#[derive(Debug, Clone, Serialize)]
+pub struct DeviceFallback {
+    /// The preferred device from the config, or the one that disappeared
+    pub requested: String,
+    /// The device that is recording instead
+    pub used: String,
 }
 
-// SAFETY: Stream is not Send/Sync but we ensure it's only accessed from the thread that created it
-// The Mutex ensures exclusive access to the recorder state
-unsafe impl Send for PushToTalkRecorder {}
+/// Called from the capture thread when a recording moved to another device
+pub type DeviceSwitchCallback = Box<dyn Fn(DeviceFallback) + Send>;
 
-impl PushToTalkRecorder {
-    pub fn new() -> Self {
-        Self {
-            stream: None,
-            samples: Arc::new(StdMutex::new(Vec::new())),
-            sample_rate: 0,
-            output_path: String::new(),
-        }
+/// List the input devices of the default host with their supported configs
+pub fn list_input_devices() -> Result<Vec<InputDeviceInfo>, String> {
+    let host = cpal::default_host();
+    let default_name = host.default_input_device().and_then(|d| d.name().ok());
+    let devices = host
+        .input_devices()
+        .map_err(|e| format!("Failed to list input devices: {}", e))?;
+
+    let mut infos = Vec::new();
+    for device in devices {
+        let Ok(name) = device.name() else {
+            continue;
+        };
+        let configs = device
+            .supported_input_configs()
+            .map(|configs| {
+                configs
+                    .map(|c| SupportedInputConfig {
+                        channels: c.channels(),
+                        min_sample_rate: c.min_sample_rate().0,
+                        max_sample_rate: c.max_sample_rate().0,
+                        sample_format: format!("{:?}", c.sample_format()),
+                    })
+                    .collect()
+            })
+            .unwrap_or_default();
+        infos.push(InputDeviceInfo {
+            is_default: default_name.as_deref() == Some(name.as_str()),
+            name,
+            configs,
+        });
     }
+    Ok(infos)
+}
 
-    pub fn start(&mut self) -> Result<(), String> {
-        // Generate unique output path
-        let timestamp = std::time::SystemTime::now()
-            .duration_since(std::time::UNIX_EPOCH)
-            .unwrap()
-            .as_millis();
-        self.output_path = format!(
-            "/tmp/saytype_ptt_{}_{}.wav",
-            std::process::id(),
-            timestamp
-        );
+/// Pick the device to record from: the preferred one, then the fallbacks in
+/// order. Returns the index into `available`, or `None` for the system default.
+fn choose_device(config: &AudioConfig, available: &[String]) -> Option<usize> {
+    config
+        .input_device
+        .iter()
+        .chain(config.fallback_devices.iter())
+        .find_map(|wanted| available.iter().position(|name| name == wanted))
+}
 
-        // Get default input device
-        let host = cpal::default_host();
-        let device = host
+/// Open the input device selected by `config`. Also reports a fallback if the
+/// preferred device isn't connected.
+fn open_input_device(config: &AudioConfig) -> Result<(Device, Option<DeviceFallback>), String> {
+    let host = cpal::default_host();
+
+    let mut devices: Vec<Device> = match host.input_devices() {
+        Ok(devices) => devices.collect(),
+        Err(e) => {
+            eprintln!("[AUDIO] Failed to list input devices: {}", e);
+            Vec::new()
+        }
+    };
+    let names: Vec<String> = devices
+        .iter()
+        .map(|d| d.name().unwrap_or_default())
+        .collect();
+
+    let device = match choose_device(config, &names) {
+        Some(index) => devices.swap_remove(index),
+        None => host
             .default_input_device()
-            .ok_or("No input device available")?;
+            .ok_or("No input device available")?,
+    };
 
-        // Get device's native config
-        let config = device
-            .default_input_config()
-            .map_err(|e| format!("Failed to get input config: {}", e))?;
+    let used = device.name().unwrap_or_else(|_| "Unknown device".to_string());
+    let fallback = match &config.input_device {
+        Some(requested) if *requested != used => {
+            eprintln!(
+                "[AUDIO] Input device '{}' not available, using '{}'",
+                requested, used
+            );
+            Some(DeviceFallback {
+                requested: requested.clone(),
+                used,
+            })
+        }
+        _ => None,
+    };
 
-        self.sample_rate = config.sample_rate().0;
+    Ok((device, fallback))
+}
 
-        // Clear the sample buffer
-        {
-            let mut samples = self.samples.lock().map_err(|e| e.to_string())?;
-            samples.clear();
-        }
+/// Whether the capture callback can convert samples of this format
+fn is_supported_format(format: SampleFormat) -> bool {
+    matches!(
+        format,
+        SampleFormat::I8
+            | SampleFormat::I16
+            | SampleFormat::I32
+            | SampleFormat::I64
+            | SampleFormat::U8
+            | SampleFormat::U16
+            | SampleFormat::U32
+            | SampleFormat::U64
+            | SampleFormat::F32
+            | SampleFormat::F64
+    )
+}
 
-        // Clone Arc for the callback
-        let samples = Arc::clone(&self.samples);
-
-        // Build input stream based on sample format
-        let stream = match config.sample_format() {
-            SampleFormat::F32 => {
-                let samples_clone = Arc::clone(&samples);
-                device
-                    .build_input_stream(
-                        &config.into(),
-                        move |data: &[f32], _: &cpal::InputCallbackInfo| {
-                            if let Ok(mut buffer) = samples_clone.lock() {
-                                buffer.extend_from_slice(data);
-                            }
-                        },
-                        move |err| {
-                            eprintln!("Audio input error: {}", err);
-                        },
-                        None,
-                    )
-                    .map_err(|e| format!("Failed to build input stream: {}", e))?
-            }
-            SampleFormat::I16 => {
-                let samples_clone = Arc::clone(&samples);
-                device
-                    .build_input_stream(
-                        &config.into(),
-                        move |data: &[i16], _: &cpal::InputCallbackInfo| {
-                            if let Ok(mut buffer) = samples_clone.lock() {
-                                // Convert i16 to f32
-                                for &sample in data {
-                                    buffer.push(sample as f32 / i16::MAX as f32);
-                                }
-                            }
-                        },
-                        move |err| {
-                            eprintln!("Audio input error: {}", err);
-                        },
-                        None,
-                    )
-                    .map_err(|e| format!("Failed to build input stream: {}", e))?
-            }
-            SampleFormat::U16 => {
-                let samples_clone = Arc::clone(&samples);
-                device
-                    .build_input_stream(
-                        &config.into(),
-                        move |data: &[u16], _: &cpal::InputCallbackInfo| {
-                            if let Ok(mut buffer) = samples_clone.lock() {
-                                // Convert u16 to f32 (centered at 0)
-                                for &sample in data {
-                                    let normalized =
-                                        (sample as f32 - 32768.0) / 32768.0;
-                                    buffer.push(normalized);
-                                }
-                            }
-                        },
-                        move |err| {
-                            eprintln!("Audio input error: {}", err);
-                        },
-                        None,
-                    )
-                    .map_err(|e| format!("Failed to build input stream: {}", e))?
+/// Order the configs a device supports from most to least suitable for speech,
+/// each at the sample rate closest to 16kHz it allows. Configs with enough
+/// channels for `selection` come first, then ones that don't need upsampling,
+/// then f32 (no conversion), then fewer channels.
+fn rank_input_configs(
+    ranges: impl IntoIterator<Item = SupportedStreamConfigRange>,
+    selection: ChannelSelection,
+) -> Vec<SupportedStreamConfig> {
+    let needed_channels = match selection {
+        ChannelSelection::Downmix => 1,
+        ChannelSelection::Channel { index } => index.saturating_add(1),
+    };
+
+    let mut configs: Vec<SupportedStreamConfig> = ranges
+        .into_iter()
+        .filter(|range| is_supported_format(range.sample_format()))
+        .filter_map(|range| {
+            let rate = TARGET_SAMPLE_RATE
+                .max(range.min_sample_rate().0)
+                .min(range.max_sample_rate().0);
+            range.try_with_sample_rate(SampleRate(rate))
+        })
+        .collect();
+    configs.sort_by_key(|config| {
+        (
+            config.channels() < needed_channels,
+            config.sample_rate().0 < TARGET_SAMPLE_RATE,
+            config.sample_format() != SampleFormat::F32,
+            config.channels(),
+        )
+    });
+    configs
+}
+
+/// Build an input stream that converts samples of type `T` to f32, turns them
+/// into mono and pushes them into the capture ring buffer
+fn build_capture_stream<T>(
+    device: &Device,
+    config: &StreamConfig,
+    mixer: ChannelMixer,
+    mut producer: Producer,
+    lost: Arc<AtomicBool>,
+) -> Result<Stream, String>
+where
+    T: SizedSample,
+    f32: FromSample<T>,
+{
+    device
+        .build_input_stream(
+            config,
+            move |data: &[T], _: &cpal::InputCallbackInfo| {
+                // Runs on the real-time thread: no locks, no allocation
+                producer.push(mixer.mix(data, |sample: T| sample.to_sample::<f32>()));
+            },
+            move |err| {
+                eprintln!("Audio input error: {}", err);
+                if let cpal::StreamError::DeviceNotAvailable = err {
+                    lost.store(true, Ordering::SeqCst);
+                }
+            },
+            None,
+        )
+        .map_err(|e| format!("Failed to build input stream: {}", e))
+}
+
+/// Build a capture stream for `config`, whatever its sample format. Returns the
+/// stream and the reading end of its ring buffer. `lost` is set if the device
+/// disappears.
+fn build_stream_for_config(
+    device: &Device,
+    config: &SupportedStreamConfig,
+    audio: &AudioConfig,
+    lost: &Arc<AtomicBool>,
+) -> Result<(Stream, Consumer), String> {
+    let mixer = ChannelMixer::new(config.channels(), audio.channels);
+    let stream_config = config.config();
+    let capacity = (audio.capture_buffer_ms * config.sample_rate().0 as u64 / 1000) as usize;
+    let (producer, consumer) = ring_buffer(capacity);
+    let lost = Arc::clone(lost);
+    let stream = match config.sample_format() {
+        SampleFormat::I8 => build_capture_stream::<i8>(device, &stream_config, mixer, producer, lost),
+        SampleFormat::I16 => build_capture_stream::<i16>(device, &stream_config, mixer, producer, lost),
+        SampleFormat::I32 => build_capture_stream::<i32>(device, &stream_config, mixer, producer, lost),
+        SampleFormat::I64 => build_capture_stream::<i64>(device, &stream_config, mixer, producer, lost),
+        SampleFormat::U8 => build_capture_stream::<u8>(device, &stream_config, mixer, producer, lost),
+        SampleFormat::U16 => build_capture_stream::<u16>(device, &stream_config, mixer, producer, lost),
+        SampleFormat::U32 => build_capture_stream::<u32>(device, &stream_config, mixer, producer, lost),
+        SampleFormat::U64 => build_capture_stream::<u64>(device, &stream_config, mixer, producer, lost),
+        SampleFormat::F32 => build_capture_stream::<f32>(device, &stream_config, mixer, producer, lost),
+        SampleFormat::F64 => build_capture_stream::<f64>(device, &stream_config, mixer, producer, lost),
+        format => Err(format!("Unsupported sample format: {:?}", format)),
+    }?;
+    Ok((stream, consumer))
+}
+
+/// Open a capture stream on `device`, trying its default config first and then
+/// the other configs it supports. Returns the stream, the reading end of its ring
+/// buffer and its sample rate.
+fn open_capture_stream(
+    device: &Device,
+    audio: &AudioConfig,
+    lost: &Arc<AtomicBool>,
+) -> Result<(Stream, Consumer, u32), String> {
+    let mut candidates = Vec::new();
+    match device.default_input_config() {
+        Ok(config) => candidates.push(config),
+        Err(e) => eprintln!("[AUDIO] No default input config: {}", e),
+    }
+    match device.supported_input_configs() {
+        Ok(ranges) => candidates.extend(rank_input_configs(ranges, audio.channels)),
+        Err(e) => eprintln!("[AUDIO] Failed to list supported input configs: {}", e),
+    }
+
+    let mut last_error = "No usable input config".to_string();
+    for config in candidates {
+        match build_stream_for_config(device, &config, audio, lost) {
+            Ok((stream, consumer)) => {
+                println!(
+                    "[AUDIO] Recording {} channel(s) of {:?} at {}Hz as {:?}",
+                    config.channels(),
+                    config.sample_format(),
+                    config.sample_rate().0,
+                    audio.channels
+                );
+                return Ok((stream, consumer, config.sample_rate().0));
             }
-            format => {
-                return Err(format!("Unsupported sample format: {:?}", format));
+            Err(e) => {
+                eprintln!("[AUDIO] Input config {:?} unusable: {}", config, e);
+                last_error = e;
             }
-        };
+        }
+    }
+    Err(last_error)
+}
+
+/// Payload for the `capture-diagnostics` event, sent after every recording
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct CaptureDiagnostics {
+    /// Audio callbacks whose samples didn't fit in the capture buffer
+    pub overruns: u64,
+    /// Captured samples lost to overruns
+    pub dropped_samples: u64,
+    /// Size of the capture buffer, in samples at the device rate
+    pub buffer_capacity: usize,
+    /// Most samples waiting in the capture buffer at once
+    pub peak_fill: usize,
+}
+
+/// A finished recording
+#[derive(Debug, Clone, Default)]
+pub struct Recording {
+    /// 16kHz mono with silence trimmed; empty if no speech was detected
+    pub samples: Vec<f32>,
+    pub diagnostics: CaptureDiagnostics,
+}
+
+/// An input stream that is capturing into a ring buffer
+struct OpenInput {
+    /// Capturing stops when this is dropped
+    _stream: Stream,
+    sample_rate: u32,
+    /// Name of the device
+    device: String,
+    /// Fallback taken when the stream was opened
+    fallback: Option<DeviceFallback>,
+    /// Set once the device disappeared; the stream delivers nothing after that
+    lost: Arc<AtomicBool>,
+}
 
-        // Start recording
+impl OpenInput {
+    /// Open the device selected in `audio` and start capturing
+    fn open(audio: &AudioConfig) -> Result<(Self, Consumer), String> {
+        let (device, fallback) = open_input_device(audio)?;
+        let lost = Arc::new(AtomicBool::new(false));
+        let (stream, capture, sample_rate) = open_capture_stream(&device, audio, &lost)?;
         stream
             .play()
             .map_err(|e| format!("Failed to start recording: {}", e))?;
 
-        self.stream = Some(stream);
-        Ok(())
+        let input = Self {
+            _stream: stream,
+            sample_rate,
+            device: device.name().unwrap_or_else(|_| "Unknown device".to_string()),
+            fallback,
+            lost,
+        };
+        Ok((input, capture))
     }
 
-    pub fn stop(&mut self) -> Result<String, String> {
-        // Stop and drop the stream
-        if let Some(stream) = self.stream.take() {
-            drop(stream);
-        } else {
-            return Err("No recording in progress".to_string());
-        }
+    fn is_lost(&self) -> bool {
+        self.lost.load(Ordering::SeqCst)
+    }
+}
 
-        // Small delay to ensure all samples are collected
-        std::thread::sleep(std::time::Duration::from_millis(50));
+/// Keeps the most recent audio while the microphone is held open between
+/// recordings, so it can be prepended when the next recording starts
+struct PrerollKeeper {
+    stop_flag: Arc<AtomicBool>,
+    thread: JoinHandle<(Consumer, Vec<f32>)>,
+}
 
-        // Take samples from buffer
-        let samples = {
-            let mut buffer = self.samples.lock().map_err(|e| e.to_string())?;
-            std::mem::take(&mut *buffer)
-        };
+impl PrerollKeeper {
+    /// Drain `capture` in the background, keeping the last `max_samples`
+    fn spawn(mut capture: Consumer, max_samples: usize) -> Self {
+        let stop_flag = Arc::new(AtomicBool::new(false));
+        let stop = Arc::clone(&stop_flag);
+        let thread = std::thread::spawn(move || {
+            let mut preroll = Vec::with_capacity(capture.capacity() + max_samples);
+            loop {
+                let finished = stop.load(Ordering::SeqCst);
+                capture.pop_into(&mut preroll);
+                let excess = preroll.len().saturating_sub(max_samples);
+                preroll.drain(..excess);
+                if finished {
+                    return (capture, preroll);
+                }
+                std::thread::sleep(FORWARD_INTERVAL);
+            }
+        });
+        Self { stop_flag, thread }
+    }
+
+    /// Stop draining; returns the capture buffer and the audio kept
+    fn finish(self) -> Result<(Consumer, Vec<f32>), String> {
+        self.stop_flag.store(true, Ordering::SeqCst);
+        self.thread
+            .join()
+            .map_err(|_| "Pre-roll thread panicked".to_string())
+    }
+}
+
+/// Push-to-talk recorder using native CPAL for audio capture
+pub struct PushToTalkRecorder {
+    input: Option<OpenInput>,
+    /// Keep `input` open between recordings and prepend the last `preroll_ms`
+    keep_open: bool,
+    preroll_ms: u64,
+    /// Settings the microphone was last opened with
+    audio: AudioConfig,
+    /// Collects pre-roll while the microphone is open and nothing is recorded
+    preroll: Option<PrerollKeeper>,
+    /// Tells the forwarder thread to do a final drain and exit
+    stop_flag: Arc<AtomicBool>,
+    /// Drains the capture buffer, resamples and forwards the audio; hands the
+    /// capture buffer back when the recording ends
+    forwarder: Option<JoinHandle<(Result<Recording, String>, Consumer)>>,
+}
 
-        if samples.is_empty() {
-            return Err("No audio samples recorded".to_string());
+// SAFETY: Stream is not Send/Sync but we ensure it's only accessed from the thread that created it
+// The Mutex ensures exclusive access to the recorder state
+unsafe impl Send for PushToTalkRecorder {}
+
+impl PushToTalkRecorder {
+    pub fn new() -> Self {
+        Self {
+            input: None,
+            keep_open: false,
+            preroll_ms: 0,
+            audio: AudioConfig::default(),
+            preroll: None,
+            stop_flag: Arc::new(AtomicBool::new(false)),
+            forwarder: None,
         }
+    }
 
-        // Resample to 16kHz if needed
-        let resampled = if self.sample_rate != TARGET_SAMPLE_RATE {
-            resample_audio(&samples, self.sample_rate, TARGET_SAMPLE_RATE)?
-        } else {
-            samples
-        };
+    /// Whether the microphone is open right now, recording or not
+    pub fn is_mic_open(&self) -> bool {
+        self.input.is_some()
+    }
 
-        // Write to WAV file
-        write_wav_file(&self.output_path, &resampled, TARGET_SAMPLE_RATE)?;
+    /// Open the device selected in `audio` and start capturing
+    fn open_input(&mut self, audio: &AudioConfig) -> Result<Consumer, String> {
+        let (input, capture) = OpenInput::open(audio)?;
+        self.input = Some(input);
+        Ok(capture)
+    }
 
-        // Verify file was created
-        if !std::path::Path::new(&self.output_path).exists() {
-            return Err("WAV file was not created".to_string());
+    /// Start keeping pre-roll from the open input
+    fn keep_preroll(&mut self, capture: Consumer) {
+        if let Some(input) = &self.input {
+            let max_samples = (self.preroll_ms * input.sample_rate as u64 / 1000) as usize;
+            self.preroll = Some(PrerollKeeper::spawn(capture, max_samples));
         }
+    }
 
-        if let Ok(metadata) = std::fs::metadata(&self.output_path) {
-            if metadata.len() <= 44 {
-                return Err("WAV file is empty or too small".to_string());
-            }
+    /// Hold the microphone open between recordings, or close it, as set in `audio`.
+    /// An open microphone is reopened so device changes take effect. While
+    /// recording, the change applies once the recording ends.
+    pub fn configure_preroll(&mut self, audio: &AudioConfig) -> Result<(), String> {
+        self.keep_open = audio.keep_mic_open;
+        self.preroll_ms = audio.preroll_ms;
+        self.audio = audio.clone();
+        if self.forwarder.is_some() {
+            return Ok(());
         }
 
-        Ok(self.output_path.clone())
+        if let Some(preroll) = self.preroll.take() {
+            let _ = preroll.finish();
+        }
+        self.input = None;
+
+        if self.keep_open {
+            let capture = self.open_input(audio)?;
+            self.keep_preroll(capture);
+            println!("[AUDIO] Microphone held open for {}ms of pre-roll", self.preroll_ms);
+        } else {
+            println!("[AUDIO] Microphone closed between recordings");
+        }
+        Ok(())
     }
-}
 
-/// Resample audio from source_rate to target_rate using rubato
-fn resample_audio(samples: &[f32], source_rate: u32, target_rate: u32) -> Result<Vec<f32>, String> {
-    if source_rate == target_rate {
-        return Ok(samples.to_vec());
+    /// Start capturing from the device selected in `audio`. If `sink` is given,
+    /// 16kHz mono frames are sent to it while recording; the sink is dropped when
+    /// the recording stops. Silence is trimmed according to `vad` before anything is sent.
+    /// If the microphone is held open, the pre-roll is prepended. `on_level` gets
+    /// the input level while recording. If the device disappears mid-recording,
+    /// the recording moves to the next available one and `on_switch` is told.
+    /// Returns the fallback taken if the preferred device isn't connected.
+    pub fn start(
+        &mut self,
+        sink: Option<FrameSink>,
+        on_level: Option<LevelCallback>,
+        on_switch: Option<DeviceSwitchCallback>,
+        audio: &AudioConfig,
+        vad: VadConfig,
+        dsp: &DspConfig,
+    ) -> Result<Option<DeviceFallback>, String> {
+        if self.forwarder.is_some() {
+            return Err("Recording already in progress".to_string());
+        }
+        self.audio = audio.clone();
+
+        let warm = match self.preroll.take().map(PrerollKeeper::finish) {
+            Some(Ok(_)) if self.input.as_ref().is_some_and(OpenInput::is_lost) => {
+                eprintln!("[AUDIO] Held-open microphone disappeared, reopening");
+                self.input = None;
+                None
+            }
+            Some(Ok(warm)) => Some(warm),
+            Some(Err(e)) => {
+                eprintln!("[AUDIO] {}, reopening the microphone", e);
+                self.input = None;
+                None
+            }
+            None => None,
+        };
+        let (capture, preroll) = match warm {
+            Some((capture, preroll)) => {
+                println!("[AUDIO] Prepending {} samples of pre-roll", preroll.len());
+                (capture, preroll)
+            }
+            None => (self.open_input(audio)?, Vec::new()),
+        };
+        let Some(input) = &self.input else {
+            return Err("No input stream".to_string());
+        };
+        let source = CaptureSource {
+            sample_rate: input.sample_rate,
+            device: input.device.clone(),
+            lost: Arc::clone(&input.lost),
+            audio: audio.clone(),
+        };
+        let fallback = input.fallback.clone();
+
+        // Resample and forward audio off the real-time thread
+        self.stop_flag.store(false, Ordering::SeqCst);
+        let stop_flag = Arc::clone(&self.stop_flag);
+        let dsp = DspChain::new(dsp);
+        self.forwarder = Some(std::thread::spawn(move || {
+            let mut capture = capture;
+            let outputs = ForwarderOutputs {
+                gate: SpeechGate::new(vad),
+                dsp,
+                sink,
+                on_level,
+                on_switch,
+            };
+            let result = run_forwarder(&mut capture, preroll, stop_flag, source, outputs);
+            (result, capture)
+        }));
+
+        Ok(fallback)
     }
 
-    // Calculate chunk size that works well with rubato
-    // Use a reasonable chunk size for real-time audio
-    let chunk_size = 1024;
+    /// End the recording: close the microphone unless it is held open, let the
+    /// forwarder drain what's left and go back to collecting pre-roll
+    fn finish_recording(&mut self) -> Result<Recording, String> {
+        let Some(forwarder) = self.forwarder.take() else {
+            return Err("No recording in progress".to_string());
+        };
 
-    // Create resampler
-    let mut resampler = FftFixedInOut::<f32>::new(
-        source_rate as usize,
-        target_rate as usize,
-        chunk_size,
-        1, // mono
-    )
-    .map_err(|e| format!("Failed to create resampler: {}", e))?;
+        // Dropping the stream stops the callbacks, so the final drain sees every sample
+        if !self.keep_open {
+            self.input = None;
+        }
 
-    let input_frames_per_chunk = resampler.input_frames_next();
-    let mut output = Vec::with_capacity(
-        (samples.len() as f64 * target_rate as f64 / source_rate as f64) as usize + 1024,
-    );
+        // Let the forwarder drain what's left, flush the resampler and close the sink
+        self.stop_flag.store(true, Ordering::SeqCst);
+        let (result, capture) = forwarder
+            .join()
+            .map_err(|_| "Audio forwarder thread panicked".to_string())?;
+
+        if self.input.as_ref().is_some_and(OpenInput::is_lost) {
+            // The recording ended on another device, whose stream closed with the forwarder
+            self.input = None;
+            if self.keep_open {
+                let audio = self.audio.clone();
+                match self.open_input(&audio) {
+                    Ok(capture) => self.keep_preroll(capture),
+                    Err(e) => eprintln!("[AUDIO] Failed to reopen the microphone: {}", e),
+                }
+            }
+        } else if self.keep_open {
+            self.keep_preroll(capture);
+        }
+        result
+    }
 
-    // Process in chunks
-    let mut pos = 0;
-    while pos + input_frames_per_chunk <= samples.len() {
-        let input_chunk = vec![samples[pos..pos + input_frames_per_chunk].to_vec()];
-        let resampled = resampler
-            .process(&input_chunk, None)
-            .map_err(|e| format!("Resampling error: {}", e))?;
-        output.extend_from_slice(&resampled[0]);
-        pos += input_frames_per_chunk;
+    /// Stop capturing and return the whole recording
+    pub fn stop(&mut self) -> Result<Recording, String> {
+        self.finish_recording()
     }
 
-    // Handle remaining samples by padding with zeros
-    if pos < samples.len() {
-        let remaining = samples.len() - pos;
-        let mut padded = samples[pos..].to_vec();
-        padded.resize(input_frames_per_chunk, 0.0);
-        let input_chunk = vec![padded];
-        let resampled = resampler
-            .process(&input_chunk, None)
-            .map_err(|e| format!("Resampling error: {}", e))?;
-        // Only take the proportion of output that corresponds to actual input
-        let output_frames =
-            (remaining as f64 * target_rate as f64 / source_rate as f64) as usize;
-        output.extend_from_slice(&resampled[0][..output_frames.min(resampled[0].len())]);
+    /// Stop capturing and throw the recording away
+    pub fn cancel(&mut self) -> Result<(), String> {
+        if self.forwarder.is_none() {
+            return Err("No recording in progress".to_string());
+        }
+        let _ = self.finish_recording();
+        Ok(())
     }
+}
+
+/// The stream the forwarder starts out draining
+struct CaptureSource {
+    sample_rate: u32,
+    device: String,
+    /// Set once the device disappeared
+    lost: Arc<AtomicBool>,
+    /// To pick a replacement for a device that disappeared
+    audio: AudioConfig,
+}
 
-    Ok(output)
+/// Where the forwarder sends what it makes of the captured audio
+struct ForwarderOutputs {
+    /// Trims silence before anything is sent
+    gate: SpeechGate,
+    /// Cleans up what the gate lets through
+    dsp: DspChain,
+    /// Receives the 16kHz audio
+    sink: Option<FrameSink>,
+    /// Receives input levels
+    on_level: Option<LevelCallback>,
+    /// Told when the recording moves to another device
+    on_switch: Option<DeviceSwitchCallback>,
 }
 
-/// Write samples to a WAV file
-fn write_wav_file(path: &str, samples: &[f32], sample_rate: u32) -> Result<(), String> {
-    let spec = WavSpec {
-        channels: 1,
+/// Periodically drain the capture buffer, resample to 16kHz, trim silence
+/// and pass the audio on. `preroll` is processed first.
+/// If the device disappears, recording continues on the next available device
+/// (whose stream lives on this thread), or stops early if there is none.
+fn run_forwarder(
+    capture: &mut Consumer,
+    mut preroll: Vec<f32>,
+    stop_flag: Arc<AtomicBool>,
+    source: CaptureSource,
+    outputs: ForwarderOutputs,
+) -> Result<Recording, String> {
+    let ForwarderOutputs {
+        mut gate,
+        mut dsp,
+        sink,
+        on_level,
+        on_switch,
+    } = outputs;
+    let CaptureSource {
         sample_rate,
-        bits_per_sample: 16,
-        sample_format: hound::SampleFormat::Int,
-    };
+        mut device,
+        mut lost,
+        audio,
+    } = source;
+    let mut resampler = ChunkResampler::new(sample_rate, TARGET_SAMPLE_RATE)?;
+    let mut meter = LevelMeter::new(sample_rate);
+    let mut recording = Vec::new();
+    let mut captured = Vec::with_capacity(capture.capacity());
+    let mut captured_any = false;
+    let mut peak_fill = 0;
+    // The capture buffer may have been in use before this recording
+    let (mut base_overruns, mut base_dropped) = (capture.overruns(), capture.dropped_samples());
+    // Overruns of the buffers of devices that disappeared
+    let (mut earlier_overruns, mut earlier_dropped) = (0, 0);
+    // Replacement for a device that disappeared
+    let mut _replacement: Option<OpenInput> = None;
+    // No device left to record from
+    let mut out_of_devices = false;
+
+    loop {
+        // Read the flags before draining so the last drain sees every sample
+        let finished = out_of_devices || stop_flag.load(Ordering::SeqCst);
+        let switching = !finished && lost.load(Ordering::SeqCst);
+
+        peak_fill = peak_fill.max(capture.buffered());
+        captured.clear();
+        captured.append(&mut preroll);
+        capture.pop_into(&mut captured);
+
+        captured_any |= !captured.is_empty();
+
+        if let Some(on_level) = &on_level {
+            for event in meter.process(&captured) {
+                on_level(event);
+            }
+        }
 
-    let mut writer =
-        WavWriter::create(path, spec).map_err(|e| format!("Failed to create WAV file: {}", e))?;
+        let mut resampled = resampler.process(&captured)?;
+        if finished || switching {
+            resampled.extend(resampler.flush()?);
+        }
+        let mut frame = gate.process(&resampled);
+        if finished {
+            frame.extend(gate.finish());
+        }
+        let frame = dsp.process(&frame);
 
-    for &sample in samples {
-        // Convert f32 [-1.0, 1.0] to i16
-        let sample_i16 = (sample.clamp(-1.0, 1.0) * i16::MAX as f32) as i16;
-        writer
-            .write_sample(sample_i16)
-            .map_err(|e| format!("Failed to write sample: {}", e))?;
-    }
+        if !frame.is_empty() {
+            if let Some(sink) = &sink {
+                // The receiver may be gone if the transcription was abandoned
+                let _ = sink.send(frame.clone());
+            }
+            recording.extend_from_slice(&frame);
+        }
 
-    writer
-        .finalize()
-        .map_err(|e| format!("Failed to finalize WAV file: {}", e))?;
+        if finished {
+            if !captured_any {
+                return Err("No audio samples recorded".to_string());
+            }
+            if !gate.speech_detected() {
+                println!("[AUDIO] No speech detected in recording");
+            }
+            let diagnostics = CaptureDiagnostics {
+                overruns: earlier_overruns + capture.overruns() - base_overruns,
+                dropped_samples: earlier_dropped + capture.dropped_samples() - base_dropped,
+                buffer_capacity: capture.capacity(),
+                peak_fill,
+            };
+            if diagnostics.overruns > 0 {
+                eprintln!(
+                    "[AUDIO] Capture buffer overran {} time(s), {} samples lost",
+                    diagnostics.overruns, diagnostics.dropped_samples
+                );
+            }
+            return Ok(Recording {
+                samples: recording,
+                diagnostics,
+            });
+        }
 
-    Ok(())
+        if switching {
+            // Everything the lost device captured went out above
+            match OpenInput::open(&audio) {
+                Ok((input, replacement_capture)) => {
+                    println!("[AUDIO] '{}' disappeared, recording from '{}'", device, input.device);
+                    earlier_overruns += capture.overruns() - base_overruns;
+                    earlier_dropped += capture.dropped_samples() - base_dropped;
+                    (base_overruns, base_dropped) = (0, 0);
+                    *capture = replacement_capture;
+                    resampler = ChunkResampler::new(input.sample_rate, TARGET_SAMPLE_RATE)?;
+                    meter = LevelMeter::new(input.sample_rate);
+                    if let Some(on_switch) = &on_switch {
+                        on_switch(DeviceFallback {
+                            requested: device.clone(),
+                            used: input.device.clone(),
+                        });
+                    }
+                    device = input.device.clone();
+                    lost = Arc::clone(&input.lost);
+                    _replacement = Some(input);
+                }
+                Err(e) => {
+                    eprintln!("[AUDIO] '{}' disappeared and no other device works: {}", device, e);
+                    out_of_devices = true;
+                    continue;
+                }
+            }
+        }
+        std::thread::sleep(FORWARD_INTERVAL);
+    }
 }
 
 // Global recorder using std Mutex for thread-safety
@@ -268,16 +755,58 @@ lazy_static::lazy_static! {
     pub static ref PTT_RECORDER: StdMutex<PushToTalkRecorder> = StdMutex::new(PushToTalkRecorder::new());
 }
 
-pub fn start_recording() -> Result<(), String> {
+/// Emits the capture thread's level events to the frontend
+pub fn level_emitter(handle: tauri::AppHandle) -> LevelCallback {
+    Box::new(move |event| match event {
+        LevelEvent::Level(level) => {
+            let _ = handle.emit("audio-level", level);
+        }
+        LevelEvent::Silent => {
+            eprintln!("[AUDIO] Microphone delivers only silence");
+            let _ = handle.emit("mic-silent", ());
+        }
+    })
+}
+
+pub fn start_recording(
+    sink: Option<FrameSink>,
+    on_level: Option<LevelCallback>,
+    on_switch: Option<DeviceSwitchCallback>,
+    audio: &AudioConfig,
+    vad: VadConfig,
+    dsp: &DspConfig,
+) -> Result<Option<DeviceFallback>, String> {
     let mut recorder = PTT_RECORDER.lock().map_err(|e| e.to_string())?;
-    recorder.start()
+    recorder.start(sink, on_level, on_switch, audio, vad, dsp)
 }
 
-pub fn stop_recording() -> Result<String, String> {
+pub fn stop_recording() -> Result<Recording, String> {
     let mut recorder = PTT_RECORDER.lock().map_err(|e| e.to_string())?;
     recorder.stop()
 }
 
+pub fn cancel_recording() -> Result<(), String> {
+    let mut recorder = PTT_RECORDER.lock().map_err(|e| e.to_string())?;
+    recorder.cancel()
+}
+
+/// Apply the pre-roll settings in `audio`. Returns whether the microphone is now
+/// open, for the tray indicator.
+pub fn configure_preroll(audio: &AudioConfig) -> Result<bool, String> {
+    let mut recorder = PTT_RECORDER.lock().map_err(|e| e.to_string())?;
+    let result = recorder.configure_preroll(audio);
+    let open = recorder.is_mic_open();
+    result.map(|_| open)
+}
+
+/// Whether the microphone is open, also between recordings
+pub fn is_mic_open() -> bool {
+    PTT_RECORDER
+        .lock()
+        .map(|recorder| recorder.is_mic_open())
+        .unwrap_or(false)
+}
+
 /// Check if microphone permission is granted using AVFoundation API
 pub fn check_microphone_permission() -> bool {
     #[cfg(target_os = "macos")]
@@ -415,6 +944,22 @@ pub fn play_stop_sound() {
         .ok();
 }
 
+/// Play a sound when a recording or transcription is cancelled
+pub fn play_cancel_sound() {
+    std::process::Command::new("afplay")
+        .arg("/System/Library/Sounds/Basso.aiff")
+        .spawn()
+        .ok();
+}
+
+/// Play a sound shortly before a recording hits the maximum length
+pub fn play_limit_warning_sound() {
+    std::process::Command::new("afplay")
+        .arg("/System/Library/Sounds/Sosumi.aiff")
+        .spawn()
+        .ok();
+}
+
 /// Play a sound when hotkey pressed but app is busy/loading
 pub fn play_busy_sound() {
     std::process::Command::new("afplay")
@@ -552,3 +1097,102 @@ pub async fn request_microphone_permission() -> Result<bool, String> {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn names(names: &[&str]) -> Vec<String> {
+        names.iter().map(|n| n.to_string()).collect()
+    }
+
+    #[test]
+    fn test_device_choice() {
+        let available = names(&["MacBook Pro Microphone", "AirPods Pro", "USB Mic"]);
+
+        let preferred = AudioConfig {
+            input_device: Some("USB Mic".to_string()),
+            fallback_devices: names(&["AirPods Pro"]),
+            ..Default::default()
+        };
+        assert_eq!(choose_device(&preferred, &available), Some(2));
+
+        // Preferred device unplugged - first available fallback wins
+        let unplugged = AudioConfig {
+            input_device: Some("Studio Display Microphone".to_string()),
+            fallback_devices: names(&["Yeti", "AirPods Pro"]),
+            ..Default::default()
+        };
+        assert_eq!(choose_device(&unplugged, &available), Some(1));
+
+        // Nothing configured, or nothing available - system default
+        assert_eq!(choose_device(&AudioConfig::default(), &available), None);
+        let gone = AudioConfig {
+            input_device: Some("Yeti".to_string()),
+            ..Default::default()
+        };
+        assert_eq!(choose_device(&gone, &available), None);
+    }
+
+    fn range(
+        channels: u16,
+        min: u32,
+        max: u32,
+        format: SampleFormat,
+    ) -> SupportedStreamConfigRange {
+        SupportedStreamConfigRange::new(
+            channels,
+            SampleRate(min),
+            SampleRate(max),
+            cpal::SupportedBufferSize::Unknown,
+            format,
+        )
+    }
+
+    #[test]
+    fn test_preroll_keeps_most_recent_audio() {
+        let (mut producer, capture) = ring_buffer(1024);
+        let keeper = PrerollKeeper::spawn(capture, 100);
+        producer.push((0..500).map(|i| i as f32));
+
+        let (mut capture, preroll) = keeper.finish().unwrap();
+        let expected: Vec<f32> = (400..500).map(|i| i as f32).collect();
+        assert_eq!(preroll, expected);
+
+        // The capture buffer is handed back for the next recording
+        producer.push([1.0]);
+        let mut next = Vec::new();
+        assert_eq!(capture.pop_into(&mut next), 1);
+    }
+
+    #[test]
+    fn test_config_ranking() {
+        let ranges = vec![
+            range(2, 44100, 96000, SampleFormat::F64),
+            range(8, 48000, 48000, SampleFormat::I32),
+            range(1, 8000, 48000, SampleFormat::F32),
+            range(1, 8000, 8000, SampleFormat::I16),
+        ];
+
+        let ranked = rank_input_configs(ranges.clone(), ChannelSelection::Downmix);
+        let summary: Vec<(u16, u32, SampleFormat)> = ranked
+            .iter()
+            .map(|c| (c.channels(), c.sample_rate().0, c.sample_format()))
+            .collect();
+        assert_eq!(
+            summary,
+            vec![
+                (1, 16000, SampleFormat::F32),
+                (2, 44100, SampleFormat::F64),
+                (8, 48000, SampleFormat::I32),
+                // Below 16kHz - only if nothing else works
+                (1, 8000, SampleFormat::I16),
+            ]
+        );
+
+        // Only the 8-channel config has input 5
+        let ranked = rank_input_configs(ranges, ChannelSelection::Channel { index: 4 });
+        assert_eq!(ranked[0].channels(), 8);
+        assert_eq!(ranked[0].sample_format(), SampleFormat::I32);
+    }
+}
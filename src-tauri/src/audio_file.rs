@@ -0,0 +1,144 @@
+use std::fs::File;
+use std::path::Path;
+
+use symphonia::core::audio::SampleBuffer;
+use symphonia::core::codecs::{DecoderOptions, CODEC_TYPE_NULL};
+use symphonia::core::errors::Error as SymphoniaError;
+use symphonia::core::formats::FormatOptions;
+use symphonia::core::io::MediaSourceStream;
+use symphonia::core::meta::MetadataOptions;
+use symphonia::core::probe::Hint;
+
+use crate::audio::TARGET_SAMPLE_RATE;
+use crate::channels::ChannelMixer;
+use crate::config::ChannelSelection;
+use crate::resample::ChunkResampler;
+
+/// Decode an audio file (WAV, FLAC, MP3, Ogg Vorbis) to 16kHz mono samples.
+/// The audio is downmixed and resampled packet by packet, so long recordings
+/// never sit in memory at their original rate.
+pub fn decode_audio_file(path: &Path) -> Result<Vec<f32>, String> {
+    let file = File::open(path).map_err(|e| format!("Failed to open {:?}: {}", path, e))?;
+    let stream = MediaSourceStream::new(Box::new(file), Default::default());
+
+    let mut hint = Hint::new();
+    if let Some(extension) = path.extension().and_then(|e| e.to_str()) {
+        hint.with_extension(extension);
+    }
+    let probed = symphonia::default::get_probe()
+        .format(
+            &hint,
+            stream,
+            &FormatOptions::default(),
+            &MetadataOptions::default(),
+        )
+        .map_err(|e| format!("Unsupported audio file {:?}: {}", path, e))?;
+    let mut format = probed.format;
+
+    let track = format
+        .tracks()
+        .iter()
+        .find(|track| track.codec_params.codec != CODEC_TYPE_NULL)
+        .ok_or("File contains no audio track")?;
+    let track_id = track.id;
+    let mut decoder = symphonia::default::get_codecs()
+        .make(&track.codec_params, &DecoderOptions::default())
+        .map_err(|e| format!("Unsupported audio codec: {}", e))?;
+
+    let mut output = Vec::new();
+    let mut interleaved: Option<SampleBuffer<f32>> = None;
+    // Created from the first decoded packet, which tells the rate and channels
+    let mut converter: Option<(ChannelMixer, ChunkResampler)> = None;
+
+    loop {
+        let packet = match format.next_packet() {
+            Ok(packet) => packet,
+            Err(SymphoniaError::IoError(e)) if e.kind() == std::io::ErrorKind::UnexpectedEof => {
+                break
+            }
+            Err(e) => return Err(format!("Failed to read audio file: {}", e)),
+        };
+        if packet.track_id() != track_id {
+            continue;
+        }
+
+        let decoded = match decoder.decode(&packet) {
+            Ok(decoded) => decoded,
+            // A damaged packet only loses a few milliseconds
+            Err(SymphoniaError::DecodeError(e)) => {
+                eprintln!("[IMPORT] Skipping undecodable packet: {}", e);
+                continue;
+            }
+            Err(e) => return Err(format!("Failed to decode audio: {}", e)),
+        };
+
+        let spec = *decoded.spec();
+        let needed = decoded.capacity() * spec.channels.count();
+        let buffer = match interleaved.take() {
+            Some(buffer) if buffer.capacity() >= needed => buffer,
+            _ => SampleBuffer::new(decoded.capacity() as u64, spec),
+        };
+        let buffer = interleaved.insert(buffer);
+        buffer.copy_interleaved_ref(decoded);
+
+        if converter.is_none() {
+            println!(
+                "[IMPORT] Decoding {:?}: {}Hz, {} channel(s)",
+                path,
+                spec.rate,
+                spec.channels.count()
+            );
+            let mixer = ChannelMixer::new(spec.channels.count() as u16, ChannelSelection::Downmix);
+            converter = Some((mixer, ChunkResampler::new(spec.rate, TARGET_SAMPLE_RATE)?));
+        }
+        if let Some((mixer, resampler)) = converter.as_mut() {
+            let mono: Vec<f32> = mixer.mix(buffer.samples(), |sample| sample).collect();
+            output.extend(resampler.process(&mono)?);
+        }
+    }
+
+    let Some((_, mut resampler)) = converter else {
+        return Err("File contains no audio".to_string());
+    };
+    output.extend(resampler.flush()?);
+    Ok(output)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use hound::{WavSpec, WavWriter};
+
+    #[test]
+    fn test_stereo_wav_is_downmixed_and_resampled() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("memo.wav");
+        let spec = WavSpec {
+            channels: 2,
+            sample_rate: 44100,
+            bits_per_sample: 16,
+            sample_format: hound::SampleFormat::Int,
+        };
+        let mut writer = WavWriter::create(&path, spec).unwrap();
+        // Two seconds of a tone on the left channel only
+        for i in 0..88200 {
+            let tone = (i as f32 * 440.0 / 44100.0 * std::f32::consts::TAU).sin() * 0.5;
+            writer.write_sample((tone * i16::MAX as f32) as i16).unwrap();
+            writer.write_sample(0i16).unwrap();
+        }
+        writer.finalize().unwrap();
+
+        let samples = decode_audio_file(&path).unwrap();
+        assert_eq!(samples.len(), 32000);
+        let peak = samples.iter().fold(0.0f32, |peak, s| peak.max(s.abs()));
+        assert!((peak - 0.25).abs() < 0.01, "peak {}", peak);
+    }
+
+    #[test]
+    fn test_non_audio_file_is_rejected() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("notes.txt");
+        std::fs::write(&path, "not audio").unwrap();
+        assert!(decode_audio_file(&path).is_err());
+    }
+}
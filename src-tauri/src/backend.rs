@@ -0,0 +1,135 @@
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+use tokio::sync::mpsc::{UnboundedReceiver, UnboundedSender};
+
+use crate::audio::TARGET_SAMPLE_RATE;
+
+/// New audio a backend without native streaming waits for before it decodes the
+/// recording again for a partial transcript
+const PARTIAL_INTERVAL_SAMPLES: usize = 5 * TARGET_SAMPLE_RATE as usize;
+
+/// Which transcription backend the app uses, set as `backend` in the config
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum BackendKind {
+    /// `"sidecar"`: bundled Parakeet MLX sidecar process
+    #[default]
+    Sidecar,
+    /// `"http"`: local HTTP server (whisper.cpp or OpenAI-compatible)
+    Http,
+    /// `"mock"`: canned responses, for development and CI without a model
+    Mock,
+}
+
+/// Errors returned by transcription backends
+#[derive(Debug, Clone, PartialEq)]
+pub enum TranscriptionError {
+    /// The backend can't take requests yet (still starting, or restarting after a crash)
+    NotReady,
+    /// The backend did not answer within the configured request timeout
+    Timeout(Duration),
+    /// Talking to the backend failed (broken pipe, connection refused, ...)
+    Io(String),
+    /// The backend answered with something we could not understand
+    Protocol(String),
+    /// The backend reported a transcription failure
+    Transcription(String),
+    /// The user cancelled the recording or transcription
+    Cancelled,
+}
+
+impl std::fmt::Display for TranscriptionError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            TranscriptionError::NotReady => write!(f, "Transcription backend not ready"),
+            TranscriptionError::Timeout(timeout) => {
+                write!(f, "Transcription backend did not respond within {:?}", timeout)
+            }
+            TranscriptionError::Io(e) => write!(f, "Transcription backend I/O error: {}", e),
+            TranscriptionError::Protocol(e) => write!(f, "Transcription protocol error: {}", e),
+            TranscriptionError::Transcription(e) => write!(f, "{}", e),
+            TranscriptionError::Cancelled => write!(f, "Transcription cancelled"),
+        }
+    }
+}
+
+impl From<TranscriptionError> for String {
+    fn from(e: TranscriptionError) -> Self {
+        e.to_string()
+    }
+}
+
+/// What a backend reports about itself
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct Capabilities {
+    /// Model name, if the backend knows it
+    pub model: Option<String>,
+    /// Feature flags, e.g. "transcribe_file"
+    pub features: Vec<String>,
+}
+
+/// Receiving end of the frames a recording produces (16kHz mono samples)
+pub type FrameReceiver = UnboundedReceiver<Vec<f32>>;
+
+/// Receives the transcript so far while a recording is in progress
+pub type PartialSink = UnboundedSender<String>;
+
+/// A speech-to-text engine the dictation pipeline can hand audio to
+#[async_trait]
+pub trait TranscriptionBackend: Send + Sync {
+    /// Short identifier for logs and the settings UI
+    fn name(&self) -> &'static str;
+
+    /// Whether the backend can accept requests right now
+    async fn is_ready(&self) -> bool;
+
+    /// Model and feature information
+    async fn capabilities(&self) -> Capabilities;
+
+    /// Transcribe 16kHz mono samples. `language` (e.g. "de") overrides the backend's
+    /// own setting; backends that know only one language ignore it.
+    async fn transcribe(
+        &self,
+        samples: &[f32],
+        language: Option<&str>,
+    ) -> Result<String, TranscriptionError>;
+
+    /// Transcribe audio as it is recorded. Frames arrive until the sender is dropped;
+    /// the transcript so far is sent to `partials` whenever it grows.
+    /// The returned future is dropped when the user cancels, so it must be safe to
+    /// abandon at any await point.
+    /// By default, everything recorded so far is transcribed again every few seconds
+    /// for the partials, and the full audio once more when the recording ends. Cutting
+    /// the audio at fixed lengths would split words; the pipeline already hands over
+    /// segments cut at pauses.
+    async fn transcribe_stream(
+        &self,
+        mut frames: FrameReceiver,
+        partials: PartialSink,
+        language: Option<&str>,
+    ) -> Result<String, TranscriptionError> {
+        let mut samples = Vec::new();
+        // Length of the audio the last partial was decoded from
+        let mut decoded = 0;
+
+        while let Some(frame) = frames.recv().await {
+            samples.extend_from_slice(&frame);
+            if samples.len() - decoded >= PARTIAL_INTERVAL_SAMPLES {
+                decoded = samples.len();
+                let text = self.transcribe(&samples, language).await?;
+                if !text.is_empty() {
+                    let _ = partials.send(text);
+                }
+            }
+        }
+
+        if samples.is_empty() {
+            return Ok(String::new());
+        }
+        self.transcribe(&samples, language).await
+    }
+
+    /// Release resources before the app exits
+    async fn shutdown(&self);
+}
@@ -0,0 +1,109 @@
+use crate::config::ChannelSelection;
+
+/// Turns interleaved multi-channel input into mono, as configured by a [`ChannelSelection`]
+#[derive(Debug, Clone, Copy)]
+pub struct ChannelMixer {
+    channels: usize,
+    selection: ChannelSelection,
+}
+
+impl ChannelMixer {
+    /// Mixer for a device with `channels` channels. Picking a channel the device
+    /// doesn't have falls back to downmixing.
+    pub fn new(channels: u16, selection: ChannelSelection) -> Self {
+        let selection = match selection {
+            ChannelSelection::Channel { index } if index >= channels => {
+                eprintln!(
+                    "[AUDIO] Device has {} channel(s), can't record channel {}; downmixing instead",
+                    channels, index
+                );
+                ChannelSelection::Downmix
+            }
+            selection => selection,
+        };
+        Self {
+            channels: channels.max(1) as usize,
+            selection,
+        }
+    }
+
+    /// The mono version of `interleaved`, converting each sample with `convert`.
+    /// A trailing partial frame is dropped; the audio host always delivers whole frames.
+    pub fn mix<'a, T: Copy>(
+        &self,
+        interleaved: &'a [T],
+        convert: impl Fn(T) -> f32 + 'a,
+    ) -> impl Iterator<Item = f32> + 'a {
+        let channels = self.channels;
+        let selection = self.selection;
+        interleaved
+            .chunks_exact(channels)
+            .map(move |frame| match selection {
+                ChannelSelection::Downmix => {
+                    frame.iter().map(|&sample| convert(sample)).sum::<f32>() / channels as f32
+                }
+                ChannelSelection::Channel { index } => convert(frame[index as usize]),
+            })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn mix(channels: u16, selection: ChannelSelection, interleaved: &[f32]) -> Vec<f32> {
+        ChannelMixer::new(channels, selection)
+            .mix(interleaved, |sample| sample)
+            .collect()
+    }
+
+    #[test]
+    fn test_mono_passes_through() {
+        let input = [0.1, -0.2, 0.3];
+        assert_eq!(mix(1, ChannelSelection::Downmix, &input), input);
+    }
+
+    #[test]
+    fn test_stereo_downmix() {
+        // Left is a ramp, right is silent
+        let input = [0.2, 0.0, 0.4, 0.0, 0.6, 0.0, -1.0, 1.0];
+        let output = mix(2, ChannelSelection::Downmix, &input);
+        assert_eq!(output.len(), 4);
+        for (got, want) in output.iter().zip([0.1, 0.2, 0.3, 0.0]) {
+            assert!((got - want).abs() < 1e-6, "got {}, expected {}", got, want);
+        }
+    }
+
+    #[test]
+    fn test_channel_pick() {
+        // 4-channel interface with the mic on input 3
+        let input: Vec<f32> = (0..3)
+            .flat_map(|frame| [0.0, 0.0, frame as f32 * 0.1, 0.9])
+            .collect();
+        let output = mix(4, ChannelSelection::Channel { index: 2 }, &input);
+        assert_eq!(output, vec![0.0, 0.1, 0.2]);
+    }
+
+    #[test]
+    fn test_missing_channel_downmixes() {
+        let input = [0.5, 0.1, 0.5, 0.3];
+        let output = mix(2, ChannelSelection::Channel { index: 5 }, &input);
+        assert_eq!(output, vec![0.3, 0.4]);
+    }
+
+    #[test]
+    fn test_partial_frame_is_dropped() {
+        let input = [0.2, 0.2, 0.2, 0.4, 0.4];
+        let output = mix(3, ChannelSelection::Channel { index: 0 }, &input);
+        assert_eq!(output, vec![0.2]);
+    }
+
+    #[test]
+    fn test_selection_serialization() {
+        let json = serde_json::to_value(ChannelSelection::Channel { index: 1 }).unwrap();
+        assert_eq!(json, serde_json::json!({"mode": "channel", "index": 1}));
+        let parsed: ChannelSelection =
+            serde_json::from_value(serde_json::json!({"mode": "downmix"})).unwrap();
+        assert_eq!(parsed, ChannelSelection::Downmix);
+    }
+}
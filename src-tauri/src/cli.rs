@@ -0,0 +1,121 @@
+use std::path::PathBuf;
+use std::time::Duration;
+
+use tauri::Manager;
+
+use crate::pipeline::TimedText;
+use crate::AppState;
+
+/// How long to wait for the backend, which may be downloading its model
+const READY_TIMEOUT: Duration = Duration::from_secs(600);
+
+const USAGE: &str = "Usage: saytype transcribe <audio file>";
+
+/// Something to do from the command line instead of starting the app
+#[derive(Debug, Clone, PartialEq)]
+pub enum CliCommand {
+    /// Print the transcript of an audio file
+    Transcribe(PathBuf),
+}
+
+/// Parse the arguments after the program name. `Ok(None)` starts the app as usual.
+pub fn parse_args(mut args: impl Iterator<Item = String>) -> Result<Option<CliCommand>, String> {
+    match args.next().as_deref() {
+        Some("transcribe") => match (args.next(), args.next()) {
+            (Some(path), None) => Ok(Some(CliCommand::Transcribe(PathBuf::from(path)))),
+            _ => Err(USAGE.to_string()),
+        },
+        _ => Ok(None),
+    }
+}
+
+/// `m:ss.s`, or `h:mm:ss.s` for long recordings
+fn format_timestamp(secs: f64) -> String {
+    let tenths = (secs * 10.0).round() as u64;
+    let (hours, minutes, seconds) = (tenths / 36000, tenths / 600 % 60, tenths % 600);
+    if hours > 0 {
+        format!("{}:{:02}:{:04.1}", hours, minutes, seconds as f64 / 10.0)
+    } else {
+        format!("{}:{:04.1}", minutes, seconds as f64 / 10.0)
+    }
+}
+
+fn format_line(timed: &TimedText) -> String {
+    format!(
+        "[{} - {}] {}",
+        format_timestamp(timed.start_secs),
+        format_timestamp(timed.end_secs),
+        timed.text
+    )
+}
+
+/// Run `command` once the backend is ready, then quit the app
+pub async fn run(app_handle: tauri::AppHandle, command: CliCommand) {
+    let CliCommand::Transcribe(path) = command;
+    let code = match transcribe(&app_handle, &path).await {
+        Ok(()) => 0,
+        Err(e) => {
+            eprintln!("Error: {}", e);
+            1
+        }
+    };
+    app_handle.exit(code);
+}
+
+async fn transcribe(app_handle: &tauri::AppHandle, path: &std::path::Path) -> Result<(), String> {
+    let state = app_handle
+        .try_state::<AppState>()
+        .ok_or("App state not available")?;
+
+    let started = std::time::Instant::now();
+    while !state.backend.is_ready().await {
+        if started.elapsed() > READY_TIMEOUT {
+            return Err(format!("{} backend did not start", state.backend.name()));
+        }
+        tokio::time::sleep(Duration::from_millis(200)).await;
+    }
+
+    let transcript = crate::transcribe_file(&state, path, |progress| {
+        eprintln!(
+            "[IMPORT] Segment {}/{} ({:.0}s of {:.0}s)",
+            progress.segment, progress.segments, progress.done_secs, progress.total_secs
+        );
+    })
+    .await?;
+
+    for timed in &transcript.segments {
+        println!("{}", format_line(timed));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(args: &[&str]) -> Result<Option<CliCommand>, String> {
+        parse_args(args.iter().map(|arg| arg.to_string()))
+    }
+
+    #[test]
+    fn test_parse_args() {
+        assert_eq!(parse(&[]), Ok(None));
+        assert_eq!(
+            parse(&["transcribe", "memo.mp3"]),
+            Ok(Some(CliCommand::Transcribe(PathBuf::from("memo.mp3"))))
+        );
+        assert!(parse(&["transcribe"]).is_err());
+        assert!(parse(&["transcribe", "a.wav", "b.wav"]).is_err());
+    }
+
+    #[test]
+    fn test_format_line() {
+        let timed = TimedText {
+            start_secs: 59.96,
+            end_secs: 3725.0,
+            text: "hello".to_string(),
+        };
+        assert_eq!(format_line(&timed), "[1:00.0 - 1:02:05.0] hello");
+        assert_eq!(format_timestamp(7.25), "0:07.3");
+    }
+}
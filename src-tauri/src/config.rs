@@ -1,5 +1,8 @@
 use serde::{Deserialize, Serialize};
 use std::collections::HashSet;
+
+use crate::backend::BackendKind;
+use crate::keys::Key;
 use std::fs;
 use std::path::PathBuf;
 
@@ -14,17 +17,68 @@ pub enum Modifier {
     Function,
 }
 
+/// How the hotkey controls recording
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum HotkeyMode {
+    /// Record while the hotkey is held (push-to-talk)
+    #[default]
+    Hold,
+    /// Press once to start recording, again to stop
+    Toggle,
+    /// A short tap latches like toggle, a long hold behaves like push-to-talk
+    Hybrid,
+}
+
+/// Gestures that keep ordinary shortcuts sharing the hotkey's keys (e.g. ⌘C with
+/// Right ⌘ as the hotkey) from starting a recording
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct GestureConfig {
+    /// Recording starts only once the hotkey was held this long; 0 starts it right away
+    pub min_hold_ms: u64,
+    /// Tap the hotkey, then press it again to record
+    pub double_tap: bool,
+    /// How soon after the first tap the second press has to follow
+    pub double_tap_ms: u64,
+    /// A press during which another key goes down is a shortcut: the recording is discarded
+    pub discard_on_other_key: bool,
+}
+
+impl Default for GestureConfig {
+    fn default() -> Self {
+        Self {
+            min_hold_ms: 0,
+            double_tap: false,
+            double_tap_ms: 400,
+            discard_on_other_key: false,
+        }
+    }
+}
+
 /// Hotkey configuration
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct HotkeyConfig {
     /// Modifier keys required (e.g., [Command], or [Control, Shift])
     pub modifiers: Vec<Modifier>,
-    /// Non-modifier key keycode, if any (e.g., Space=49, F13=105)
-    pub key: Option<i64>,
-    /// Whether modifier is left (1) or right (2) side, maps keycode -> location
-    pub modifier_locations: Vec<(i64, u32)>,
+    /// Non-modifier key, if any (e.g., Space, F13)
+    pub key: Option<Key>,
+    /// The modifier keys to hold, each naming its side (e.g., MetaRight)
+    pub modifier_keys: Vec<Key>,
     /// Human-readable label (e.g., "Right ⌘" or "Ctrl+Space")
     pub label: String,
+    /// Key that discards the current recording or transcription (default Escape)
+    #[serde(default = "default_cancel_key")]
+    pub cancel_key: Option<Key>,
+    /// Hold, toggle or hybrid recording
+    #[serde(default)]
+    pub mode: HotkeyMode,
+    #[serde(default)]
+    pub gestures: GestureConfig,
+}
+
+fn default_cancel_key() -> Option<Key> {
+    Some(Key::Escape)
 }
 
 impl Default for HotkeyConfig {
@@ -33,32 +87,460 @@ impl Default for HotkeyConfig {
         Self {
             modifiers: vec![Modifier::Command],
             key: None,
-            modifier_locations: vec![(54, 2)], // Right Command
+            modifier_keys: vec![Key::MetaRight],
             label: "Right ⌘".to_string(),
+            cancel_key: default_cancel_key(),
+            mode: HotkeyMode::Hold,
+            gestures: GestureConfig::default(),
         }
     }
 }
 
 impl HotkeyConfig {
-    /// Get all keycodes that must be held for this hotkey
-    pub fn required_keycodes(&self) -> HashSet<i64> {
-        let mut keycodes: HashSet<i64> = self.modifier_locations.iter().map(|(kc, _)| *kc).collect();
+    /// Get all keys that must be held for this hotkey
+    pub fn required_keys(&self) -> HashSet<Key> {
+        let mut keys: HashSet<Key> = self.modifier_keys.iter().copied().collect();
         if let Some(key) = self.key {
-            keycodes.insert(key);
+            keys.insert(key);
+        }
+        keys
+    }
+}
+
+/// What happens with the transcript of a profile
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum InsertionMethod {
+    /// Paste it at the cursor, keeping what was on the clipboard before
+    #[default]
+    Paste,
+    /// Only copy it to the clipboard
+    Clipboard,
+}
+
+/// How a profile rewrites the transcript before inserting it
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PostProcessing {
+    /// Insert the text as transcribed
+    #[default]
+    None,
+    /// No capital at the start and no closing period, for dictating into code
+    /// comments, identifiers or commit subjects
+    Code,
+}
+
+/// Named dictation settings a hotkey binding records with
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct DictationProfile {
+    /// Shown in the settings; bindings refer to the profile by name
+    pub name: String,
+    /// Language to transcribe (e.g. "de"); `None` leaves it to the backend
+    pub language: Option<String>,
+    pub post_processing: PostProcessing,
+    pub insertion: InsertionMethod,
+}
+
+impl Default for DictationProfile {
+    fn default() -> Self {
+        Self {
+            name: DEFAULT_PROFILE.to_string(),
+            language: None,
+            post_processing: PostProcessing::None,
+            insertion: InsertionMethod::Paste,
         }
-        keycodes
     }
+}
+
+/// Name of the profile created with a fresh config
+pub const DEFAULT_PROFILE: &str = "Dictation";
+
+/// Smallest usable capture buffer: the audio thread must not outrun the
+/// resampler, which drains the buffer every 100ms, on an ordinary hiccup
+const MIN_CAPTURE_BUFFER_MS: u64 = 500;
+
+/// A hotkey and the profile it dictates with
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HotkeyBinding {
+    pub hotkey: HotkeyConfig,
+    /// Name of a profile in `AppConfig::profiles`
+    pub profile: String,
+}
 
-    /// Get all modifier keycodes (for clearing before paste)
-    pub fn modifier_keycodes(&self) -> Vec<i64> {
-        self.modifier_locations.iter().map(|(kc, _)| *kc).collect()
+impl Default for HotkeyBinding {
+    fn default() -> Self {
+        Self {
+            hotkey: HotkeyConfig::default(),
+            profile: DEFAULT_PROFILE.to_string(),
+        }
     }
 }
 
-/// App configuration
+/// Transcription sidecar settings
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SidecarConfig {
+    /// How long a single transcription request may take before the sidecar
+    /// is considered hung and gets restarted
+    pub request_timeout_ms: u64,
+}
+
+impl Default for SidecarConfig {
+    fn default() -> Self {
+        Self {
+            request_timeout_ms: 30_000,
+        }
+    }
+}
+
+/// API dialect spoken by the HTTP transcription server, set as `api` in the
+/// `http` section
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum HttpApi {
+    /// `"whispercpp"`: whisper.cpp `server` (`POST /inference`)
+    #[default]
+    WhisperCpp,
+    /// `"openai"`: OpenAI-compatible (`POST /v1/audio/transcriptions`)
+    OpenAi,
+}
+
+/// HTTP backend settings (used when `backend` is "http")
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct HttpBackendConfig {
+    /// Server address, e.g. "http://127.0.0.1:8080"
+    pub base_url: String,
+    pub api: HttpApi,
+    /// Model name sent with each request
+    pub model: String,
+    /// Optional language hint (e.g. "en")
+    pub language: Option<String>,
+    /// Bearer token, for servers that require one
+    pub api_key: Option<String>,
+    /// Request timeout
+    pub timeout_ms: u64,
+}
+
+impl Default for HttpBackendConfig {
+    fn default() -> Self {
+        Self {
+            base_url: "http://127.0.0.1:8080".to_string(),
+            api: HttpApi::WhisperCpp,
+            model: "whisper-1".to_string(),
+            language: None,
+            api_key: None,
+            timeout_ms: 30_000,
+        }
+    }
+}
+
+/// One scripted answer of the mock backend
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
+#[serde(default)]
+pub struct MockScriptEntry {
+    /// Text to return
+    pub text: String,
+    /// Error to return instead of text
+    pub error: Option<String>,
+    /// Simulated processing time
+    pub delay_ms: u64,
+}
+
+/// How a multi-channel input is turned into the mono signal that gets transcribed
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(tag = "mode", rename_all = "lowercase")]
+pub enum ChannelSelection {
+    /// Average all channels
+    #[default]
+    Downmix,
+    /// Record a single channel, e.g. the input of an audio interface the mic is
+    /// plugged into. `index` counts from 0.
+    Channel { index: u16 },
+}
+
+/// Microphone settings
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct AudioConfig {
+    /// Name of the preferred input device; `None` uses the system default
+    pub input_device: Option<String>,
+    /// Devices to try, in order, when the preferred one is not connected.
+    /// The system default is used when none of them is available either.
+    pub fallback_devices: Vec<String>,
+    /// Which channels of the device to record
+    pub channels: ChannelSelection,
+    /// Captured audio buffered between the audio thread and the resampler.
+    /// Audio that doesn't fit is dropped and reported as an overrun.
+    /// Raised to `MIN_CAPTURE_BUFFER_MS` when smaller.
+    pub capture_buffer_ms: u64,
+    /// Recordings are stopped automatically after this long, e.g. when a key
+    /// release was missed and the hotkey is stuck. 0 disables the limit.
+    pub max_recording_secs: u64,
+    /// Keep the microphone open between recordings so the audio from just before
+    /// the hotkey fires can be included. Off by default for privacy: the audio is
+    /// only held in memory, but the system shows the microphone as in use.
+    pub keep_mic_open: bool,
+    /// Audio from before the hotkey press prepended when `keep_mic_open` is on
+    pub preroll_ms: u64,
+}
+
+impl Default for AudioConfig {
+    fn default() -> Self {
+        Self {
+            input_device: None,
+            fallback_devices: Vec::new(),
+            channels: ChannelSelection::Downmix,
+            capture_buffer_ms: 2000,
+            max_recording_secs: 600,
+            keep_mic_open: false,
+            preroll_ms: 300,
+        }
+    }
+}
+
+/// How long recordings are split into segments that are transcribed one after another
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SegmentConfig {
+    /// Turn off to send each recording to the backend in one piece
+    pub enabled: bool,
+    /// Segments are cut at the first pause once they are this long
+    pub min_segment_secs: u64,
+    /// Segments never get longer than this; should fit the model's context
+    pub max_segment_secs: u64,
+    /// Silence needed to cut a segment at `min_segment_secs`. The pause needed
+    /// shrinks as the segment approaches `max_segment_secs`.
+    pub pause_ms: u64,
+}
+
+impl Default for SegmentConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            min_segment_secs: 20,
+            max_segment_secs: 28,
+            pause_ms: 300,
+        }
+    }
+}
+
+/// Voice activity detection applied to recordings before transcription
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct VadConfig {
+    /// Turn off to send every recording to the backend untouched
+    pub enabled: bool,
+    /// Windows louder than this (RMS, dBFS) count as speech
+    pub threshold_db: f32,
+    /// How much uninterrupted speech a recording needs before it is transcribed at all
+    pub min_speech_ms: u64,
+    /// Silence kept before the first and after the last speech
+    pub padding_ms: u64,
+}
+
+impl Default for VadConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            threshold_db: -45.0,
+            min_speech_ms: 150,
+            padding_ms: 300,
+        }
+    }
+}
+
+/// How recordings are brought to a consistent level
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Normalization {
+    /// Leave the level as recorded
+    Off,
+    /// Scale so the loudest sample reaches `peak_target_db`
+    #[default]
+    Peak,
+    /// Scale so the RMS level reaches `loudness_target_db`
+    Loudness,
+}
+
+/// Processing applied to recorded audio before it is transcribed
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct DspConfig {
+    /// Remove DC offset and low rumble (fans, handling noise)
+    pub high_pass: bool,
+    /// Cutoff of the high-pass filter
+    pub high_pass_hz: f32,
+    /// Silence what stays below `gate_threshold_db` between words
+    pub noise_gate: bool,
+    /// Peak level (dBFS) below which the noise gate closes
+    pub gate_threshold_db: f32,
+    pub normalization: Normalization,
+    /// Peak level (dBFS) for `Normalization::Peak`
+    pub peak_target_db: f32,
+    /// RMS level (dBFS) for `Normalization::Loudness`
+    pub loudness_target_db: f32,
+    /// Normalization never amplifies by more than this
+    pub max_gain_db: f32,
+    /// Round off peaks near full scale instead of clipping them hard
+    pub soft_clip: bool,
+}
+
+impl Default for DspConfig {
+    fn default() -> Self {
+        Self {
+            high_pass: true,
+            high_pass_hz: 80.0,
+            noise_gate: false,
+            gate_threshold_db: -50.0,
+            normalization: Normalization::Peak,
+            peak_target_db: -3.0,
+            loudness_target_db: -20.0,
+            max_gain_db: 20.0,
+            soft_clip: true,
+        }
+    }
+}
+
+/// Mock backend settings (used when `backend` is "mock")
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct MockConfig {
+    /// Answers played back in order; the last one repeats
+    pub script: Vec<MockScriptEntry>,
+}
+
+impl Default for MockConfig {
+    fn default() -> Self {
+        Self {
+            script: vec![MockScriptEntry {
+                text: "This is a mock transcription.".to_string(),
+                error: None,
+                delay_ms: 300,
+            }],
+        }
+    }
+}
+
+/// App configuration
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
 pub struct AppConfig {
-    pub hotkey: HotkeyConfig,
+    /// Hotkeys, each starting a recording with its own profile
+    pub bindings: Vec<HotkeyBinding>,
+    pub profiles: Vec<DictationProfile>,
+    /// Which transcription backend to use
+    pub backend: BackendKind,
+    pub sidecar: SidecarConfig,
+    pub http: HttpBackendConfig,
+    pub mock: MockConfig,
+    pub audio: AudioConfig,
+    pub vad: VadConfig,
+    pub segments: SegmentConfig,
+    pub dsp: DspConfig,
+}
+
+impl Default for AppConfig {
+    fn default() -> Self {
+        Self {
+            bindings: vec![HotkeyBinding::default()],
+            profiles: vec![DictationProfile::default()],
+            backend: BackendKind::default(),
+            sidecar: SidecarConfig::default(),
+            http: HttpBackendConfig::default(),
+            mock: MockConfig::default(),
+            audio: AudioConfig::default(),
+            vad: VadConfig::default(),
+            segments: SegmentConfig::default(),
+            dsp: DspConfig::default(),
+        }
+    }
+}
+
+impl AppConfig {
+    /// The profile called `name`, or the default settings if it was deleted
+    pub fn profile(&self, name: &str) -> DictationProfile {
+        match self.profiles.iter().find(|profile| profile.name == name) {
+            Some(profile) => profile.clone(),
+            None => {
+                eprintln!("[CONFIG] No profile named '{}', using defaults", name);
+                DictationProfile::default()
+            }
+        }
+    }
+
+    /// Check a binding before it replaces the one at `index`, or is added if `index` is None
+    fn check_binding(&self, binding: &HotkeyBinding, index: Option<usize>) -> Result<(), String> {
+        if binding.hotkey.required_keys().is_empty() {
+            return Err("At least one key is required".to_string());
+        }
+        if !self.profiles.iter().any(|profile| profile.name == binding.profile) {
+            return Err(format!("Unknown profile: {}", binding.profile));
+        }
+        let keys = binding.hotkey.required_keys();
+        let taken = self
+            .bindings
+            .iter()
+            .enumerate()
+            .any(|(i, other)| Some(i) != index && other.hotkey.required_keys() == keys);
+        if taken {
+            return Err(format!("{} is already in use", binding.hotkey.label));
+        }
+        Ok(())
+    }
+
+    pub fn add_binding(&mut self, binding: HotkeyBinding) -> Result<(), String> {
+        self.check_binding(&binding, None)?;
+        self.bindings.push(binding);
+        Ok(())
+    }
+
+    pub fn update_binding(&mut self, index: usize, binding: HotkeyBinding) -> Result<(), String> {
+        if index >= self.bindings.len() {
+            return Err(format!("No hotkey binding {}", index));
+        }
+        self.check_binding(&binding, Some(index))?;
+        self.bindings[index] = binding;
+        Ok(())
+    }
+
+    /// Remove a binding; the last one stays so dictation remains possible
+    pub fn remove_binding(&mut self, index: usize) -> Result<(), String> {
+        if index >= self.bindings.len() {
+            return Err(format!("No hotkey binding {}", index));
+        }
+        if self.bindings.len() == 1 {
+            return Err("At least one hotkey is required".to_string());
+        }
+        self.bindings.remove(index);
+        Ok(())
+    }
+
+    /// Add a profile, or replace the one with the same name
+    pub fn save_profile(&mut self, profile: DictationProfile) -> Result<(), String> {
+        if profile.name.trim().is_empty() {
+            return Err("Profiles need a name".to_string());
+        }
+        match self.profiles.iter_mut().find(|p| p.name == profile.name) {
+            Some(existing) => *existing = profile,
+            None => self.profiles.push(profile),
+        }
+        Ok(())
+    }
+
+    /// Remove a profile no binding uses
+    pub fn remove_profile(&mut self, name: &str) -> Result<(), String> {
+        if let Some(binding) = self.bindings.iter().find(|binding| binding.profile == name) {
+            return Err(format!("'{}' is used by {}", name, binding.hotkey.label));
+        }
+        let before = self.profiles.len();
+        self.profiles.retain(|profile| profile.name != name);
+        if self.profiles.len() == before {
+            return Err(format!("Unknown profile: {}", name));
+        }
+        Ok(())
+    }
 }
 
 /// Get the config file path
@@ -78,9 +560,15 @@ pub fn load_config() -> AppConfig {
             if path.exists() {
                 match fs::read_to_string(&path) {
                     Ok(contents) => {
-                        match serde_json::from_str(&contents) {
-                            Ok(config) => {
+                        match parse_config(&contents) {
+                            Ok((config, migrated)) => {
                                 println!("[CONFIG] Loaded config from {:?}", path);
+                                if migrated {
+                                    println!("[CONFIG] Converted the config to the current format");
+                                    if let Err(e) = save_config(&config) {
+                                        eprintln!("[CONFIG] Failed to save migrated config: {}", e);
+                                    }
+                                }
                                 return config;
                             }
                             Err(e) => {
@@ -102,6 +590,85 @@ pub fn load_config() -> AppConfig {
     AppConfig::default()
 }
 
+/// Parse a config file, migrating older formats. Also returns whether it was migrated.
+fn parse_config(contents: &str) -> Result<(AppConfig, bool), String> {
+    let mut value: serde_json::Value =
+        serde_json::from_str(contents).map_err(|e| e.to_string())?;
+    let migrated = migrate_hotkey(&mut value);
+    let mut config: AppConfig = serde_json::from_value(value).map_err(|e| e.to_string())?;
+    if config.audio.capture_buffer_ms < MIN_CAPTURE_BUFFER_MS {
+        eprintln!(
+            "[CONFIG] capture_buffer_ms {} is too small, using {}",
+            config.audio.capture_buffer_ms, MIN_CAPTURE_BUFFER_MS
+        );
+        config.audio.capture_buffer_ms = MIN_CAPTURE_BUFFER_MS;
+    }
+    Ok((config, migrated))
+}
+
+/// Configs used to have a single `hotkey`; it becomes the only binding, using
+/// the default profile. Returns whether anything changed.
+fn migrate_hotkey(config: &mut serde_json::Value) -> bool {
+    let Some(config) = config.as_object_mut() else {
+        return false;
+    };
+    let Some(mut hotkey) = config.remove("hotkey") else {
+        return false;
+    };
+    migrate_keycodes(&mut hotkey);
+    if !config.contains_key("bindings") {
+        let binding = serde_json::json!({ "hotkey": hotkey, "profile": DEFAULT_PROFILE });
+        config.insert("bindings".to_string(), serde_json::Value::Array(vec![binding]));
+    }
+    true
+}
+
+/// Hotkeys used to be stored as macOS keycodes: `key` and `cancel_key` as
+/// numbers, and `modifier_locations` as `[keycode, side]` pairs. Rewrites them
+/// as key names and returns whether anything changed.
+fn migrate_keycodes(hotkey: &mut serde_json::Value) -> bool {
+    use serde_json::Value;
+
+    let Some(hotkey) = hotkey.as_object_mut() else {
+        return false;
+    };
+    let key_name = |keycode: i64| match Key::from_mac_keycode(keycode) {
+        Some(key) => Value::from(key.code()),
+        None => {
+            eprintln!("[CONFIG] Dropping unknown keycode {} from the hotkey", keycode);
+            Value::Null
+        }
+    };
+
+    let mut migrated = false;
+    if let Some(keycode) = hotkey.get("key").and_then(Value::as_i64) {
+        hotkey.insert("key".to_string(), key_name(keycode));
+        migrated = true;
+    }
+    if let Some(keycode) = hotkey.get("cancel_key").and_then(Value::as_i64) {
+        // Dropping it would leave the dictation without a way to cancel
+        let key = Key::from_mac_keycode(keycode).or_else(|| {
+            eprintln!("[CONFIG] Unknown cancel keycode {}, using the default", keycode);
+            default_cancel_key()
+        });
+        hotkey.insert("cancel_key".to_string(), serde_json::json!(key));
+        migrated = true;
+    }
+    if let Some(locations) = hotkey.remove("modifier_locations") {
+        let keys: Vec<Value> = locations
+            .as_array()
+            .into_iter()
+            .flatten()
+            .filter_map(|location| location.get(0).and_then(Value::as_i64))
+            .map(key_name)
+            .filter(|key| !key.is_null())
+            .collect();
+        hotkey.insert("modifier_keys".to_string(), Value::Array(keys));
+        migrated = true;
+    }
+    migrated
+}
+
 /// Save configuration to disk
 pub fn save_config(config: &AppConfig) -> Result<(), String> {
     let path = config_path()?;
@@ -122,264 +689,139 @@ pub fn save_config(config: &AppConfig) -> Result<(), String> {
     Ok(())
 }
 
-/// Map JavaScript event.code to macOS keycode
-pub fn js_code_to_keycode(code: &str) -> Option<i64> {
-    // Common keycodes for macOS
-    // Reference: https://eastmanreference.com/complete-list-of-applescript-key-codes
-    match code {
-        // Letters
-        "KeyA" => Some(0),
-        "KeyS" => Some(1),
-        "KeyD" => Some(2),
-        "KeyF" => Some(3),
-        "KeyH" => Some(4),
-        "KeyG" => Some(5),
-        "KeyZ" => Some(6),
-        "KeyX" => Some(7),
-        "KeyC" => Some(8),
-        "KeyV" => Some(9),
-        "KeyB" => Some(11),
-        "KeyQ" => Some(12),
-        "KeyW" => Some(13),
-        "KeyE" => Some(14),
-        "KeyR" => Some(15),
-        "KeyY" => Some(16),
-        "KeyT" => Some(17),
-        "Key1" | "Digit1" => Some(18),
-        "Key2" | "Digit2" => Some(19),
-        "Key3" | "Digit3" => Some(20),
-        "Key4" | "Digit4" => Some(21),
-        "Key6" | "Digit6" => Some(22),
-        "Key5" | "Digit5" => Some(23),
-        "Equal" => Some(24),
-        "Key9" | "Digit9" => Some(25),
-        "Key7" | "Digit7" => Some(26),
-        "Minus" => Some(27),
-        "Key8" | "Digit8" => Some(28),
-        "Key0" | "Digit0" => Some(29),
-        "BracketRight" => Some(30),
-        "KeyO" => Some(31),
-        "KeyU" => Some(32),
-        "BracketLeft" => Some(33),
-        "KeyI" => Some(34),
-        "KeyP" => Some(35),
-        "Enter" => Some(36),
-        "KeyL" => Some(37),
-        "KeyJ" => Some(38),
-        "Quote" => Some(39),
-        "KeyK" => Some(40),
-        "Semicolon" => Some(41),
-        "Backslash" => Some(42),
-        "Comma" => Some(43),
-        "Slash" => Some(44),
-        "KeyN" => Some(45),
-        "KeyM" => Some(46),
-        "Period" => Some(47),
-        "Tab" => Some(48),
-        "Space" => Some(49),
-        "Backquote" => Some(50),
-        "Backspace" => Some(51),
-        "Escape" => Some(53),
-
-        // Modifier keys with side differentiation
-        "MetaRight" => Some(54),   // Right Command
-        "MetaLeft" => Some(55),    // Left Command
-        "ShiftLeft" => Some(56),   // Left Shift
-        "CapsLock" => Some(57),
-        "AltLeft" => Some(58),     // Left Option
-        "ControlLeft" => Some(59), // Left Control
-        "ShiftRight" => Some(60),  // Right Shift
-        "AltRight" => Some(61),    // Right Option
-        "ControlRight" => Some(62),// Right Control
-        "Fn" => Some(63),          // Function key
-
-        // Function keys
-        "F17" => Some(64),
-        "NumpadDecimal" => Some(65),
-        "NumpadMultiply" => Some(67),
-        "NumpadAdd" => Some(69),
-        "NumLock" => Some(71),
-        "NumpadDivide" => Some(75),
-        "NumpadEnter" => Some(76),
-        "NumpadSubtract" => Some(78),
-        "F18" => Some(79),
-        "F19" => Some(80),
-        "NumpadEqual" => Some(81),
-        "Numpad0" => Some(82),
-        "Numpad1" => Some(83),
-        "Numpad2" => Some(84),
-        "Numpad3" => Some(85),
-        "Numpad4" => Some(86),
-        "Numpad5" => Some(87),
-        "Numpad6" => Some(88),
-        "Numpad7" => Some(89),
-        "F20" => Some(90),
-        "Numpad8" => Some(91),
-        "Numpad9" => Some(92),
-        "F5" => Some(96),
-        "F6" => Some(97),
-        "F7" => Some(98),
-        "F3" => Some(99),
-        "F8" => Some(100),
-        "F9" => Some(101),
-        "F11" => Some(103),
-        "F13" => Some(105),
-        "F16" => Some(106),
-        "F14" => Some(107),
-        "F10" => Some(109),
-        "F12" => Some(111),
-        "F15" => Some(113),
-        "Home" => Some(115),
-        "PageUp" => Some(116),
-        "Delete" => Some(117),
-        "F4" => Some(118),
-        "End" => Some(119),
-        "F2" => Some(120),
-        "PageDown" => Some(121),
-        "F1" => Some(122),
-        "ArrowLeft" => Some(123),
-        "ArrowRight" => Some(124),
-        "ArrowDown" => Some(125),
-        "ArrowUp" => Some(126),
-
-        _ => None,
-    }
-}
-
-/// Map macOS keycode to display label
-pub fn keycode_to_label(keycode: i64) -> String {
-    match keycode {
-        // Modifiers
-        54 => "Right ⌘".to_string(),
-        55 => "Left ⌘".to_string(),
-        56 => "Left ⇧".to_string(),
-        60 => "Right ⇧".to_string(),
-        58 => "Left ⌥".to_string(),
-        61 => "Right ⌥".to_string(),
-        59 => "Left ⌃".to_string(),
-        62 => "Right ⌃".to_string(),
-        57 => "⇪ Caps Lock".to_string(),
-        63 => "fn".to_string(),
-
-        // Common keys
-        49 => "Space".to_string(),
-        36 => "Return".to_string(),
-        48 => "Tab".to_string(),
-        53 => "Escape".to_string(),
-        51 => "Delete".to_string(),
-
-        // Function keys
-        122 => "F1".to_string(),
-        120 => "F2".to_string(),
-        99 => "F3".to_string(),
-        118 => "F4".to_string(),
-        96 => "F5".to_string(),
-        97 => "F6".to_string(),
-        98 => "F7".to_string(),
-        100 => "F8".to_string(),
-        101 => "F9".to_string(),
-        109 => "F10".to_string(),
-        103 => "F11".to_string(),
-        111 => "F12".to_string(),
-        105 => "F13".to_string(),
-        107 => "F14".to_string(),
-        113 => "F15".to_string(),
-        106 => "F16".to_string(),
-        64 => "F17".to_string(),
-        79 => "F18".to_string(),
-        80 => "F19".to_string(),
-        90 => "F20".to_string(),
-
-        // Letters
-        0 => "A".to_string(),
-        11 => "B".to_string(),
-        8 => "C".to_string(),
-        2 => "D".to_string(),
-        14 => "E".to_string(),
-        3 => "F".to_string(),
-        5 => "G".to_string(),
-        4 => "H".to_string(),
-        34 => "I".to_string(),
-        38 => "J".to_string(),
-        40 => "K".to_string(),
-        37 => "L".to_string(),
-        46 => "M".to_string(),
-        45 => "N".to_string(),
-        31 => "O".to_string(),
-        35 => "P".to_string(),
-        12 => "Q".to_string(),
-        15 => "R".to_string(),
-        1 => "S".to_string(),
-        17 => "T".to_string(),
-        32 => "U".to_string(),
-        9 => "V".to_string(),
-        13 => "W".to_string(),
-        7 => "X".to_string(),
-        16 => "Y".to_string(),
-        6 => "Z".to_string(),
-
-        // Numbers
-        18 => "1".to_string(),
-        19 => "2".to_string(),
-        20 => "3".to_string(),
-        21 => "4".to_string(),
-        23 => "5".to_string(),
-        22 => "6".to_string(),
-        26 => "7".to_string(),
-        28 => "8".to_string(),
-        25 => "9".to_string(),
-        29 => "0".to_string(),
-
-        // Arrow keys
-        123 => "←".to_string(),
-        124 => "→".to_string(),
-        125 => "↓".to_string(),
-        126 => "↑".to_string(),
-
-        _ => format!("Key{}", keycode),
-    }
-}
-
-/// Check if a keycode is a modifier key
-pub fn is_modifier_keycode(keycode: i64) -> bool {
-    matches!(keycode, 54 | 55 | 56 | 60 | 58 | 61 | 59 | 62 | 57 | 63)
-}
-
-/// Build a display label from keycodes
-pub fn build_label(keycodes: &[i64]) -> String {
-    let mut labels: Vec<String> = keycodes.iter().map(|&kc| keycode_to_label(kc)).collect();
-
-    // Sort so modifiers come first
-    labels.sort_by(|a, b| {
-        let a_is_mod = a.contains('⌘') || a.contains('⇧') || a.contains('⌥') || a.contains('⌃') || a.contains("fn") || a.contains("Caps");
-        let b_is_mod = b.contains('⌘') || b.contains('⇧') || b.contains('⌥') || b.contains('⌃') || b.contains("fn") || b.contains("Caps");
-        b_is_mod.cmp(&a_is_mod)
-    });
-
-    labels.join("+")
-}
-
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::keys;
 
     #[test]
     fn test_default_config() {
         let config = HotkeyConfig::default();
         assert_eq!(config.label, "Right ⌘");
-        assert!(config.required_keycodes().contains(&54));
+        assert!(config.required_keys().contains(&Key::MetaRight));
     }
 
     #[test]
     fn test_keycode_mapping() {
-        assert_eq!(js_code_to_keycode("MetaRight"), Some(54));
-        assert_eq!(js_code_to_keycode("Space"), Some(49));
-        assert_eq!(js_code_to_keycode("F13"), Some(105));
+        assert_eq!(Key::from_code("MetaRight").map(Key::mac_keycode), Some(54));
+        assert_eq!(Key::from_code("Space").map(Key::mac_keycode), Some(49));
+        assert_eq!(Key::from_code("F13").map(Key::mac_keycode), Some(105));
+        // Older settings windows recorded digits as `Key1`
+        assert_eq!(Key::from_code("Key1"), Some(Key::Digit1));
+    }
+
+    #[test]
+    fn test_missing_sections_use_defaults() {
+        // Configs written before a section existed must still load
+        let json = r#"{"hotkey":{"modifiers":["command"],"key":null,"modifier_locations":[[54,2]],"label":"Right ⌘"}}"#;
+        let (config, _) = parse_config(json).unwrap();
+        let hotkey = &config.bindings[0].hotkey;
+        assert_eq!(hotkey.label, "Right ⌘");
+        assert_eq!(config.backend, BackendKind::Sidecar);
+        assert_eq!(config.sidecar.request_timeout_ms, 30_000);
+        assert_eq!(hotkey.cancel_key, Some(Key::Escape));
+        assert_eq!(hotkey.mode, HotkeyMode::Hold);
+        assert_eq!(hotkey.gestures, GestureConfig::default());
+        assert_eq!(config.profiles, vec![DictationProfile::default()]);
+    }
+
+    #[test]
+    fn test_keycode_configs_are_migrated() {
+        let json = r#"{"hotkey":{"modifiers":["control"],"key":49,"modifier_locations":[[59,1]],"label":"Left ⌃+Space","cancel_key":53,"mode":"toggle"}}"#;
+        let (config, migrated) = parse_config(json).unwrap();
+        assert!(migrated);
+        assert_eq!(config.bindings.len(), 1);
+        let binding = &config.bindings[0];
+        assert_eq!(binding.profile, DEFAULT_PROFILE);
+        assert_eq!(binding.hotkey.key, Some(Key::Space));
+        assert_eq!(binding.hotkey.modifier_keys, vec![Key::ControlLeft]);
+        assert_eq!(binding.hotkey.cancel_key, Some(Key::Escape));
+        assert_eq!(binding.hotkey.mode, HotkeyMode::Toggle);
+
+        // Stored by name, as a binding, from now on
+        let json = serde_json::to_value(&config).unwrap();
+        assert!(json.get("hotkey").is_none());
+        let hotkey = &json["bindings"][0]["hotkey"];
+        assert_eq!(hotkey["key"], "Space");
+        assert_eq!(hotkey["modifier_keys"][0], "ControlLeft");
+        assert!(hotkey.get("modifier_locations").is_none());
+
+        // An unknown cancel keycode must not leave the dictation without a cancel key
+        let json = r#"{"hotkey":{"modifiers":["control"],"key":49,"modifier_locations":[[59,1]],"label":"Left ⌃+Space","cancel_key":999}}"#;
+        let (config, _) = parse_config(json).unwrap();
+        assert_eq!(config.bindings[0].hotkey.cancel_key, Some(Key::Escape));
     }
 
     #[test]
     fn test_label_building() {
-        assert_eq!(build_label(&[54]), "Right ⌘");
-        assert_eq!(build_label(&[59, 49]), "Left ⌃+Space");
+        assert_eq!(keys::build_label(&[Key::MetaRight]), "Right ⌘");
+        assert_eq!(keys::build_label(&[Key::Space, Key::ControlLeft]), "Left ⌃+Space");
+    }
+
+    fn binding(keys: &[Key], profile: &str) -> HotkeyBinding {
+        HotkeyBinding {
+            hotkey: HotkeyConfig {
+                key: keys.iter().copied().find(|key| !key.is_modifier()),
+                modifier_keys: keys.iter().copied().filter(|key| key.is_modifier()).collect(),
+                label: keys::build_label(keys),
+                ..HotkeyConfig::default()
+            },
+            profile: profile.to_string(),
+        }
+    }
+
+    #[test]
+    fn test_bindings_need_free_keys_and_known_profiles() {
+        let mut config = AppConfig::default();
+        let code = DictationProfile {
+            name: "Code".to_string(),
+            post_processing: PostProcessing::Code,
+            ..DictationProfile::default()
+        };
+        config.save_profile(code).unwrap();
+
+        assert!(config.add_binding(binding(&[Key::AltRight], "Code")).is_ok());
+        // Right ⌘ is the default binding
+        assert!(config.add_binding(binding(&[Key::MetaRight], "Code")).is_err());
+        assert!(config.add_binding(binding(&[Key::F13], "Clipboard")).is_err());
+        // Rebinding a hotkey to its own keys is fine
+        assert!(config.update_binding(1, binding(&[Key::AltRight], DEFAULT_PROFILE)).is_ok());
+        assert_eq!(config.bindings[1].profile, DEFAULT_PROFILE);
+
+        // Profiles in use can't be removed, nor can the last binding
+        assert!(config.remove_profile(DEFAULT_PROFILE).is_err());
+        config.remove_binding(0).unwrap();
+        assert!(config.remove_binding(0).is_err());
+        assert!(config.remove_profile("Code").is_ok());
+        assert_eq!(config.profile("Code"), DictationProfile::default());
+    }
+
+    #[test]
+    fn test_hotkey_mode_serialization() {
+        let hotkey = HotkeyConfig {
+            mode: HotkeyMode::Hybrid,
+            ..Default::default()
+        };
+        let json = serde_json::to_value(&hotkey).unwrap();
+        assert_eq!(json["mode"], "hybrid");
+        let parsed: HotkeyConfig = serde_json::from_value(json).unwrap();
+        assert_eq!(parsed.mode, HotkeyMode::Hybrid);
+    }
+
+    #[test]
+    fn test_backend_values() {
+        let json = r#"{"backend":"http","http":{"api":"openai"}}"#;
+        let (config, _) = parse_config(json).unwrap();
+        assert_eq!(config.backend, BackendKind::Http);
+        assert_eq!(config.http.api, HttpApi::OpenAi);
+        assert_eq!(serde_json::to_value(HttpApi::WhisperCpp).unwrap(), "whispercpp");
+    }
+
+    #[test]
+    fn test_tiny_capture_buffer_is_raised() {
+        let (config, _) = parse_config(r#"{"audio":{"capture_buffer_ms":0}}"#).unwrap();
+        assert_eq!(config.audio.capture_buffer_ms, MIN_CAPTURE_BUFFER_MS);
+        let (config, _) = parse_config(r#"{"audio":{"capture_buffer_ms":5000}}"#).unwrap();
+        assert_eq!(config.audio.capture_buffer_ms, 5000);
     }
 }
@@ -0,0 +1,377 @@
+use crate::audio::TARGET_SAMPLE_RATE;
+use crate::config::{DspConfig, Normalization};
+use crate::vad::ms_to_samples;
+
+/// Attenuation of the noise gate when closed
+const GATE_FLOOR: f32 = 0.01;
+/// How fast the noise gate opens on speech
+const GATE_OPEN_MS: f32 = 1.0;
+/// How long the gate stays open after the level drops, so word endings aren't cut
+const GATE_HOLD_MS: u64 = 80;
+/// How fast the gate closes once released
+const GATE_CLOSE_MS: f32 = 20.0;
+/// Soft clipping leaves samples below this untouched
+const SOFT_CLIP_KNEE: f32 = 0.8;
+
+fn db_to_amplitude(db: f32) -> f32 {
+    10f32.powf(db / 20.0)
+}
+
+/// One-pole smoothing coefficient for a time constant of `ms` at 16kHz
+fn smoothing(ms: f32) -> f32 {
+    (-1.0 / (ms / 1000.0 * TARGET_SAMPLE_RATE as f32)).exp()
+}
+
+/// Second-order Butterworth high-pass; also removes DC offset
+struct HighPass {
+    b0: f64,
+    b1: f64,
+    b2: f64,
+    a1: f64,
+    a2: f64,
+    s1: f64,
+    s2: f64,
+}
+
+impl HighPass {
+    fn new(cutoff_hz: f32) -> Self {
+        let w0 = std::f64::consts::TAU * cutoff_hz as f64 / TARGET_SAMPLE_RATE as f64;
+        let alpha = w0.sin() / std::f64::consts::SQRT_2;
+        let cos = w0.cos();
+        let a0 = 1.0 + alpha;
+        Self {
+            b0: (1.0 + cos) / 2.0 / a0,
+            b1: -(1.0 + cos) / a0,
+            b2: (1.0 + cos) / 2.0 / a0,
+            a1: -2.0 * cos / a0,
+            a2: (1.0 - alpha) / a0,
+            s1: 0.0,
+            s2: 0.0,
+        }
+    }
+
+    fn process(&mut self, sample: f32) -> f32 {
+        let x = sample as f64;
+        let y = self.b0 * x + self.s1;
+        self.s1 = self.b1 * x - self.a1 * y + self.s2;
+        self.s2 = self.b2 * x - self.a2 * y;
+        y as f32
+    }
+}
+
+/// Attenuates audio that stays below a threshold, e.g. fan noise between words
+struct NoiseGate {
+    threshold: f32,
+    /// Samples since the input last reached the threshold
+    quiet_samples: usize,
+    hold_samples: usize,
+    gain: f32,
+    open_coef: f32,
+    close_coef: f32,
+}
+
+impl NoiseGate {
+    fn new(threshold_db: f32) -> Self {
+        Self {
+            threshold: db_to_amplitude(threshold_db),
+            quiet_samples: 0,
+            hold_samples: ms_to_samples(GATE_HOLD_MS),
+            gain: 1.0,
+            open_coef: smoothing(GATE_OPEN_MS),
+            close_coef: smoothing(GATE_CLOSE_MS),
+        }
+    }
+
+    fn process(&mut self, sample: f32) -> f32 {
+        if sample.abs() >= self.threshold {
+            self.quiet_samples = 0;
+        } else {
+            self.quiet_samples += 1;
+        }
+        let target = if self.quiet_samples < self.hold_samples {
+            1.0
+        } else {
+            GATE_FLOOR
+        };
+        let coef = if target > self.gain {
+            self.open_coef
+        } else {
+            self.close_coef
+        };
+        self.gain = target + (self.gain - target) * coef;
+        sample * self.gain
+    }
+}
+
+/// Scales audio towards a target level, measured over everything seen so far.
+///
+/// Each block is measured before it is scaled, so a complete recording processed
+/// in one call is normalized exactly and a streamed one never overshoots: the gain
+/// drops at once when the level rises, and only rises gradually across a block.
+struct Normalizer {
+    mode: Normalization,
+    target: f32,
+    max_gain: f32,
+    peak: f32,
+    sum_squares: f64,
+    count: usize,
+    /// Gain applied at the end of the previous block
+    gain: Option<f32>,
+}
+
+impl Normalizer {
+    fn new(config: &DspConfig) -> Self {
+        let target_db = match config.normalization {
+            Normalization::Loudness => config.loudness_target_db,
+            _ => config.peak_target_db,
+        };
+        Self {
+            mode: config.normalization,
+            target: db_to_amplitude(target_db),
+            max_gain: db_to_amplitude(config.max_gain_db),
+            peak: 0.0,
+            sum_squares: 0.0,
+            count: 0,
+            gain: None,
+        }
+    }
+
+    fn process(&mut self, samples: &mut [f32]) {
+        if self.mode == Normalization::Off || samples.is_empty() {
+            return;
+        }
+
+        for &sample in samples.iter() {
+            self.peak = self.peak.max(sample.abs());
+            self.sum_squares += (sample as f64) * (sample as f64);
+        }
+        self.count += samples.len();
+
+        let level = match self.mode {
+            Normalization::Loudness => (self.sum_squares / self.count as f64).sqrt() as f32,
+            _ => self.peak,
+        };
+        let gain = if level > 0.0 {
+            (self.target / level).min(self.max_gain)
+        } else {
+            self.max_gain
+        };
+
+        let start = self.gain.unwrap_or(gain).min(gain);
+        let step = (gain - start) / samples.len() as f32;
+        for (i, sample) in samples.iter_mut().enumerate() {
+            *sample *= start + step * (i + 1) as f32;
+        }
+        self.gain = Some(gain);
+    }
+}
+
+/// Leaves samples below the knee untouched and bends larger ones smoothly
+/// towards full scale, so nothing ever reaches it
+fn soft_clip(sample: f32) -> f32 {
+    let magnitude = sample.abs();
+    if magnitude <= SOFT_CLIP_KNEE {
+        return sample;
+    }
+    let headroom = 1.0 - SOFT_CLIP_KNEE;
+    let bent = SOFT_CLIP_KNEE + headroom * ((magnitude - SOFT_CLIP_KNEE) / headroom).tanh();
+    bent.copysign(sample)
+}
+
+/// Cleans up 16kHz recordings before transcription: high-pass, noise gate,
+/// normalization and soft clipping, each as enabled in [`DspConfig`]
+pub struct DspChain {
+    high_pass: Option<HighPass>,
+    noise_gate: Option<NoiseGate>,
+    normalizer: Normalizer,
+    soft_clip: bool,
+}
+
+impl DspChain {
+    pub fn new(config: &DspConfig) -> Self {
+        Self {
+            high_pass: config
+                .high_pass
+                .then(|| HighPass::new(config.high_pass_hz)),
+            noise_gate: config
+                .noise_gate
+                .then(|| NoiseGate::new(config.gate_threshold_db)),
+            normalizer: Normalizer::new(config),
+            soft_clip: config.soft_clip,
+        }
+    }
+
+    /// Process the next block of a recording
+    pub fn process(&mut self, samples: &[f32]) -> Vec<f32> {
+        let mut output = samples.to_vec();
+        if let Some(high_pass) = &mut self.high_pass {
+            output.iter_mut().for_each(|s| *s = high_pass.process(*s));
+        }
+        if let Some(noise_gate) = &mut self.noise_gate {
+            output.iter_mut().for_each(|s| *s = noise_gate.process(*s));
+        }
+        self.normalizer.process(&mut output);
+        if self.soft_clip {
+            output.iter_mut().for_each(|s| *s = soft_clip(*s));
+        }
+        output
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const RATE: f32 = TARGET_SAMPLE_RATE as f32;
+
+    fn sine(freq: f32, amplitude: f32, ms: u32) -> Vec<f32> {
+        (0..ms * TARGET_SAMPLE_RATE / 1000)
+            .map(|i| (i as f32 * freq / RATE * std::f32::consts::TAU).sin() * amplitude)
+            .collect()
+    }
+
+    fn peak(samples: &[f32]) -> f32 {
+        samples.iter().fold(0.0, |peak, s| peak.max(s.abs()))
+    }
+
+    fn rms(samples: &[f32]) -> f32 {
+        (samples.iter().map(|s| s * s).sum::<f32>() / samples.len() as f32).sqrt()
+    }
+
+    /// Chain with every stage off, to enable stages one at a time
+    fn bypass() -> DspConfig {
+        DspConfig {
+            high_pass: false,
+            noise_gate: false,
+            normalization: Normalization::Off,
+            soft_clip: false,
+            ..DspConfig::default()
+        }
+    }
+
+    #[test]
+    fn test_bypass_is_transparent() {
+        let input = sine(440.0, 0.3, 100);
+        assert_eq!(DspChain::new(&bypass()).process(&input), input);
+    }
+
+    #[test]
+    fn test_high_pass_removes_dc_offset() {
+        let input: Vec<f32> = sine(1000.0, 0.3, 1000).iter().map(|s| s + 0.4).collect();
+        let config = DspConfig {
+            high_pass: true,
+            ..bypass()
+        };
+        let output = DspChain::new(&config).process(&input);
+        // Once the filter has settled the offset is gone and the tone is intact
+        let settled = &output[8000..];
+        let mean = settled.iter().sum::<f32>() / settled.len() as f32;
+        assert!(mean.abs() < 0.001, "mean {}", mean);
+        assert!((peak(settled) - 0.3).abs() < 0.01, "peak {}", peak(settled));
+    }
+
+    #[test]
+    fn test_high_pass_attenuates_rumble() {
+        let config = DspConfig {
+            high_pass: true,
+            ..bypass()
+        };
+        let output = DspChain::new(&config).process(&sine(20.0, 0.5, 1000));
+        // Two octaves below the cutoff: about -24dB
+        assert!(peak(&output[8000..]) < 0.05, "peak {}", peak(&output[8000..]));
+    }
+
+    #[test]
+    fn test_peak_normalization_of_quiet_speaker() {
+        let config = DspConfig {
+            normalization: Normalization::Peak,
+            ..bypass()
+        };
+        let output = DspChain::new(&config).process(&sine(300.0, 0.1, 500));
+        assert!((peak(&output) - db_to_amplitude(-3.0)).abs() < 0.001);
+    }
+
+    #[test]
+    fn test_normalization_gain_is_capped() {
+        let config = DspConfig {
+            normalization: Normalization::Peak,
+            ..bypass()
+        };
+        // A near-silent input is raised by the maximum 20dB only
+        let output = DspChain::new(&config).process(&sine(300.0, 0.001, 500));
+        assert!((peak(&output) - 0.01).abs() < 0.0001, "peak {}", peak(&output));
+    }
+
+    #[test]
+    fn test_loudness_normalization() {
+        let config = DspConfig {
+            normalization: Normalization::Loudness,
+            ..bypass()
+        };
+        let output = DspChain::new(&config).process(&sine(300.0, 0.05, 500));
+        assert!((rms(&output) - db_to_amplitude(-20.0)).abs() < 0.001);
+    }
+
+    #[test]
+    fn test_streamed_normalization_never_overshoots() {
+        let config = DspConfig {
+            normalization: Normalization::Peak,
+            ..bypass()
+        };
+        let mut chain = DspChain::new(&config);
+        // The speaker gets louder halfway through
+        let input = [sine(300.0, 0.1, 500), sine(300.0, 0.4, 500)].concat();
+        let output: Vec<f32> = input.chunks(1600).flat_map(|block| chain.process(block)).collect();
+        let target = db_to_amplitude(-3.0);
+        assert!(peak(&output) <= target + 1e-4, "peak {}", peak(&output));
+        assert!((peak(&output[..8000]) - target).abs() < 0.001);
+        assert!((peak(&output[8000..]) - target).abs() < 0.001);
+    }
+
+    #[test]
+    fn test_streamed_loudness_gain_rises_gradually() {
+        let config = DspConfig {
+            normalization: Normalization::Loudness,
+            ..bypass()
+        };
+        let mut chain = DspChain::new(&config);
+        // The speaker gets quieter; the gain comes up without jumping
+        let input = [sine(300.0, 0.1, 500), sine(300.0, 0.02, 1000)].concat();
+        let output: Vec<f32> = input.chunks(1600).flat_map(|block| chain.process(block)).collect();
+        let max_step = output
+            .windows(2)
+            .map(|pair| (pair[1] - pair[0]).abs())
+            .fold(0.0, f32::max);
+        assert!(max_step < 0.02, "step {}", max_step);
+        assert!(rms(&output[20000..]) > rms(&output[8000..9600]));
+    }
+
+    #[test]
+    fn test_noise_gate_silences_noise_between_words() {
+        let config = DspConfig {
+            noise_gate: true,
+            ..bypass()
+        };
+        // A word, then fan noise at -60dB
+        let input = [sine(300.0, 0.3, 300), sine(3000.0, 0.001, 500)].concat();
+        let output = DspChain::new(&config).process(&input);
+        assert!((peak(&output[..4800]) - 0.3).abs() < 0.01);
+        // The noise is attenuated once the gate has released
+        assert!(peak(&output[8000..]) < 0.0001, "peak {}", peak(&output[8000..]));
+    }
+
+    #[test]
+    fn test_soft_clip() {
+        assert_eq!(soft_clip(0.5), 0.5);
+        assert_eq!(soft_clip(-SOFT_CLIP_KNEE), -SOFT_CLIP_KNEE);
+        // Continuous at the knee, monotonic and below full scale above it
+        assert!((soft_clip(SOFT_CLIP_KNEE + 1e-4) - (SOFT_CLIP_KNEE + 1e-4)).abs() < 1e-5);
+        let mut previous = SOFT_CLIP_KNEE;
+        for i in 1..=20 {
+            let clipped = soft_clip(SOFT_CLIP_KNEE + i as f32 * 0.05);
+            assert!(clipped > previous && clipped < 1.0, "{}", clipped);
+            previous = clipped;
+        }
+        assert_eq!(soft_clip(-3.0), -soft_clip(3.0));
+    }
+}
@@ -0,0 +1,402 @@
+//! Linux hotkey source: reads key events from every keyboard under /dev/input.
+//! Needs read access to the device nodes, usually through the `input` group.
+
+use std::collections::{HashMap, HashSet};
+use std::os::unix::fs::DirEntryExt;
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use evdev::{Device, EventType};
+
+use crate::hotkey;
+use crate::hotkey_machine::{KeyEvent, ModifierFlags};
+use crate::keys::Key;
+
+const INPUT_DIR: &str = "/dev/input";
+
+/// How often the input directory is checked for keyboards that were plugged in
+const RESCAN_INTERVAL: Duration = Duration::from_secs(2);
+
+/// What the device readers report
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum DeviceMessage {
+    /// An evdev key event: value 0 is a release, 1 a press and 2 an auto-repeat
+    Key { device: usize, code: u16, value: i32 },
+    /// The keyboard was unplugged
+    Removed(usize),
+}
+
+/// Turns key changes from any number of keyboards into the events the hotkey
+/// machine expects. Modifiers become flag changes, as on macOS.
+#[derive(Debug, Default)]
+struct KeyTranslator {
+    /// Keys held on each keyboard
+    held: HashMap<usize, HashSet<Key>>,
+}
+
+impl KeyTranslator {
+    fn handle(&mut self, message: DeviceMessage) -> Vec<KeyEvent> {
+        match message {
+            DeviceMessage::Key {
+                device,
+                code,
+                value,
+            } => self.key(device, code, value).into_iter().collect(),
+            DeviceMessage::Removed(device) => self.device_removed(device),
+        }
+    }
+
+    fn key(&mut self, device: usize, code: u16, value: i32) -> Option<KeyEvent> {
+        let key = Key::from_evdev_code(code)?;
+        let was_held = self.is_held(key);
+        let keys = self.held.entry(device).or_default();
+        match value {
+            0 => keys.remove(&key),
+            1 => keys.insert(key),
+            _ => false,
+        };
+
+        if key.is_modifier() {
+            // Modifiers don't repeat, and the same modifier on a second keyboard
+            // changes nothing
+            (self.is_held(key) != was_held).then(|| self.flags_changed(key))
+        } else if value == 0 {
+            Some(KeyEvent::Up(key))
+        } else {
+            // Repeats are forwarded like macOS key repeats
+            Some(KeyEvent::Down(key))
+        }
+    }
+
+    /// Release whatever an unplugged keyboard still held
+    fn device_removed(&mut self, device: usize) -> Vec<KeyEvent> {
+        let Some(keys) = self.held.remove(&device) else {
+            return Vec::new();
+        };
+        keys.into_iter()
+            .filter(|&key| !self.is_held(key))
+            .map(|key| {
+                if key.is_modifier() {
+                    self.flags_changed(key)
+                } else {
+                    KeyEvent::Up(key)
+                }
+            })
+            .collect()
+    }
+
+    fn is_held(&self, key: Key) -> bool {
+        self.held.values().any(|keys| keys.contains(&key))
+    }
+
+    fn flags_changed(&self, key: Key) -> KeyEvent {
+        KeyEvent::FlagsChanged {
+            key,
+            flags: ModifierFlags::from_held(self.held.values().flatten().copied()),
+        }
+    }
+}
+
+/// A device node; the inode tells a re-created node at the same path apart
+type Node = (PathBuf, u64);
+
+/// Opens keyboards as they appear in the input directory and reads each on its
+/// own thread
+struct DeviceWatcher {
+    dir: PathBuf,
+    sender: Sender<DeviceMessage>,
+    /// Keyboards being read; a reader removes its node when the device goes away
+    reading: Arc<Mutex<HashSet<Node>>>,
+    /// Devices that aren't keyboards
+    ignored: HashSet<Node>,
+    /// Nodes that failed to open, so each is only reported once
+    unreadable: HashSet<PathBuf>,
+    next_id: usize,
+}
+
+impl DeviceWatcher {
+    fn new(dir: &Path, sender: Sender<DeviceMessage>) -> Self {
+        Self {
+            dir: dir.to_path_buf(),
+            sender,
+            reading: Arc::new(Mutex::new(HashSet::new())),
+            ignored: HashSet::new(),
+            unreadable: HashSet::new(),
+            next_id: 0,
+        }
+    }
+
+    /// Start reading keyboards that appeared since the last scan. Returns how many
+    /// keyboards are being read.
+    fn scan(&mut self) -> Result<usize, String> {
+        let entries = std::fs::read_dir(&self.dir)
+            .map_err(|e| format!("Failed to read {}: {}", self.dir.display(), e))?;
+
+        let mut present = HashSet::new();
+        for entry in entries.flatten() {
+            let path = entry.path();
+            let is_event_node = path
+                .file_name()
+                .and_then(|name| name.to_str())
+                .is_some_and(|name| name.starts_with("event"));
+            if !is_event_node {
+                continue;
+            }
+            let node = (path, entry.ino());
+            present.insert(node.clone());
+
+            let reading = self.reading.lock().is_ok_and(|reading| reading.contains(&node));
+            if reading || self.ignored.contains(&node) {
+                continue;
+            }
+
+            let device = match Device::open(&node.0) {
+                Ok(device) => device,
+                Err(e) => {
+                    // Retried on the next scan - permissions are often applied
+                    // just after the node appears
+                    if self.unreadable.insert(node.0.clone()) {
+                        eprintln!("[HOTKEY] Can't read {}: {}", node.0.display(), e);
+                    }
+                    continue;
+                }
+            };
+            self.unreadable.remove(&node.0);
+
+            if is_keyboard(&device) {
+                self.spawn_reader(node, device);
+            } else {
+                self.ignored.insert(node);
+            }
+        }
+
+        self.ignored.retain(|node| present.contains(node));
+        Ok(self.reading.lock().map_or(0, |reading| reading.len()))
+    }
+
+    fn spawn_reader(&mut self, node: Node, device: Device) {
+        let id = self.next_id;
+        self.next_id += 1;
+        println!(
+            "[HOTKEY] Reading keyboard {} ({})",
+            node.0.display(),
+            device.name().unwrap_or("unnamed")
+        );
+
+        if let Ok(mut reading) = self.reading.lock() {
+            reading.insert(node.clone());
+        }
+        let reading = self.reading.clone();
+        let sender = self.sender.clone();
+        std::thread::spawn(move || {
+            read_device(id, device, &sender);
+            if let Ok(mut reading) = reading.lock() {
+                reading.remove(&node);
+            }
+            let _ = sender.send(DeviceMessage::Removed(id));
+        });
+    }
+
+    /// Pick up keyboards plugged in later, for as long as the app runs
+    fn run(mut self) {
+        loop {
+            std::thread::sleep(RESCAN_INTERVAL);
+            if let Err(e) = self.scan() {
+                eprintln!("[HOTKEY] {}", e);
+            }
+        }
+    }
+}
+
+/// Anything with keys a hotkey can use; mice and power buttons are left alone
+fn is_keyboard(device: &Device) -> bool {
+    device
+        .supported_keys()
+        .is_some_and(|keys| keys.iter().any(|key| Key::from_evdev_code(key.code()).is_some()))
+}
+
+/// Forward key events until the device goes away
+fn read_device(id: usize, mut device: Device, sender: &Sender<DeviceMessage>) {
+    loop {
+        let events = match device.fetch_events() {
+            Ok(events) => events,
+            Err(e) => {
+                println!("[HOTKEY] Keyboard {} gone: {}", id, e);
+                return;
+            }
+        };
+        for event in events {
+            if event.event_type() != EventType::KEY {
+                continue;
+            }
+            let message = DeviceMessage::Key {
+                device: id,
+                code: event.code(),
+                value: event.value(),
+            };
+            if sender.send(message).is_err() {
+                return;
+            }
+        }
+    }
+}
+
+fn dispatch(app_handle: tauri::AppHandle, receiver: Receiver<DeviceMessage>) {
+    let mut translator = KeyTranslator::default();
+    for message in receiver {
+        for event in translator.handle(message) {
+            hotkey::handle_key_event(&app_handle, event);
+        }
+    }
+}
+
+/// Start feeding key events from all keyboards to the hotkey. Fails if no
+/// keyboard can be read yet; keyboards that become readable later are still
+/// picked up.
+pub fn start(app_handle: tauri::AppHandle) -> Result<(), String> {
+    let (sender, receiver) = mpsc::channel();
+    let mut watcher = DeviceWatcher::new(Path::new(INPUT_DIR), sender);
+    let keyboards = watcher.scan();
+
+    std::thread::spawn(move || watcher.run());
+    std::thread::spawn(move || dispatch(app_handle, receiver));
+
+    match keyboards? {
+        0 => Err(format!(
+            "No readable keyboard in {}. Add your user to the 'input' group and log in again.",
+            INPUT_DIR
+        )),
+        count => {
+            println!("[HOTKEY] Listening to {} keyboard(s)", count);
+            Ok(())
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::{HotkeyConfig, HotkeyMode, Modifier};
+    use crate::hotkey_machine::{HotkeyAction, HotkeyMachine};
+    use std::time::Instant;
+
+    const KEY_A: u16 = 30;
+    const KEY_LEFTMETA: u16 = 125;
+    const KEY_RIGHTMETA: u16 = 126;
+
+    fn key(device: usize, code: u16, value: i32) -> DeviceMessage {
+        DeviceMessage::Key {
+            device,
+            code,
+            value,
+        }
+    }
+
+    fn command_flags() -> ModifierFlags {
+        ModifierFlags {
+            command: true,
+            ..ModifierFlags::default()
+        }
+    }
+
+    #[test]
+    fn test_modifiers_become_flag_changes() {
+        let mut translator = KeyTranslator::default();
+        assert_eq!(
+            translator.handle(key(0, KEY_RIGHTMETA, 1)),
+            vec![KeyEvent::FlagsChanged {
+                key: Key::MetaRight,
+                flags: command_flags()
+            }]
+        );
+        // Modifier repeats change nothing
+        assert!(translator.handle(key(0, KEY_RIGHTMETA, 2)).is_empty());
+        assert_eq!(translator.handle(key(0, KEY_A, 1)), vec![KeyEvent::Down(Key::KeyA)]);
+        assert_eq!(translator.handle(key(0, KEY_A, 2)), vec![KeyEvent::Down(Key::KeyA)]);
+        assert_eq!(translator.handle(key(0, KEY_A, 0)), vec![KeyEvent::Up(Key::KeyA)]);
+        assert_eq!(
+            translator.handle(key(0, KEY_RIGHTMETA, 0)),
+            vec![KeyEvent::FlagsChanged {
+                key: Key::MetaRight,
+                flags: ModifierFlags::default()
+            }]
+        );
+    }
+
+    #[test]
+    fn test_unplugged_keyboard_releases_its_keys() {
+        let mut translator = KeyTranslator::default();
+        translator.handle(key(0, KEY_LEFTMETA, 1));
+        translator.handle(key(1, KEY_LEFTMETA, 1));
+        translator.handle(key(1, KEY_A, 1));
+
+        // Left Super is still held on the other keyboard
+        assert_eq!(
+            translator.handle(DeviceMessage::Removed(1)),
+            vec![KeyEvent::Up(Key::KeyA)]
+        );
+        assert_eq!(
+            translator.handle(key(0, KEY_LEFTMETA, 0)),
+            vec![KeyEvent::FlagsChanged {
+                key: Key::MetaLeft,
+                flags: ModifierFlags::default()
+            }]
+        );
+    }
+
+    /// Plugs in a uinput virtual keyboard and dictates with Right Super
+    #[test]
+    #[ignore = "needs /dev/input and write access to /dev/uinput"]
+    fn test_virtual_keyboard() {
+        use evdev::uinput::VirtualDevice;
+        use evdev::{AttributeSet, InputEvent, KeyCode};
+
+        let (sender, receiver) = mpsc::channel();
+        let mut watcher = DeviceWatcher::new(Path::new(INPUT_DIR), sender);
+        watcher.scan().unwrap();
+
+        // Plugged in after the first scan, like a hotplugged keyboard
+        let mut keys = AttributeSet::<KeyCode>::new();
+        keys.insert(KeyCode::KEY_RIGHTMETA);
+        keys.insert(KeyCode::KEY_A);
+        let mut keyboard = VirtualDevice::builder()
+            .unwrap()
+            .name("saytype test keyboard")
+            .with_keys(&keys)
+            .unwrap()
+            .build()
+            .unwrap();
+        std::thread::sleep(Duration::from_millis(500));
+        watcher.scan().unwrap();
+
+        for (code, value) in [(KEY_RIGHTMETA, 1), (KEY_RIGHTMETA, 0)] {
+            keyboard
+                .emit(&[InputEvent::new(EventType::KEY.0, code, value)])
+                .unwrap();
+        }
+        drop(keyboard);
+
+        let config = HotkeyConfig {
+            modifiers: vec![Modifier::Command],
+            key: None,
+            modifier_keys: vec![Key::MetaRight],
+            mode: HotkeyMode::Hold,
+            ..HotkeyConfig::default()
+        };
+        let mut machine = HotkeyMachine::new(&[config]);
+        let mut translator = KeyTranslator::default();
+        let mut actions = Vec::new();
+        let mut removed = false;
+        while let Ok(message) = receiver.recv_timeout(Duration::from_secs(2)) {
+            removed |= matches!(message, DeviceMessage::Removed(_));
+            for event in translator.handle(message) {
+                actions.extend(machine.handle(event, Instant::now()));
+            }
+        }
+
+        assert_eq!(actions, vec![HotkeyAction::Pressed(0), HotkeyAction::Released]);
+        assert!(removed, "unplugging wasn't noticed");
+    }
+}
@@ -0,0 +1,147 @@
+//! macOS hotkey source: a CGEventTap that feeds key events to the hotkey machine
+
+use core_foundation::base::TCFType;
+use core_foundation::runloop::kCFRunLoopCommonModes;
+use core_foundation_sys::runloop::{CFRunLoopAddSource, CFRunLoopGetMain};
+use core_graphics::event::{
+    CGEvent, CGEventFlags, CGEventTap, CGEventTapLocation, CGEventTapOptions, CGEventTapPlacement,
+    CGEventType, CallbackResult, EventField,
+};
+use std::sync::OnceLock;
+
+use crate::hotkey;
+use crate::hotkey_machine::{KeyEvent, ModifierFlags};
+use crate::keys::Key;
+
+// Store the mach port pointer for re-enabling the tap.
+// Safety: The raw pointer is from CFMachPort which is thread-safe. We only read it
+// after initialization (via OnceLock) and only use it to call CGEventTapEnable.
+static TAP_MACH_PORT: OnceLock<usize> = OnceLock::new();
+
+// FFI declaration for CGEventTapEnable (not exposed by core-graphics crate)
+#[link(name = "CoreGraphics", kind = "framework")]
+extern "C" {
+    fn CGEventTapEnable(tap: *mut std::ffi::c_void, enable: bool);
+}
+
+/// Re-enable the event tap if it was disabled by macOS
+fn reenable_tap() {
+    if let Some(&port_addr) = TAP_MACH_PORT.get() {
+        println!("[HOTKEY] Re-enabling event tap...");
+        unsafe {
+            CGEventTapEnable(port_addr as *mut std::ffi::c_void, true);
+        }
+    }
+}
+
+/// Set up the event tap and attach it to the main run loop.
+/// This function returns immediately - it does NOT block.
+/// The event tap will receive events as part of the main run loop.
+pub fn setup_event_tap(app_handle: tauri::AppHandle) -> Result<(), String> {
+    println!("[HOTKEY] Creating CGEventTap...");
+    let ax_trusted = crate::text_insertion::check_accessibility_permission();
+    println!("[HOTKEY] AXIsProcessTrusted: {}", ax_trusted);
+
+    // Note: TapDisabledByTimeout and TapDisabledByUserInput are NOT included in the mask
+    // because they have special sentinel values (0xFFFFFFFE, 0xFFFFFFFF) that overflow
+    // when the core-graphics crate tries to create a bitmask. These events are automatically
+    // delivered to the callback when the tap is disabled, regardless of the mask.
+    let tap = CGEventTap::new(
+        CGEventTapLocation::HID,
+        CGEventTapPlacement::HeadInsertEventTap,
+        CGEventTapOptions::ListenOnly,
+        vec![
+            CGEventType::KeyDown,
+            CGEventType::KeyUp,
+            CGEventType::FlagsChanged,
+        ],
+        move |_proxy, event_type, event| {
+            // Check if tap was disabled and re-enable it
+            if matches!(
+                event_type,
+                CGEventType::TapDisabledByTimeout | CGEventType::TapDisabledByUserInput
+            ) {
+                println!("[HOTKEY] Event tap was disabled by macOS, re-enabling...");
+                reenable_tap();
+                return CallbackResult::Keep;
+            }
+
+            handle_event(&app_handle, event_type, event);
+            CallbackResult::Keep
+        },
+    )
+    .map_err(|e| {
+        let msg = format!(
+            "Failed to create event tap. AXIsProcessTrusted={}, Error: {:?}",
+            crate::text_insertion::check_accessibility_permission(),
+            e
+        );
+        eprintln!("[HOTKEY] {}", msg);
+        msg
+    })?;
+
+    // Store mach port pointer for re-enabling later
+    let port_addr = tap.mach_port().as_concrete_TypeRef() as usize;
+    let _ = TAP_MACH_PORT.set(port_addr);
+
+    tap.enable();
+
+    let run_loop_source = tap
+        .mach_port()
+        .create_runloop_source(0)
+        .map_err(|_| "Failed to create run loop source")?;
+
+    // KEY FIX: Add to MAIN run loop, not current thread's run loop
+    // This ensures events are delivered even when the app is backgrounded,
+    // because Tauri keeps the main run loop alive.
+    unsafe {
+        let main_run_loop = CFRunLoopGetMain();
+        CFRunLoopAddSource(
+            main_run_loop,
+            run_loop_source.as_concrete_TypeRef(),
+            kCFRunLoopCommonModes,
+        );
+    }
+
+    // Keep tap and run loop source alive for app lifetime (intentional leak)
+    // These must not be dropped or the event tap will stop working
+    std::mem::forget(tap);
+    std::mem::forget(run_loop_source);
+
+    // DO NOT call CFRunLoop::run_current() - main run loop is already running via Tauri
+
+    println!("[HOTKEY] Event tap attached to main run loop");
+    Ok(())
+}
+
+fn handle_event(app_handle: &tauri::AppHandle, event_type: CGEventType, event: &CGEvent) {
+    let keycode = event.get_integer_value_field(EventField::KEYBOARD_EVENT_KEYCODE);
+    // Keys without a name can't be part of a hotkey
+    let Some(key) = Key::from_mac_keycode(keycode) else {
+        return;
+    };
+
+    let key_event = match event_type {
+        CGEventType::FlagsChanged => KeyEvent::FlagsChanged {
+            key,
+            flags: modifier_flags(event.get_flags()),
+        },
+        CGEventType::KeyDown => KeyEvent::Down(key),
+        CGEventType::KeyUp => KeyEvent::Up(key),
+        _ => return,
+    };
+
+    hotkey::handle_key_event(app_handle, key_event);
+}
+
+/// The modifier families the event reports as down
+fn modifier_flags(flags: CGEventFlags) -> ModifierFlags {
+    ModifierFlags {
+        command: flags.contains(CGEventFlags::CGEventFlagCommand),
+        shift: flags.contains(CGEventFlags::CGEventFlagShift),
+        option: flags.contains(CGEventFlags::CGEventFlagAlternate),
+        control: flags.contains(CGEventFlags::CGEventFlagControl),
+        caps_lock: flags.contains(CGEventFlags::CGEventFlagAlphaShift),
+        function: flags.contains(CGEventFlags::CGEventFlagSecondaryFn),
+    }
+}
@@ -1,230 +1,176 @@
-use core_foundation::base::TCFType;
-use core_foundation::runloop::kCFRunLoopCommonModes;
-use core_foundation_sys::runloop::{CFRunLoopAddSource, CFRunLoopGetMain};
-use core_graphics::event::{
-    CGEvent, CGEventFlags, CGEventTap, CGEventTapLocation, CGEventTapOptions, CGEventTapPlacement,
-    CGEventType, CallbackResult, EventField,
-};
-use std::collections::HashSet;
-use std::sync::atomic::{AtomicBool, Ordering};
-use std::sync::{Mutex, OnceLock};
-use tauri::{Emitter, Manager};
+//! What the hotkey does: the platform's key event source (`event_tap` on macOS,
+//! `evdev_hotkey` on Linux) feeds events in, and recording starts, stops or is
+//! cancelled accordingly.
 
-use crate::{audio, config, sidecar, text_insertion, AppState};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use tauri::{Emitter, Manager};
 
-// Track whether the hotkey combo is currently activated
-static HOTKEY_ACTIVE: AtomicBool = AtomicBool::new(false);
+use crate::config::{DictationProfile, HotkeyBinding, InsertionMethod};
+use crate::hotkey_machine::{HotkeyAction, HotkeyMachine, KeyEvent};
+use crate::keys::Key;
+use crate::pipeline::{self, DictationOutcome, TranscriptionCanceller};
+use crate::segment::Segmenter;
+use crate::{audio, text_insertion, AppState};
+
+/// How long before the maximum recording length the warning sound plays
+const LIMIT_WARNING_LEAD: Duration = Duration::from_secs(10);
+
+// Incremented for every hotkey press, so a length watchdog only stops its own recording
+// and a transcription only clears its own canceller
+static RECORDING_GENERATION: AtomicU64 = AtomicU64::new(0);
+
+/// What the current recording was started with
+struct Dictation {
+    /// `RECORDING_GENERATION` of the recording
+    generation: u64,
+    /// Keys of the binding, cleared before pasting
+    modifier_keys: Vec<Key>,
+    profile: DictationProfile,
+}
 
-// Track currently held keys
+// Turns the key events into recording actions
 lazy_static::lazy_static! {
-    static ref HELD_KEYS: Mutex<HashSet<i64>> = Mutex::new(HashSet::new());
+    static ref MACHINE: Mutex<HotkeyMachine> = Mutex::new(HotkeyMachine::new(&[]));
+    /// The bindings the machine detects, in the same order
+    static ref BINDINGS: Mutex<Vec<HotkeyBinding>> = Mutex::new(Vec::new());
+    static ref DICTATION: Mutex<Option<Dictation>> = Mutex::new(None);
 }
 
-/// Clear the held keys set (called when hotkey config changes)
-pub fn clear_held_keys() {
-    if let Ok(mut held) = HELD_KEYS.lock() {
-        held.clear();
-    }
-    HOTKEY_ACTIVE.store(false, Ordering::SeqCst);
+/// Use new hotkey bindings, forgetting the held keys
+pub fn set_hotkey_bindings(bindings: &[HotkeyBinding]) {
+    let (Ok(mut machine), Ok(mut current)) = (MACHINE.lock(), BINDINGS.lock()) else {
+        return;
+    };
+    let hotkeys: Vec<_> = bindings.iter().map(|binding| binding.hotkey.clone()).collect();
+    machine.set_config(&hotkeys);
+    *current = bindings.to_vec();
 }
 
-// Store the mach port pointer for re-enabling the tap.
-// Safety: The raw pointer is from CFMachPort which is thread-safe. We only read it
-// after initialization (via OnceLock) and only use it to call CGEventTapEnable.
-static TAP_MACH_PORT: OnceLock<usize> = OnceLock::new();
+/// Feed a key event from the platform's event source
+pub fn handle_key_event(app_handle: &tauri::AppHandle, event: KeyEvent) {
+    let (actions, deadline) = match MACHINE.lock() {
+        Ok(mut machine) => {
+            let was_latched = machine.is_latched();
+            let deadline = machine.deadline();
+            let actions = machine.handle(event, Instant::now());
+            if machine.is_latched() && !was_latched {
+                println!("[DEBUG] Recording latched on");
+            }
+            // Only a new deadline needs a timer
+            (actions, machine.deadline().filter(|&new| Some(new) != deadline))
+        }
+        Err(_) => return,
+    };
 
-// FFI declaration for CGEventTapEnable (not exposed by core-graphics crate)
-#[link(name = "CoreGraphics", kind = "framework")]
-extern "C" {
-    fn CGEventTapEnable(tap: *mut std::ffi::c_void, enable: bool);
+    dispatch(app_handle, actions);
+    if let Some(deadline) = deadline {
+        wake_at(app_handle, deadline);
+    }
 }
 
-/// Re-enable the event tap if it was disabled by macOS
-fn reenable_tap() {
-    if let Some(&port_addr) = TAP_MACH_PORT.get() {
-        println!("[HOTKEY] Re-enabling event tap...");
-        unsafe {
-            CGEventTapEnable(port_addr as *mut std::ffi::c_void, true);
-        }
-    }
+/// Tick the machine at `deadline`, e.g. to start recording once the hotkey was
+/// held for the minimum hold time
+fn wake_at(app_handle: &tauri::AppHandle, deadline: Instant) {
+    let handle = app_handle.clone();
+    tauri::async_runtime::spawn(async move {
+        tokio::time::sleep_until(deadline.into()).await;
+        let actions = match MACHINE.lock() {
+            Ok(mut machine) => machine.tick(Instant::now()),
+            Err(_) => return,
+        };
+        dispatch(&handle, actions);
+    });
 }
 
-/// Set up the event tap and attach it to the main run loop.
-/// This function returns immediately - it does NOT block.
-/// The event tap will receive events as part of the main run loop.
-pub fn setup_event_tap(app_handle: tauri::AppHandle) -> Result<(), String> {
-    println!("[HOTKEY] Creating CGEventTap...");
-    let ax_trusted = crate::text_insertion::check_accessibility_permission();
-    println!("[HOTKEY] AXIsProcessTrusted: {}", ax_trusted);
-
-    // Note: TapDisabledByTimeout and TapDisabledByUserInput are NOT included in the mask
-    // because they have special sentinel values (0xFFFFFFFE, 0xFFFFFFFF) that overflow
-    // when the core-graphics crate tries to create a bitmask. These events are automatically
-    // delivered to the callback when the tap is disabled, regardless of the mask.
-    let tap = CGEventTap::new(
-        CGEventTapLocation::HID,
-        CGEventTapPlacement::HeadInsertEventTap,
-        CGEventTapOptions::ListenOnly,
-        vec![
-            CGEventType::KeyDown,
-            CGEventType::KeyUp,
-            CGEventType::FlagsChanged,
-        ],
-        move |_proxy, event_type, event| {
-            // Check if tap was disabled and re-enable it
-            if matches!(
-                event_type,
-                CGEventType::TapDisabledByTimeout | CGEventType::TapDisabledByUserInput
-            ) {
-                println!("[HOTKEY] Event tap was disabled by macOS, re-enabling...");
-                reenable_tap();
-                return CallbackResult::Keep;
+fn dispatch(app_handle: &tauri::AppHandle, actions: Vec<HotkeyAction>) {
+    for action in actions {
+        match action {
+            HotkeyAction::Pressed(index) => {
+                let binding = BINDINGS.lock().ok().and_then(|b| b.get(index).cloned());
+                if let Some(binding) = binding {
+                    on_hotkey_pressed(app_handle, binding);
+                }
             }
-
-            handle_event(&app_handle, event_type, event);
-            CallbackResult::Keep
-        },
-    )
-    .map_err(|e| {
-        let msg = format!(
-            "Failed to create event tap. AXIsProcessTrusted={}, Error: {:?}",
-            crate::text_insertion::check_accessibility_permission(),
-            e
-        );
-        eprintln!("[HOTKEY] {}", msg);
-        msg
-    })?;
-
-    // Store mach port pointer for re-enabling later
-    let port_addr = tap.mach_port().as_concrete_TypeRef() as usize;
-    let _ = TAP_MACH_PORT.set(port_addr);
-
-    tap.enable();
-
-    let run_loop_source = tap
-        .mach_port()
-        .create_runloop_source(0)
-        .map_err(|_| "Failed to create run loop source")?;
-
-    // KEY FIX: Add to MAIN run loop, not current thread's run loop
-    // This ensures events are delivered even when the app is backgrounded,
-    // because Tauri keeps the main run loop alive.
-    unsafe {
-        let main_run_loop = CFRunLoopGetMain();
-        CFRunLoopAddSource(
-            main_run_loop,
-            run_loop_source.as_concrete_TypeRef(),
-            kCFRunLoopCommonModes,
-        );
+            HotkeyAction::Released => on_hotkey_released(app_handle),
+            HotkeyAction::Cancelled => on_cancel_pressed(app_handle),
+        }
     }
-
-    // Keep tap and run loop source alive for app lifetime (intentional leak)
-    // These must not be dropped or the event tap will stop working
-    std::mem::forget(tap);
-    std::mem::forget(run_loop_source);
-
-    // DO NOT call CFRunLoop::run_current() - main run loop is already running via Tauri
-
-    println!("[HOTKEY] Event tap attached to main run loop");
-    Ok(())
 }
 
-fn handle_event(app_handle: &tauri::AppHandle, event_type: CGEventType, event: &CGEvent) {
-    let keycode = event.get_integer_value_field(EventField::KEYBOARD_EVENT_KEYCODE);
-    let flags = event.get_flags();
-
-    // Get current hotkey configuration from state
-    let required_keycodes = match app_handle.try_state::<AppState>() {
-        Some(state) => match state.current_hotkey.lock() {
-            Ok(hotkey) => hotkey.required_keycodes(),
-            Err(_) => return,
-        },
-        None => return,
-    };
+/// The cancel key discards the recording while the hotkey is held or latched,
+/// or the transcription that is still running after it was released
+fn on_cancel_pressed(app_handle: &tauri::AppHandle) {
+    let handle = app_handle.clone();
+    tauri::async_runtime::spawn(async move {
+        let Some(state) = handle.try_state::<AppState>() else {
+            return;
+        };
 
-    match event_type {
-        CGEventType::FlagsChanged => {
-            // For modifier keys, we need to check if they're pressed or released
-            // by checking the event flags
-            let is_pressed = is_modifier_pressed(keycode, flags);
+        let canceller = state
+            .transcription_canceller
+            .lock()
+            .ok()
+            .and_then(|mut canceller| canceller.take());
+        let Some((_, canceller)) = canceller else {
+            // Nothing in flight, the cancel key was just a normal key press
+            return;
+        };
 
-            update_held_key(keycode, is_pressed);
-        }
-        CGEventType::KeyDown => {
-            // Non-modifier key pressed
-            if !config::is_modifier_keycode(keycode) {
-                update_held_key(keycode, true);
-            }
+        println!("[DEBUG] Cancelling dictation");
+        canceller.cancel();
+        if let Ok(mut slot) = state.pending_transcription.lock() {
+            slot.take();
         }
-        CGEventType::KeyUp => {
-            // Non-modifier key released
-            if !config::is_modifier_keycode(keycode) {
-                update_held_key(keycode, false);
+
+        {
+            let mut is_recording = state.is_recording.lock().await;
+            if *is_recording {
+                *is_recording = false;
+                if let Err(e) = audio::cancel_recording() {
+                    eprintln!("[DEBUG] Failed to cancel recording: {}", e);
+                }
             }
         }
-        _ => {}
-    }
-
-    // Check if hotkey combo is now active
-    let all_held = match HELD_KEYS.lock() {
-        Ok(held) => required_keycodes.iter().all(|kc| held.contains(kc)),
-        Err(_) => false,
-    };
 
-    let was_active = HOTKEY_ACTIVE.load(Ordering::SeqCst);
-
-    if all_held && !was_active {
-        HOTKEY_ACTIVE.store(true, Ordering::SeqCst);
-        on_hotkey_pressed(app_handle);
-    } else if !all_held && was_active {
-        HOTKEY_ACTIVE.store(false, Ordering::SeqCst);
-        on_hotkey_released(app_handle);
-    }
-}
-
-/// Check if a modifier key is currently pressed based on event flags
-fn is_modifier_pressed(keycode: i64, flags: CGEventFlags) -> bool {
-    match keycode {
-        // Command keys (54 = right, 55 = left)
-        54 | 55 => flags.contains(CGEventFlags::CGEventFlagCommand),
-        // Shift keys (56 = left, 60 = right)
-        56 | 60 => flags.contains(CGEventFlags::CGEventFlagShift),
-        // Option/Alt keys (58 = left, 61 = right)
-        58 | 61 => flags.contains(CGEventFlags::CGEventFlagAlternate),
-        // Control keys (59 = left, 62 = right)
-        59 | 62 => flags.contains(CGEventFlags::CGEventFlagControl),
-        // Caps Lock (57)
-        57 => flags.contains(CGEventFlags::CGEventFlagAlphaShift),
-        // Function key (63)
-        63 => flags.contains(CGEventFlags::CGEventFlagSecondaryFn),
-        _ => false,
-    }
+        audio::play_cancel_sound();
+        crate::tray::set_recording_state(&handle, false);
+        let _ = handle.emit("recording-cancelled", ());
+    });
 }
 
-/// Update the held keys set
-fn update_held_key(keycode: i64, pressed: bool) {
-    if let Ok(mut held) = HELD_KEYS.lock() {
-        if pressed {
-            held.insert(keycode);
-        } else {
-            held.remove(&keycode);
-        }
+fn on_hotkey_pressed(app_handle: &tauri::AppHandle, binding: HotkeyBinding) {
+    println!(
+        "[DEBUG] {} pressed - starting recording with profile '{}'",
+        binding.hotkey.label, binding.profile
+    );
+    let app_config = app_handle
+        .try_state::<AppState>()
+        .and_then(|state| state.config.lock().ok().map(|config| config.clone()))
+        .unwrap_or_default();
+    let generation = RECORDING_GENERATION.fetch_add(1, Ordering::SeqCst) + 1;
+    let profile = app_config.profile(&binding.profile);
+    let language = profile.language.clone();
+    // Stored before anything is awaited, so the release always finds it
+    if let Ok(mut dictation) = DICTATION.lock() {
+        *dictation = Some(Dictation {
+            generation,
+            modifier_keys: binding.hotkey.modifier_keys,
+            profile,
+        });
     }
-}
 
-fn on_hotkey_pressed(app_handle: &tauri::AppHandle) {
-    println!("[DEBUG] Hotkey pressed - starting recording");
     let handle = app_handle.clone();
     tauri::async_runtime::spawn(async move {
-        // Check if sidecar is ready before allowing recording
+        // Check if the transcription backend is ready before allowing recording
         if let Some(state) = handle.try_state::<AppState>() {
-            let sidecar_ready = state.sidecar_ready.lock().await;
-            if !*sidecar_ready {
-                println!("[DEBUG] Hotkey pressed but sidecar not ready");
+            if !state.backend.is_ready().await {
+                println!("[DEBUG] Hotkey pressed but {} backend not ready", state.backend.name());
                 audio::play_busy_sound();
+                abandon_press(generation);
                 return;
             }
-            drop(sidecar_ready); // Release lock before acquiring is_recording lock
 
             let mut is_recording = state.is_recording.lock().await;
             *is_recording = true;
@@ -232,31 +178,157 @@ fn on_hotkey_pressed(app_handle: &tauri::AppHandle) {
 
         audio::play_start_sound();
 
-        if let Err(e) = audio::start_recording() {
-            eprintln!("[DEBUG] Failed to start recording: {}", e);
-            return;
+        let segmenter = Segmenter::from_config(&app_config);
+        let audio_config = app_config.audio;
+
+        // Stream audio to the backend while recording
+        let sink = match handle.try_state::<AppState>() {
+            Some(state) => {
+                let partial_handle = handle.clone();
+                let (sink, pending) = pipeline::begin_transcription(
+                    state.backend.clone(),
+                    segmenter,
+                    language,
+                    move |text| {
+                        let _ = partial_handle.emit("transcription-partial", text);
+                    },
+                );
+                if let Ok(mut canceller) = state.transcription_canceller.lock() {
+                    *canceller = Some((generation, pending.canceller()));
+                }
+                if let Ok(mut slot) = state.pending_transcription.lock() {
+                    *slot = Some(pending);
+                }
+                Some(sink)
+            }
+            None => None,
+        };
+
+        let levels = audio::level_emitter(handle.clone());
+        let switch_handle = handle.clone();
+        let switches: audio::DeviceSwitchCallback = Box::new(move |fallback| {
+            let _ = switch_handle.emit("device-fallback", fallback);
+        });
+        match audio::start_recording(
+            sink,
+            Some(levels),
+            Some(switches),
+            &audio_config,
+            app_config.vad,
+            &app_config.dsp,
+        ) {
+            Ok(Some(fallback)) => {
+                let _ = handle.emit("device-fallback", fallback);
+            }
+            Ok(None) => {}
+            Err(e) => {
+                eprintln!("[DEBUG] Failed to start recording: {}", e);
+                abandon_press(generation);
+                // Nothing will be recorded - abandon the transcription
+                if let Some(state) = handle.try_state::<AppState>() {
+                    if let Some(canceller) = take_canceller(&state, generation) {
+                        canceller.cancel();
+                    }
+                    if let Ok(mut slot) = state.pending_transcription.lock() {
+                        slot.take();
+                    }
+                }
+                return;
+            }
         }
 
         println!("[DEBUG] Recording started successfully");
         crate::tray::set_recording_state(&handle, true);
         let _ = handle.emit("recording-started", ());
+
+        if audio_config.max_recording_secs > 0 {
+            let limit = Duration::from_secs(audio_config.max_recording_secs);
+            watch_recording_length(handle, generation, limit).await;
+        }
     });
 }
 
+/// Whether the recording numbered `generation` is still running
+async fn is_recording(handle: &tauri::AppHandle, generation: u64) -> bool {
+    let Some(state) = handle.try_state::<AppState>() else {
+        return false;
+    };
+    let is_recording = *state.is_recording.lock().await;
+    is_recording && RECORDING_GENERATION.load(Ordering::SeqCst) == generation
+}
+
+/// Warn shortly before the recording reaches `limit`, then stop it. Also covers a
+/// hotkey stuck down because its release event was missed.
+async fn watch_recording_length(handle: tauri::AppHandle, generation: u64, limit: Duration) {
+    let warn_after = limit.saturating_sub(LIMIT_WARNING_LEAD);
+    tokio::time::sleep(warn_after).await;
+    if !is_recording(&handle, generation).await {
+        return;
+    }
+    if !warn_after.is_zero() {
+        audio::play_limit_warning_sound();
+    }
+
+    tokio::time::sleep(limit - warn_after).await;
+    if !is_recording(&handle, generation).await {
+        return;
+    }
+
+    println!("[DEBUG] Recording reached the {:?} limit, stopping", limit);
+    // Forget the held keys so a stuck hotkey works again, and ignore the release
+    // of a hotkey that really is still held
+    if let Ok(mut machine) = MACHINE.lock() {
+        machine.reset();
+        machine.unlatch();
+    }
+    let _ = handle.emit("recording-limit-reached", limit.as_secs());
+    on_hotkey_released(&handle);
+}
+
+/// The canceller of recording `generation`, unless a newer recording replaced it
+fn take_canceller(state: &AppState, generation: u64) -> Option<TranscriptionCanceller> {
+    let mut slot = state.transcription_canceller.lock().ok()?;
+    match slot.as_ref() {
+        Some((owner, _)) if *owner == generation => slot.take().map(|(_, canceller)| canceller),
+        _ => None,
+    }
+}
+
+/// A press that didn't start a recording must not latch, and its release has nothing to stop
+fn abandon_press(generation: u64) {
+    if let Ok(mut machine) = MACHINE.lock() {
+        machine.abandon_press();
+    }
+    if let Ok(mut dictation) = DICTATION.lock() {
+        if dictation.as_ref().is_some_and(|dictation| dictation.generation == generation) {
+            *dictation = None;
+        }
+    }
+}
+
 fn on_hotkey_released(app_handle: &tauri::AppHandle) {
     let handle = app_handle.clone();
 
-    // Get modifier keycodes for clearing
-    let modifier_keycodes: Vec<i64> = match app_handle.try_state::<AppState>() {
-        Some(state) => match state.current_hotkey.lock() {
-            Ok(hotkey) => hotkey.modifier_keycodes(),
-            Err(_) => vec![54], // fallback to right command
-        },
-        None => vec![54],
-    };
+    // The binding's modifier keys for clearing, and the profile to insert with
+    let Dictation {
+        generation,
+        modifier_keys,
+        profile,
+    } = DICTATION
+        .lock()
+        .ok()
+        .and_then(|mut dictation| dictation.take())
+        .unwrap_or_else(|| Dictation {
+            generation: RECORDING_GENERATION.load(Ordering::SeqCst),
+            modifier_keys: vec![Key::MetaRight], // fallback to right command
+            profile: DictationProfile::default(),
+        });
 
     tauri::async_runtime::spawn(async move {
-        if let Some(state) = handle.try_state::<AppState>() {
+        let Some(state) = handle.try_state::<AppState>() else {
+            return;
+        };
+        {
             let mut is_recording = state.is_recording.lock().await;
             *is_recording = false;
         }
@@ -264,13 +336,42 @@ fn on_hotkey_released(app_handle: &tauri::AppHandle) {
         audio::play_stop_sound();
 
         println!("[DEBUG] Stopping recording...");
-        let audio_path = match audio::stop_recording() {
-            Ok(path) => {
-                println!("[DEBUG] Recording saved to: {}", path);
-                path
+        // Stopping closes the frame sink, which ends the stream to the backend
+        let recorded = audio::stop_recording().map(|recording| {
+            let _ = handle.emit("capture-diagnostics", &recording.diagnostics);
+            recording.samples
+        });
+        let pending = state
+            .pending_transcription
+            .lock()
+            .ok()
+            .and_then(|mut slot| slot.take());
+
+        let pending = match (recorded, pending) {
+            (Ok(samples), Some(_)) if samples.is_empty() => {
+                // Nothing but silence - don't let the model hallucinate text into it
+                println!("[DEBUG] No speech detected, skipping transcription");
+                if let Some(canceller) = take_canceller(&state, generation) {
+                    canceller.cancel();
+                }
+                crate::tray::set_recording_state(&handle, false);
+                let _ = handle.emit("no-speech", ());
+                return;
             }
-            Err(e) => {
+            (Ok(samples), Some(pending)) => {
+                println!("[DEBUG] Recorded {} samples", samples.len());
+                pending
+            }
+            (Err(e), _) => {
                 eprintln!("[DEBUG] Failed to stop recording: {}", e);
+                if let Some(canceller) = take_canceller(&state, generation) {
+                    canceller.cancel();
+                }
+                crate::tray::set_recording_state(&handle, false);
+                return;
+            }
+            (Ok(_), None) => {
+                eprintln!("[DEBUG] No transcription in progress");
                 crate::tray::set_recording_state(&handle, false);
                 return;
             }
@@ -280,32 +381,40 @@ fn on_hotkey_released(app_handle: &tauri::AppHandle) {
         let _ = handle.emit("transcription-started", ());
 
         println!("[DEBUG] Starting transcription...");
-        match sidecar::transcribe(&handle, &audio_path).await {
-            Ok(text) => {
-                println!("[DEBUG] Transcription result: '{}'", text);
-                if !text.is_empty() {
-                    let text_clone = text.clone();
-                    let keycodes = modifier_keycodes.clone();
-                    let result = handle.run_on_main_thread(move || {
-                        if let Err(e) = text_insertion::insert_text_via_clipboard(&text_clone, &keycodes) {
-                            eprintln!("[DEBUG] Failed to insert text: {}", e);
+        let post_processing = profile.post_processing;
+        let insert_handle = handle.clone();
+        let insert_text = move |text: String| {
+            insert_handle
+                .run_on_main_thread(move || {
+                    let result = match profile.insertion {
+                        InsertionMethod::Paste => {
+                            text_insertion::insert_text_via_clipboard(&text, &modifier_keys)
                         }
-                    });
-
+                        InsertionMethod::Clipboard => text_insertion::copy_to_clipboard(&text),
+                    };
                     if let Err(e) = result {
-                        eprintln!("[DEBUG] Failed to run on main thread: {}", e);
+                        eprintln!("[DEBUG] Failed to insert text: {}", e);
                     }
-                    let _ = handle.emit("transcription-complete", text);
-                } else {
-                    println!("[DEBUG] Transcription was empty");
-                }
+                })
+                .map_err(|e| format!("Failed to run on main thread: {}", e))
+        };
+
+        match pipeline::finish_transcription(pending, post_processing, insert_text).await {
+            DictationOutcome::Inserted(text) => {
+                let _ = handle.emit("transcription-complete", text);
             }
-            Err(e) => {
-                eprintln!("[DEBUG] Transcription failed: {}", e);
-                let _ = handle.emit("transcription-error", e);
+            DictationOutcome::Empty => {
+                println!("[DEBUG] Transcription was empty");
+            }
+            DictationOutcome::Failed(e) => {
+                let _ = handle.emit("transcription-error", e.to_string());
+            }
+            DictationOutcome::Cancelled => {
+                // on_cancel_pressed already reported it
             }
         }
 
-        let _ = std::fs::remove_file(&audio_path);
+        // A recording started meanwhile has its own canceller
+        take_canceller(&state, generation);
     });
 }
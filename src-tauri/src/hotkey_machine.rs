@@ -0,0 +1,900 @@
+//! Platform-neutral hotkey detection. The platform's event source translates key
+//! events into [`KeyEvent`]s and carries out the [`HotkeyAction`]s that come back.
+
+use std::collections::HashSet;
+use std::time::{Duration, Instant};
+
+use crate::config::{HotkeyConfig, HotkeyMode};
+use crate::keys::Key;
+
+/// In hybrid mode, a press shorter than this latches recording on
+pub const HYBRID_TAP_THRESHOLD: Duration = Duration::from_millis(300);
+
+/// Modifier families the OS reports as down. Left and right keys share a flag.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ModifierFlags {
+    pub command: bool,
+    pub shift: bool,
+    pub option: bool,
+    pub control: bool,
+    pub caps_lock: bool,
+    pub function: bool,
+}
+
+impl ModifierFlags {
+    /// Whether the family of modifier `key` is down; None for other keys
+    pub fn family_down(&self, key: Key) -> Option<bool> {
+        match key {
+            Key::MetaLeft | Key::MetaRight => Some(self.command),
+            Key::ShiftLeft | Key::ShiftRight => Some(self.shift),
+            Key::AltLeft | Key::AltRight => Some(self.option),
+            Key::ControlLeft | Key::ControlRight => Some(self.control),
+            Key::CapsLock => Some(self.caps_lock),
+            Key::Fn => Some(self.function),
+            _ => None,
+        }
+    }
+
+    /// Flags for the given held keys; keys that aren't modifiers are ignored
+    pub fn from_held(keys: impl IntoIterator<Item = Key>) -> Self {
+        let mut flags = Self::default();
+        for key in keys {
+            match key {
+                Key::MetaLeft | Key::MetaRight => flags.command = true,
+                Key::ShiftLeft | Key::ShiftRight => flags.shift = true,
+                Key::AltLeft | Key::AltRight => flags.option = true,
+                Key::ControlLeft | Key::ControlRight => flags.control = true,
+                Key::CapsLock => flags.caps_lock = true,
+                Key::Fn => flags.function = true,
+                _ => {}
+            }
+        }
+        flags
+    }
+}
+
+/// A key event, as reported by the platform
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum KeyEvent {
+    /// A regular key went down, or auto-repeated
+    Down(Key),
+    /// A regular key went up
+    Up(Key),
+    /// A modifier key went down or up; `flags` is the state after the change
+    FlagsChanged { key: Key, flags: ModifierFlags },
+}
+
+/// What the app should do in response to key events
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HotkeyAction {
+    /// Start recording with the binding at this index
+    Pressed(usize),
+    /// Stop recording and transcribe
+    Released,
+    /// Discard the recording, or the transcription still running
+    Cancelled,
+}
+
+/// The keys, mode and gestures of one binding
+#[derive(Debug)]
+struct Chord {
+    required: HashSet<Key>,
+    cancel_key: Option<Key>,
+    mode: HotkeyMode,
+    min_hold: Duration,
+    /// Longest gap between the taps of a double tap, if the binding needs one
+    double_tap: Option<Duration>,
+    discard_on_other_key: bool,
+}
+
+/// What the press of the active binding has done so far
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Press {
+    /// Held for less than the minimum hold time; recording starts once it has been
+    Waiting,
+    /// Started the recording
+    Recording,
+    /// The first tap of a double tap
+    FirstTap,
+    /// Stopped a latched recording or was abandoned; its release is ignored
+    Spent,
+    /// The cancel key was pressed; its release is ignored
+    Cancelled,
+}
+
+/// Tracks held keys and turns them into recording actions according to the
+/// keys, mode and gestures of each hotkey binding. Only one binding records at a time.
+#[derive(Debug)]
+pub struct HotkeyMachine {
+    chords: Vec<Chord>,
+    held: HashSet<Key>,
+    /// The binding whose keys are all held, and what its press did
+    active: Option<(usize, Press)>,
+    /// Recording keeps running after the hotkey is released
+    latched: bool,
+    pressed_at: Option<Instant>,
+    /// Binding and time of a first tap that a second press can complete
+    last_tap: Option<(usize, Instant)>,
+}
+
+impl HotkeyMachine {
+    pub fn new(hotkeys: &[HotkeyConfig]) -> Self {
+        Self {
+            chords: Self::chords(hotkeys),
+            held: HashSet::new(),
+            active: None,
+            latched: false,
+            pressed_at: None,
+            last_tap: None,
+        }
+    }
+
+    fn chords(hotkeys: &[HotkeyConfig]) -> Vec<Chord> {
+        hotkeys
+            .iter()
+            .map(|hotkey| Chord {
+                required: hotkey.required_keys(),
+                cancel_key: hotkey.cancel_key,
+                mode: hotkey.mode,
+                min_hold: Duration::from_millis(hotkey.gestures.min_hold_ms),
+                double_tap: hotkey
+                    .gestures
+                    .double_tap
+                    .then(|| Duration::from_millis(hotkey.gestures.double_tap_ms)),
+                discard_on_other_key: hotkey.gestures.discard_on_other_key,
+            })
+            .collect()
+    }
+
+    /// Switch to new hotkeys. A latched recording stays latched.
+    pub fn set_config(&mut self, hotkeys: &[HotkeyConfig]) {
+        self.chords = Self::chords(hotkeys);
+        self.reset();
+    }
+
+    /// Forget the held keys, e.g. after a release event was missed
+    pub fn reset(&mut self) {
+        self.held.clear();
+        self.active = None;
+        self.last_tap = None;
+    }
+
+    /// The recording ended without the hotkey, so the next press starts a new one
+    pub fn unlatch(&mut self) {
+        self.latched = false;
+    }
+
+    /// A press that didn't start a recording must not latch, and its release has
+    /// nothing to stop
+    pub fn abandon_press(&mut self) {
+        self.latched = false;
+        if let Some((_, press)) = &mut self.active {
+            *press = Press::Spent;
+        }
+    }
+
+    /// When [`tick`](Self::tick) has to be called next: the moment a press has
+    /// been held for the minimum hold time
+    pub fn deadline(&self) -> Option<Instant> {
+        match (self.active, self.pressed_at) {
+            (Some((index, Press::Waiting)), Some(pressed_at)) => {
+                Some(pressed_at + self.chords[index].min_hold)
+            }
+            _ => None,
+        }
+    }
+
+    /// Let time pass without a key event; starts recording once a press has been
+    /// held long enough
+    pub fn tick(&mut self, now: Instant) -> Vec<HotkeyAction> {
+        match (self.active, self.deadline()) {
+            (Some((index, _)), Some(deadline)) if now >= deadline => {
+                self.active = Some((index, Press::Recording));
+                vec![HotkeyAction::Pressed(index)]
+            }
+            _ => Vec::new(),
+        }
+    }
+
+    /// Feed a key event that happened at `now`
+    pub fn handle(&mut self, event: KeyEvent, now: Instant) -> Vec<HotkeyAction> {
+        // A press held long enough started recording before this event
+        let mut actions = self.tick(now);
+        // A hotkey only fires on the key press that completes it
+        let mut newly_held = None;
+
+        match event {
+            KeyEvent::FlagsChanged { key, flags } => {
+                if let Some(family_down) = flags.family_down(key) {
+                    // With both keys of a family down the flag stays set, so a
+                    // change of a held key is its release
+                    let pressed = family_down && !self.held.contains(&key);
+                    self.set_held(key, pressed);
+                    if pressed {
+                        newly_held = Some(key);
+                    }
+                }
+                // Drop modifiers whose release was missed
+                self.held
+                    .retain(|&held| flags.family_down(held).unwrap_or(true));
+            }
+            KeyEvent::Down(key) if !key.is_modifier() => {
+                if self.is_cancel_key(key) {
+                    actions.extend(self.cancel());
+                }
+                if !self.held.contains(&key) {
+                    newly_held = Some(key);
+                }
+                self.set_held(key, true);
+            }
+            KeyEvent::Up(key) if !key.is_modifier() => {
+                self.set_held(key, false);
+            }
+            _ => {}
+        }
+
+        if let Some(key) = newly_held {
+            actions.extend(self.other_key_pressed(key));
+        }
+
+        match self.active {
+            None => {
+                if let Some(index) = newly_held.and_then(|key| self.completed_chord(key)) {
+                    actions.extend(self.chord_pressed(index, now));
+                }
+            }
+            Some((index, press)) if !self.is_held(&self.chords[index]) => {
+                self.active = None;
+                match press {
+                    Press::Recording if self.should_latch(index, now) => self.latched = true,
+                    Press::Recording => actions.push(HotkeyAction::Released),
+                    // Nothing was started, or it was already stopped or thrown away
+                    Press::Waiting | Press::FirstTap | Press::Spent | Press::Cancelled => {}
+                }
+            }
+            Some(_) => {}
+        }
+
+        actions
+    }
+
+    /// A hotkey combination is currently held
+    #[cfg(test)]
+    pub fn is_active(&self) -> bool {
+        self.active.is_some()
+    }
+
+    /// Recording was latched on and runs until the next press
+    pub fn is_latched(&self) -> bool {
+        self.latched
+    }
+
+    fn set_held(&mut self, key: Key, pressed: bool) {
+        if pressed {
+            self.held.insert(key);
+        } else {
+            self.held.remove(&key);
+        }
+    }
+
+    fn is_held(&self, chord: &Chord) -> bool {
+        !chord.required.is_empty() && chord.required.iter().all(|key| self.held.contains(key))
+    }
+
+    /// The binding that pressing `key` completed; when several are, the one with
+    /// the most keys
+    fn completed_chord(&self, key: Key) -> Option<usize> {
+        let mut completed: Option<usize> = None;
+        for (index, chord) in self.chords.iter().enumerate() {
+            let more_keys = completed
+                .is_none_or(|best| chord.required.len() > self.chords[best].required.len());
+            if more_keys && chord.required.contains(&key) && self.is_held(chord) {
+                completed = Some(index);
+            }
+        }
+        completed
+    }
+
+    /// All keys of binding `index` just went down
+    fn chord_pressed(&mut self, index: usize, now: Instant) -> Option<HotkeyAction> {
+        if self.latched {
+            // Pressing any hotkey stops a latched recording
+            self.latched = false;
+            self.active = Some((index, Press::Spent));
+            return Some(HotkeyAction::Released);
+        }
+
+        let chord = &self.chords[index];
+        if let Some(window) = chord.double_tap {
+            let second_tap = matches!(
+                self.last_tap,
+                Some((tapped, at)) if tapped == index && now.duration_since(at) <= window
+            );
+            if !second_tap {
+                self.last_tap = Some((index, now));
+                self.active = Some((index, Press::FirstTap));
+                return None;
+            }
+        }
+        self.last_tap = None;
+
+        self.pressed_at = Some(now);
+        if chord.min_hold.is_zero() {
+            self.active = Some((index, Press::Recording));
+            Some(HotkeyAction::Pressed(index))
+        } else {
+            self.active = Some((index, Press::Waiting));
+            None
+        }
+    }
+
+    /// `key` went down; if it isn't part of the held hotkey, the press may be a shortcut
+    fn other_key_pressed(&mut self, key: Key) -> Option<HotkeyAction> {
+        if matches!(self.last_tap, Some((index, _)) if !self.chords[index].required.contains(&key))
+        {
+            // Taps with typing in between aren't a double tap
+            self.last_tap = None;
+        }
+
+        let (index, press) = self.active?;
+        let chord = &self.chords[index];
+        if !chord.discard_on_other_key || chord.required.contains(&key) {
+            return None;
+        }
+        match press {
+            Press::Recording => {
+                // Free the hotkey for a binding the new key may complete
+                self.active = None;
+                Some(HotkeyAction::Cancelled)
+            }
+            Press::Waiting | Press::FirstTap => {
+                self.active = None;
+                None
+            }
+            Press::Spent | Press::Cancelled => None,
+        }
+    }
+
+    /// A cancel key of any binding that isn't itself part of a hotkey
+    fn is_cancel_key(&self, key: Key) -> bool {
+        self.chords.iter().any(|chord| chord.cancel_key == Some(key))
+            && !self.chords.iter().any(|chord| chord.required.contains(&key))
+    }
+
+    fn cancel(&mut self) -> Option<HotkeyAction> {
+        if let Some((_, press)) = &mut self.active {
+            if *press == Press::Cancelled {
+                // Key repeat - already cancelled
+                return None;
+            }
+            *press = Press::Cancelled;
+        }
+        self.latched = false;
+        Some(HotkeyAction::Cancelled)
+    }
+
+    /// Whether releasing binding `index` at `now` should keep the recording running
+    fn should_latch(&self, index: usize, now: Instant) -> bool {
+        match self.chords[index].mode {
+            HotkeyMode::Hold => false,
+            HotkeyMode::Toggle => true,
+            HotkeyMode::Hybrid => matches!(
+                self.pressed_at,
+                Some(pressed_at) if now.duration_since(pressed_at) < HYBRID_TAP_THRESHOLD
+            ),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::{GestureConfig, Modifier};
+
+    const RIGHT_CMD: Key = Key::MetaRight;
+    const LEFT_CMD: Key = Key::MetaLeft;
+    const SHIFT: Key = Key::ShiftLeft;
+    const SPACE: Key = Key::Space;
+    const ESCAPE: Key = Key::Escape;
+    const KEY_A: Key = Key::KeyA;
+    const KEY_N: Key = Key::KeyN;
+
+    fn config(keys: &[Key], mode: HotkeyMode) -> HotkeyConfig {
+        HotkeyConfig {
+            modifiers: vec![Modifier::Command],
+            key: keys.iter().copied().find(|key| !key.is_modifier()),
+            modifier_keys: keys.iter().copied().filter(|key| key.is_modifier()).collect(),
+            mode,
+            ..HotkeyConfig::default()
+        }
+    }
+
+    /// Simulated keyboard: turns physical key changes into the events the OS reports
+    struct Keyboard {
+        machine: HotkeyMachine,
+        down: HashSet<Key>,
+        now: Instant,
+    }
+
+    impl Keyboard {
+        fn new(keys: &[Key], mode: HotkeyMode) -> Self {
+            Self::with_bindings(&[(keys, mode)])
+        }
+
+        fn with_bindings(bindings: &[(&[Key], HotkeyMode)]) -> Self {
+            let hotkeys: Vec<HotkeyConfig> =
+                bindings.iter().map(|&(keys, mode)| config(keys, mode)).collect();
+            Self::with_hotkeys(&hotkeys)
+        }
+
+        fn with_gestures(keys: &[Key], mode: HotkeyMode, gestures: GestureConfig) -> Self {
+            Self::with_hotkeys(&[HotkeyConfig { gestures, ..config(keys, mode) }])
+        }
+
+        fn with_hotkeys(hotkeys: &[HotkeyConfig]) -> Self {
+            Self {
+                machine: HotkeyMachine::new(hotkeys),
+                down: HashSet::new(),
+                now: Instant::now(),
+            }
+        }
+
+        /// Change a key's physical state; the event is lost if `reported` is false
+        fn set(&mut self, key: Key, down: bool, reported: bool) -> Vec<HotkeyAction> {
+            if down {
+                self.down.insert(key);
+            } else {
+                self.down.remove(&key);
+            }
+            if !reported {
+                return Vec::new();
+            }
+            let event = if key.is_modifier() {
+                KeyEvent::FlagsChanged {
+                    key,
+                    flags: ModifierFlags::from_held(self.down.iter().copied()),
+                }
+            } else if down {
+                KeyEvent::Down(key)
+            } else {
+                KeyEvent::Up(key)
+            };
+            self.machine.handle(event, self.now)
+        }
+
+        fn press(&mut self, key: Key) -> Vec<HotkeyAction> {
+            self.set(key, true, true)
+        }
+
+        fn release(&mut self, key: Key) -> Vec<HotkeyAction> {
+            self.set(key, false, true)
+        }
+
+        fn wait(&mut self, ms: u64) {
+            self.now += Duration::from_millis(ms);
+        }
+
+        /// Let `ms` pass and wake the machine, like the app's timer does
+        fn wait_and_tick(&mut self, ms: u64) -> Vec<HotkeyAction> {
+            self.wait(ms);
+            self.machine.tick(self.now)
+        }
+
+        /// Press and release `key` after `ms`
+        fn tap(&mut self, key: Key, ms: u64) -> Vec<HotkeyAction> {
+            let mut actions = self.press(key);
+            actions.extend(self.wait_and_tick(ms));
+            actions.extend(self.release(key));
+            actions
+        }
+    }
+
+    use HotkeyAction::*;
+
+    #[test]
+    fn test_hold_to_talk() {
+        let mut keyboard = Keyboard::new(&[RIGHT_CMD], HotkeyMode::Hold);
+        assert_eq!(keyboard.press(RIGHT_CMD), vec![Pressed(0)]);
+        keyboard.wait(2000);
+        assert_eq!(keyboard.release(RIGHT_CMD), vec![Released]);
+    }
+
+    #[test]
+    fn test_other_side_of_modifier_does_not_trigger() {
+        let mut keyboard = Keyboard::new(&[RIGHT_CMD], HotkeyMode::Hold);
+        assert!(keyboard.press(LEFT_CMD).is_empty());
+        // The command flag is already set; the right key still registers
+        assert_eq!(keyboard.press(RIGHT_CMD), vec![Pressed(0)]);
+        assert_eq!(keyboard.release(RIGHT_CMD), vec![Released]);
+        assert!(keyboard.release(LEFT_CMD).is_empty());
+    }
+
+    #[test]
+    fn test_toggle_latches_until_next_press() {
+        let mut keyboard = Keyboard::new(&[RIGHT_CMD], HotkeyMode::Toggle);
+        assert_eq!(keyboard.press(RIGHT_CMD), vec![Pressed(0)]);
+        assert!(keyboard.release(RIGHT_CMD).is_empty());
+        assert!(keyboard.machine.is_latched());
+        assert_eq!(keyboard.press(RIGHT_CMD), vec![Released]);
+        // The release of the stopping press does nothing
+        assert!(keyboard.release(RIGHT_CMD).is_empty());
+        assert_eq!(keyboard.press(RIGHT_CMD), vec![Pressed(0)]);
+    }
+
+    #[test]
+    fn test_hybrid_tap_latches_and_hold_does_not() {
+        let mut keyboard = Keyboard::new(&[RIGHT_CMD], HotkeyMode::Hybrid);
+        keyboard.press(RIGHT_CMD);
+        keyboard.wait(100);
+        assert!(keyboard.release(RIGHT_CMD).is_empty());
+        assert_eq!(keyboard.press(RIGHT_CMD), vec![Released]);
+        keyboard.release(RIGHT_CMD);
+
+        assert_eq!(keyboard.press(RIGHT_CMD), vec![Pressed(0)]);
+        keyboard.wait(1000);
+        assert_eq!(keyboard.release(RIGHT_CMD), vec![Released]);
+    }
+
+    #[test]
+    fn test_cancel_while_held_ignores_release() {
+        let mut keyboard = Keyboard::new(&[RIGHT_CMD], HotkeyMode::Hold);
+        keyboard.press(RIGHT_CMD);
+        assert_eq!(keyboard.press(ESCAPE), vec![Cancelled]);
+        // Auto-repeat of the cancel key
+        assert!(keyboard.press(ESCAPE).is_empty());
+        keyboard.release(ESCAPE);
+        assert!(keyboard.release(RIGHT_CMD).is_empty());
+    }
+
+    #[test]
+    fn test_cancel_unlatches() {
+        let mut keyboard = Keyboard::new(&[RIGHT_CMD], HotkeyMode::Toggle);
+        keyboard.press(RIGHT_CMD);
+        keyboard.release(RIGHT_CMD);
+        assert_eq!(keyboard.press(ESCAPE), vec![Cancelled]);
+        keyboard.release(ESCAPE);
+        // The next press starts a new recording instead of stopping one
+        assert_eq!(keyboard.press(RIGHT_CMD), vec![Pressed(0)]);
+    }
+
+    #[test]
+    fn test_abandoned_press_ignores_release() {
+        let mut keyboard = Keyboard::new(&[RIGHT_CMD], HotkeyMode::Toggle);
+        keyboard.press(RIGHT_CMD);
+        keyboard.machine.abandon_press();
+        assert!(keyboard.release(RIGHT_CMD).is_empty());
+        assert!(!keyboard.machine.is_latched());
+    }
+
+    #[test]
+    fn test_stuck_modifier_is_released_by_next_flags_change() {
+        let mut keyboard = Keyboard::new(&[RIGHT_CMD, SPACE], HotkeyMode::Hold);
+        keyboard.press(RIGHT_CMD);
+        // The command key-up never arrives
+        keyboard.set(RIGHT_CMD, false, false);
+        keyboard.press(SHIFT);
+        keyboard.release(SHIFT);
+        // Space alone must not trigger the Cmd+Space hotkey
+        assert!(keyboard.press(SPACE).is_empty());
+    }
+
+    #[test]
+    fn test_each_binding_starts_its_own_recording() {
+        let mut keyboard = Keyboard::with_bindings(&[
+            (&[RIGHT_CMD], HotkeyMode::Hold),
+            (&[Key::AltRight], HotkeyMode::Toggle),
+            (&[Key::F13], HotkeyMode::Hold),
+        ]);
+        assert_eq!(keyboard.press(Key::F13), vec![Pressed(2)]);
+        // Another hotkey during the recording is ignored
+        assert!(keyboard.press(RIGHT_CMD).is_empty());
+        assert!(keyboard.release(RIGHT_CMD).is_empty());
+        assert_eq!(keyboard.release(Key::F13), vec![Released]);
+
+        // The toggle binding latches, and any hotkey stops it
+        assert_eq!(keyboard.press(Key::AltRight), vec![Pressed(1)]);
+        assert!(keyboard.release(Key::AltRight).is_empty());
+        assert_eq!(keyboard.press(RIGHT_CMD), vec![Released]);
+        assert!(keyboard.release(RIGHT_CMD).is_empty());
+        assert_eq!(keyboard.press(RIGHT_CMD), vec![Pressed(0)]);
+    }
+
+    #[test]
+    fn test_longest_held_chord_wins() {
+        let mut keyboard = Keyboard::with_bindings(&[
+            (&[RIGHT_CMD], HotkeyMode::Hold),
+            (&[RIGHT_CMD, SHIFT], HotkeyMode::Hold),
+        ]);
+        assert!(keyboard.press(SHIFT).is_empty());
+        assert_eq!(keyboard.press(RIGHT_CMD), vec![Pressed(1)]);
+        assert_eq!(keyboard.release(SHIFT), vec![Released]);
+        // Right ⌘ is still down, but it was part of the last press
+        assert!(keyboard.press(KEY_N).is_empty());
+        assert!(keyboard.release(RIGHT_CMD).is_empty());
+        assert_eq!(keyboard.press(RIGHT_CMD), vec![Pressed(0)]);
+    }
+
+    fn gestures(min_hold_ms: u64, double_tap: bool, discard_on_other_key: bool) -> GestureConfig {
+        GestureConfig {
+            min_hold_ms,
+            double_tap,
+            discard_on_other_key,
+            ..GestureConfig::default()
+        }
+    }
+
+    #[test]
+    fn test_min_hold_delays_recording() {
+        let hold = gestures(200, false, false);
+        let mut keyboard = Keyboard::with_gestures(&[RIGHT_CMD], HotkeyMode::Hold, hold);
+        assert!(keyboard.press(RIGHT_CMD).is_empty());
+        assert_eq!(keyboard.machine.deadline(), Some(keyboard.now + Duration::from_millis(200)));
+        assert!(keyboard.wait_and_tick(150).is_empty());
+        assert_eq!(keyboard.wait_and_tick(50), vec![Pressed(0)]);
+        assert_eq!(keyboard.machine.deadline(), None);
+        assert_eq!(keyboard.release(RIGHT_CMD), vec![Released]);
+
+        // Let go too early and nothing happens, now or later
+        assert!(keyboard.tap(RIGHT_CMD, 100).is_empty());
+        assert!(keyboard.wait_and_tick(500).is_empty());
+    }
+
+    #[test]
+    fn test_late_event_starts_the_recording_first() {
+        let hold = gestures(200, false, false);
+        let mut keyboard = Keyboard::with_gestures(&[RIGHT_CMD], HotkeyMode::Hold, hold);
+        keyboard.press(RIGHT_CMD);
+        // The timer didn't fire before the release came in
+        keyboard.wait(300);
+        assert_eq!(keyboard.release(RIGHT_CMD), vec![Pressed(0), Released]);
+    }
+
+    #[test]
+    fn test_double_tap() {
+        let double = gestures(0, true, false);
+        let mut keyboard = Keyboard::with_gestures(&[RIGHT_CMD], HotkeyMode::Hold, double);
+        assert!(keyboard.tap(RIGHT_CMD, 80).is_empty());
+        keyboard.wait(150);
+        assert_eq!(keyboard.press(RIGHT_CMD), vec![Pressed(0)]);
+        keyboard.wait(2000);
+        assert_eq!(keyboard.release(RIGHT_CMD), vec![Released]);
+
+        // Too slow: the second press is a new first tap
+        assert!(keyboard.tap(RIGHT_CMD, 80).is_empty());
+        keyboard.wait(500);
+        assert!(keyboard.tap(RIGHT_CMD, 80).is_empty());
+        keyboard.wait(100);
+        assert_eq!(keyboard.press(RIGHT_CMD), vec![Pressed(0)]);
+        keyboard.release(RIGHT_CMD);
+
+        // Typing between the taps
+        keyboard.tap(RIGHT_CMD, 80);
+        keyboard.tap(KEY_N, 30);
+        assert!(keyboard.press(RIGHT_CMD).is_empty());
+    }
+
+    #[test]
+    fn test_shortcut_is_discarded() {
+        // While waiting for the minimum hold, ⌘C never starts recording
+        let discard = gestures(200, false, true);
+        let mut keyboard = Keyboard::with_gestures(&[RIGHT_CMD], HotkeyMode::Hold, discard);
+        keyboard.press(RIGHT_CMD);
+        assert!(keyboard.tap(Key::KeyC, 50).is_empty());
+        assert!(keyboard.wait_and_tick(500).is_empty());
+        assert!(keyboard.release(RIGHT_CMD).is_empty());
+
+        // Once recording, the shortcut throws the recording away
+        let discard = gestures(0, false, true);
+        let mut keyboard = Keyboard::with_gestures(&[RIGHT_CMD], HotkeyMode::Toggle, discard);
+        assert_eq!(keyboard.press(RIGHT_CMD), vec![Pressed(0)]);
+        assert_eq!(keyboard.press(Key::KeyC), vec![Cancelled]);
+        keyboard.release(Key::KeyC);
+        assert!(keyboard.release(RIGHT_CMD).is_empty());
+        assert!(!keyboard.machine.is_latched());
+
+        // Keys of the hotkey itself are no shortcut
+        let mut keyboard = Keyboard::with_gestures(&[RIGHT_CMD, SHIFT], HotkeyMode::Hold, discard);
+        keyboard.press(SHIFT);
+        assert_eq!(keyboard.press(RIGHT_CMD), vec![Pressed(0)]);
+        assert_eq!(keyboard.release(SHIFT), vec![Released]);
+    }
+
+    #[test]
+    fn test_discarded_press_frees_a_longer_binding() {
+        let plain = HotkeyConfig {
+            gestures: gestures(200, false, true),
+            ..config(&[RIGHT_CMD], HotkeyMode::Hold)
+        };
+        let chord = config(&[RIGHT_CMD, SHIFT], HotkeyMode::Hold);
+        let mut keyboard = Keyboard::with_hotkeys(&[plain, chord]);
+        keyboard.press(RIGHT_CMD);
+        assert_eq!(keyboard.press(SHIFT), vec![Pressed(1)]);
+        assert!(keyboard.wait_and_tick(500).is_empty());
+        assert_eq!(keyboard.release(SHIFT), vec![Released]);
+    }
+
+    /// Small deterministic PRNG so the property tests need no extra dependency
+    struct Rng(u64);
+
+    impl Rng {
+        fn next(&mut self) -> u64 {
+            self.0 ^= self.0 << 13;
+            self.0 ^= self.0 >> 7;
+            self.0 ^= self.0 << 17;
+            self.0
+        }
+
+        fn below(&mut self, n: usize) -> usize {
+            (self.next() % n as u64) as usize
+        }
+
+        fn chance(&mut self, percent: u64) -> bool {
+            self.next() % 100 < percent
+        }
+
+        fn shuffle<T>(&mut self, items: &mut [T]) {
+            for i in (1..items.len()).rev() {
+                items.swap(i, self.below(i + 1));
+            }
+        }
+    }
+
+    fn random_gestures(rng: &mut Rng) -> GestureConfig {
+        GestureConfig {
+            min_hold_ms: [0, 150, 400][rng.below(3)],
+            double_tap: rng.chance(30),
+            double_tap_ms: 400,
+            discard_on_other_key: rng.chance(50),
+        }
+    }
+
+    const MODES: [HotkeyMode; 3] = [HotkeyMode::Hold, HotkeyMode::Toggle, HotkeyMode::Hybrid];
+    const CHORDS: [&[Key]; 4] = [
+        &[RIGHT_CMD],
+        &[RIGHT_CMD, SHIFT],
+        &[LEFT_CMD, SPACE],
+        &[RIGHT_CMD, SHIFT, KEY_A],
+    ];
+    const KEYS: [Key; 6] = [RIGHT_CMD, LEFT_CMD, SHIFT, SPACE, ESCAPE, KEY_A];
+
+    /// Actions must make sense to the recorder: a start only while idle, a stop
+    /// only while recording - whatever keys are mashed and events lost, with one
+    /// binding or several
+    #[test]
+    fn prop_actions_alternate() {
+        let mut rng = Rng(0x5eed);
+        for _ in 0..500 {
+            let hotkeys: Vec<HotkeyConfig> = (0..1 + rng.below(3))
+                .map(|_| HotkeyConfig {
+                    gestures: random_gestures(&mut rng),
+                    ..config(CHORDS[rng.below(CHORDS.len())], MODES[rng.below(MODES.len())])
+                })
+                .collect();
+            let mut keyboard = Keyboard::with_hotkeys(&hotkeys);
+            let mut recording = false;
+
+            for _ in 0..200 {
+                let key = KEYS[rng.below(KEYS.len())];
+                let down = !keyboard.down.contains(&key) || rng.chance(10);
+                let reported = rng.chance(95);
+                let actions = if rng.chance(20) {
+                    keyboard.wait_and_tick(rng.below(600) as u64)
+                } else {
+                    keyboard.wait(rng.below(600) as u64);
+                    keyboard.set(key, down, reported)
+                };
+                for action in actions {
+                    let context = format!("{:?}", hotkeys);
+                    match action {
+                        Pressed(_) => assert!(!recording, "started twice ({})", context),
+                        Released => assert!(recording, "stopped while idle ({})", context),
+                        Cancelled => {}
+                    }
+                    recording = matches!(action, Pressed(_));
+                }
+            }
+        }
+    }
+
+    /// In hold mode, letting go of every key always ends the recording, even
+    /// after lost events
+    #[test]
+    fn prop_hold_never_sticks() {
+        let mut rng = Rng(0xcafe);
+        for _ in 0..500 {
+            let chord = CHORDS[rng.below(CHORDS.len())];
+            let mut keyboard = Keyboard::new(chord, HotkeyMode::Hold);
+
+            for _ in 0..rng.below(50) {
+                let key = KEYS[rng.below(KEYS.len())];
+                let down = rng.chance(50);
+                keyboard.set(key, down, rng.chance(90));
+            }
+            // Release everything, then touch a modifier so lost releases get noticed
+            for key in KEYS {
+                keyboard.release(key);
+            }
+            keyboard.press(SHIFT);
+            keyboard.release(SHIFT);
+
+            assert!(!keyboard.machine.is_active(), "{:?} stuck", chord);
+            assert_eq!(keyboard.press(KEY_A), Vec::new());
+        }
+    }
+
+    /// With `discard_on_other_key`, a press during which another key went down
+    /// never leaves a recording behind, whatever the timing and other gestures
+    #[test]
+    fn prop_shortcuts_never_record() {
+        let mut rng = Rng(0xc0de);
+        for _ in 0..500 {
+            let chord = CHORDS[rng.below(CHORDS.len())];
+            let mode = MODES[rng.below(MODES.len())];
+            let gestures = GestureConfig {
+                discard_on_other_key: true,
+                ..random_gestures(&mut rng)
+            };
+            let mut keyboard = Keyboard::with_gestures(chord, mode, gestures);
+            let mut actions = Vec::new();
+
+            if gestures.double_tap && rng.chance(70) {
+                for &key in chord {
+                    actions.extend(keyboard.press(key));
+                }
+                for &key in chord {
+                    actions.extend(keyboard.release(key));
+                }
+                keyboard.wait(rng.below(300) as u64);
+            }
+            for &key in chord {
+                actions.extend(keyboard.press(key));
+            }
+            actions.extend(keyboard.wait_and_tick(rng.below(600) as u64));
+            let other = [KEY_N, SPACE, Key::KeyC, LEFT_CMD]
+                .into_iter()
+                .find(|key| !chord.contains(key))
+                .unwrap();
+            actions.extend(keyboard.tap(other, rng.below(200) as u64));
+            actions.extend(keyboard.wait_and_tick(rng.below(600) as u64));
+            for &key in chord {
+                actions.extend(keyboard.release(key));
+            }
+            actions.extend(keyboard.wait_and_tick(1000));
+
+            let recording = actions.iter().fold(false, |_, action| match action {
+                Pressed(_) => true,
+                Released | Cancelled => false,
+            });
+            assert!(!recording, "{:?} {:?} {:?}: {:?}", chord, mode, gestures, actions);
+            assert!(!keyboard.machine.is_latched());
+        }
+    }
+
+    /// The chord fires once, on its last key, whatever order it is pressed in,
+    /// and stops on the first key let go
+    #[test]
+    fn prop_chord_order_does_not_matter() {
+        let mut rng = Rng(0xbeef);
+        for chord in CHORDS {
+            for _ in 0..50 {
+                let mut keyboard = Keyboard::new(chord, HotkeyMode::Hold);
+                let mut order = chord.to_vec();
+                rng.shuffle(&mut order);
+                // An unrelated key held throughout doesn't matter either
+                if rng.chance(50) {
+                    keyboard.press(KEY_N);
+                }
+
+                for (i, &key) in order.iter().enumerate() {
+                    let expected = if i + 1 == order.len() { vec![Pressed(0)] } else { vec![] };
+                    assert_eq!(keyboard.press(key), expected, "order {:?}", order);
+                }
+                rng.shuffle(&mut order);
+                for (i, &key) in order.iter().enumerate() {
+                    let expected = if i == 0 { vec![Released] } else { vec![] };
+                    assert_eq!(keyboard.release(key), expected, "order {:?}", order);
+                }
+            }
+        }
+    }
+}
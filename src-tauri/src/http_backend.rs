@@ -0,0 +1,317 @@
+use async_trait::async_trait;
+use serde::Deserialize;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Duration;
+
+use crate::audio::TARGET_SAMPLE_RATE;
+use crate::backend::{Capabilities, TranscriptionBackend, TranscriptionError};
+use crate::config::{HttpApi, HttpBackendConfig};
+use crate::wav;
+
+/// How long the readiness probe waits for the server
+const PROBE_TIMEOUT: Duration = Duration::from_secs(1);
+
+/// Body of a successful response - both whisper.cpp and OpenAI return `{"text": "..."}`
+#[derive(Deserialize)]
+struct TranscriptionResponse {
+    text: String,
+}
+
+/// Transcription via a local HTTP server speaking the whisper.cpp `server`
+/// or OpenAI `/v1/audio/transcriptions` API
+pub struct HttpBackend {
+    client: reqwest::Client,
+    config: HttpBackendConfig,
+    /// Whether the last request reached the server; avoids probing on every hotkey press
+    reachable: AtomicBool,
+}
+
+impl HttpBackend {
+    pub fn new(config: HttpBackendConfig) -> Result<Self, String> {
+        let client = reqwest::Client::builder()
+            .timeout(Duration::from_millis(config.timeout_ms))
+            .build()
+            .map_err(|e| format!("Failed to create HTTP client: {}", e))?;
+
+        Ok(Self {
+            client,
+            config,
+            reachable: AtomicBool::new(true),
+        })
+    }
+
+    fn endpoint(&self) -> String {
+        let base = self.config.base_url.trim_end_matches('/');
+        match self.config.api {
+            HttpApi::WhisperCpp => format!("{}/inference", base),
+            HttpApi::OpenAi => format!("{}/v1/audio/transcriptions", base),
+        }
+    }
+
+    fn map_error(&self, e: reqwest::Error) -> TranscriptionError {
+        if e.is_timeout() {
+            TranscriptionError::Timeout(Duration::from_millis(self.config.timeout_ms))
+        } else {
+            self.reachable.store(false, Ordering::SeqCst);
+            TranscriptionError::Io(format!("Request to {} failed: {}", self.config.base_url, e))
+        }
+    }
+
+    /// Check whether anything answers at the base URL
+    async fn probe(&self) -> bool {
+        self.client
+            .get(&self.config.base_url)
+            .timeout(PROBE_TIMEOUT)
+            .send()
+            .await
+            .is_ok()
+    }
+}
+
+#[async_trait]
+impl TranscriptionBackend for HttpBackend {
+    fn name(&self) -> &'static str {
+        "http"
+    }
+
+    async fn is_ready(&self) -> bool {
+        if self.reachable.load(Ordering::SeqCst) {
+            return true;
+        }
+        let reachable = self.probe().await;
+        self.reachable.store(reachable, Ordering::SeqCst);
+        reachable
+    }
+
+    async fn capabilities(&self) -> Capabilities {
+        Capabilities {
+            model: Some(self.config.model.clone()),
+            features: vec!["transcribe_file".to_string()],
+        }
+    }
+
+    async fn transcribe(
+        &self,
+        samples: &[f32],
+        language: Option<&str>,
+    ) -> Result<String, TranscriptionError> {
+        // Both APIs only take files, so wrap the samples in an in-memory WAV
+        let audio = wav::encode_wav(samples, TARGET_SAMPLE_RATE).map_err(TranscriptionError::Io)?;
+
+        let file = reqwest::multipart::Part::bytes(audio)
+            .file_name("audio.wav")
+            .mime_str("audio/wav")
+            .map_err(|e| TranscriptionError::Protocol(e.to_string()))?;
+
+        let mut form = reqwest::multipart::Form::new()
+            .part("file", file)
+            .text("model", self.config.model.clone())
+            .text("response_format", "json");
+        if let Some(language) = language.or(self.config.language.as_deref()) {
+            form = form.text("language", language.to_string());
+        }
+
+        let mut request = self.client.post(self.endpoint()).multipart(form);
+        if let Some(api_key) = &self.config.api_key {
+            request = request.bearer_auth(api_key);
+        }
+
+        let response = request.send().await.map_err(|e| self.map_error(e))?;
+        self.reachable.store(true, Ordering::SeqCst);
+
+        let status = response.status();
+        let body = response.text().await.map_err(|e| self.map_error(e))?;
+
+        if !status.is_success() {
+            return Err(TranscriptionError::Transcription(format!(
+                "Server returned {}: {}",
+                status,
+                body.trim()
+            )));
+        }
+
+        let parsed: TranscriptionResponse = serde_json::from_str(&body).map_err(|e| {
+            TranscriptionError::Protocol(format!("Failed to parse response: {} - Raw: {}", e, body))
+        })?;
+
+        // whisper.cpp pads segments with spaces and newlines
+        Ok(parsed.text.trim().to_string())
+    }
+
+    async fn shutdown(&self) {}
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::{BufRead, BufReader, Read, Write};
+    use std::net::TcpListener;
+    use std::sync::mpsc;
+
+    /// What the stand-in server saw
+    struct CapturedRequest {
+        request_line: String,
+        body: String,
+    }
+
+    /// Serve exactly one request on a random local port, answering with `status` and `body`
+    /// after `delay`. Returns the base URL and a receiver for the captured request.
+    fn serve_once(
+        status: &'static str,
+        body: &'static str,
+        delay: Duration,
+    ) -> (String, mpsc::Receiver<CapturedRequest>) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let base_url = format!("http://{}", listener.local_addr().unwrap());
+        let (tx, rx) = mpsc::channel();
+
+        std::thread::spawn(move || {
+            let (stream, _) = listener.accept().unwrap();
+            let mut reader = BufReader::new(stream.try_clone().unwrap());
+
+            let mut request_line = String::new();
+            reader.read_line(&mut request_line).unwrap();
+
+            let mut content_length = 0;
+            loop {
+                let mut header = String::new();
+                reader.read_line(&mut header).unwrap();
+                if header.trim().is_empty() {
+                    break;
+                }
+                if let Some((name, value)) = header.split_once(':') {
+                    if name.eq_ignore_ascii_case("content-length") {
+                        content_length = value.trim().parse().unwrap();
+                    }
+                }
+            }
+            let mut body_bytes = vec![0u8; content_length];
+            reader.read_exact(&mut body_bytes).unwrap();
+
+            let _ = tx.send(CapturedRequest {
+                request_line: request_line.trim().to_string(),
+                body: String::from_utf8_lossy(&body_bytes).to_string(),
+            });
+
+            std::thread::sleep(delay);
+            let mut stream = stream;
+            let _ = write!(
+                stream,
+                "HTTP/1.1 {}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                status,
+                body.len(),
+                body
+            );
+        });
+
+        (base_url, rx)
+    }
+
+    /// A tenth of a second of silence
+    fn test_samples() -> Vec<f32> {
+        vec![0.0; 1600]
+    }
+
+    fn config(base_url: &str, api: HttpApi) -> HttpBackendConfig {
+        HttpBackendConfig {
+            base_url: base_url.to_string(),
+            api,
+            model: "whisper-1".to_string(),
+            timeout_ms: 2_000,
+            ..Default::default()
+        }
+    }
+
+    #[tokio::test]
+    async fn test_whisper_cpp_request() {
+        let (base_url, rx) = serve_once("200 OK", r#"{"text":" hello world\n"}"#, Duration::ZERO);
+        let backend = HttpBackend::new(config(&base_url, HttpApi::WhisperCpp)).unwrap();
+
+        let text = backend.transcribe(&test_samples(), None).await.unwrap();
+        assert_eq!(text, "hello world");
+
+        let request = rx.recv().unwrap();
+        assert_eq!(request.request_line, "POST /inference HTTP/1.1");
+        assert!(request.body.contains("name=\"file\""));
+        assert!(request.body.contains("RIFF"));
+    }
+
+    #[tokio::test]
+    async fn test_openai_request() {
+        let (base_url, rx) = serve_once("200 OK", r#"{"text":"hi"}"#, Duration::ZERO);
+        let backend = HttpBackend::new(config(&base_url, HttpApi::OpenAi)).unwrap();
+
+        assert_eq!(backend.transcribe(&test_samples(), None).await.unwrap(), "hi");
+
+        let request = rx.recv().unwrap();
+        assert_eq!(request.request_line, "POST /v1/audio/transcriptions HTTP/1.1");
+        assert!(request.body.contains("name=\"model\""));
+        assert!(request.body.contains("whisper-1"));
+    }
+
+    #[tokio::test]
+    async fn test_profile_language_overrides_config() {
+        let (base_url, rx) = serve_once("200 OK", r#"{"text":"hallo"}"#, Duration::ZERO);
+        let mut config = config(&base_url, HttpApi::OpenAi);
+        config.language = Some("en".to_string());
+        let backend = HttpBackend::new(config).unwrap();
+
+        assert_eq!(backend.transcribe(&test_samples(), Some("de")).await.unwrap(), "hallo");
+
+        let body = rx.recv().unwrap().body;
+        let language = body.split("name=\"language\"").nth(1).unwrap();
+        assert!(language.trim_start().starts_with("de"));
+    }
+
+    #[tokio::test]
+    async fn test_server_error() {
+        let (base_url, _rx) = serve_once(
+            "500 Internal Server Error",
+            r#"{"error":"model not loaded"}"#,
+            Duration::ZERO,
+        );
+        let backend = HttpBackend::new(config(&base_url, HttpApi::WhisperCpp)).unwrap();
+
+        match backend.transcribe(&test_samples(), None).await {
+            Err(TranscriptionError::Transcription(message)) => {
+                assert!(message.contains("500"));
+                assert!(message.contains("model not loaded"));
+            }
+            other => panic!("unexpected result: {:?}", other),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_timeout() {
+        let (base_url, _rx) = serve_once("200 OK", r#"{"text":"late"}"#, Duration::from_secs(2));
+        let mut config = config(&base_url, HttpApi::WhisperCpp);
+        config.timeout_ms = 200;
+        let backend = HttpBackend::new(config).unwrap();
+
+        assert_eq!(
+            backend.transcribe(&test_samples(), None).await,
+            Err(TranscriptionError::Timeout(Duration::from_millis(200)))
+        );
+    }
+
+    #[tokio::test]
+    async fn test_unreachable_server() {
+        // Bind and drop to get a port nothing listens on
+        let port = TcpListener::bind("127.0.0.1:0")
+            .unwrap()
+            .local_addr()
+            .unwrap()
+            .port();
+        let backend = HttpBackend::new(config(
+            &format!("http://127.0.0.1:{}", port),
+            HttpApi::WhisperCpp,
+        ))
+        .unwrap();
+
+        assert!(matches!(
+            backend.transcribe(&test_samples(), None).await,
+            Err(TranscriptionError::Io(_))
+        ));
+        assert!(!backend.is_ready().await);
+    }
+}
@@ -0,0 +1,376 @@
+//! Platform-neutral keys. A key is stored by its W3C `KeyboardEvent.code` name, so
+//! a config file works on every platform, and is mapped to the platform's own
+//! keycode only where key events come in or are sent.
+
+use serde::{Deserialize, Serialize};
+
+use crate::config::Modifier;
+
+/// A physical key, named like the W3C `KeyboardEvent.code` the settings window
+/// records. Left and right modifiers are separate keys.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum Key {
+    KeyA,
+    KeyB,
+    KeyC,
+    KeyD,
+    KeyE,
+    KeyF,
+    KeyG,
+    KeyH,
+    KeyI,
+    KeyJ,
+    KeyK,
+    KeyL,
+    KeyM,
+    KeyN,
+    KeyO,
+    KeyP,
+    KeyQ,
+    KeyR,
+    KeyS,
+    KeyT,
+    KeyU,
+    KeyV,
+    KeyW,
+    KeyX,
+    KeyY,
+    KeyZ,
+    Digit0,
+    Digit1,
+    Digit2,
+    Digit3,
+    Digit4,
+    Digit5,
+    Digit6,
+    Digit7,
+    Digit8,
+    Digit9,
+    Minus,
+    Equal,
+    BracketLeft,
+    BracketRight,
+    Backslash,
+    Semicolon,
+    Quote,
+    Backquote,
+    Comma,
+    Period,
+    Slash,
+    Enter,
+    Tab,
+    Space,
+    Backspace,
+    Escape,
+    Delete,
+    Home,
+    End,
+    PageUp,
+    PageDown,
+    ArrowLeft,
+    ArrowRight,
+    ArrowDown,
+    ArrowUp,
+    F1,
+    F2,
+    F3,
+    F4,
+    F5,
+    F6,
+    F7,
+    F8,
+    F9,
+    F10,
+    F11,
+    F12,
+    F13,
+    F14,
+    F15,
+    F16,
+    F17,
+    F18,
+    F19,
+    F20,
+    Numpad0,
+    Numpad1,
+    Numpad2,
+    Numpad3,
+    Numpad4,
+    Numpad5,
+    Numpad6,
+    Numpad7,
+    Numpad8,
+    Numpad9,
+    NumpadDecimal,
+    NumpadMultiply,
+    NumpadAdd,
+    NumpadSubtract,
+    NumpadDivide,
+    NumpadEnter,
+    NumpadEqual,
+    NumLock,
+    MetaLeft,
+    MetaRight,
+    ShiftLeft,
+    ShiftRight,
+    AltLeft,
+    AltRight,
+    ControlLeft,
+    ControlRight,
+    CapsLock,
+    Fn,
+}
+
+/// One row of the key table
+struct KeyInfo {
+    key: Key,
+    code: &'static str,
+    /// macOS virtual keycode
+    mac: i64,
+    /// Linux input event code
+    #[cfg_attr(not(any(target_os = "linux", test)), allow(dead_code))]
+    evdev: u16,
+    label: &'static str,
+}
+
+const fn info(
+    key: Key,
+    code: &'static str,
+    mac: i64,
+    evdev: u16,
+    label: &'static str,
+) -> KeyInfo {
+    KeyInfo {
+        key,
+        code,
+        mac,
+        evdev,
+        label,
+    }
+}
+
+/// Every key with its per-platform keycodes, in the order of the enum.
+/// macOS: https://eastmanreference.com/complete-list-of-applescript-key-codes
+/// evdev: linux/input-event-codes.h
+const KEYS: &[KeyInfo] = &[
+    info(Key::KeyA, "KeyA", 0, 30, "A"),
+    info(Key::KeyB, "KeyB", 11, 48, "B"),
+    info(Key::KeyC, "KeyC", 8, 46, "C"),
+    info(Key::KeyD, "KeyD", 2, 32, "D"),
+    info(Key::KeyE, "KeyE", 14, 18, "E"),
+    info(Key::KeyF, "KeyF", 3, 33, "F"),
+    info(Key::KeyG, "KeyG", 5, 34, "G"),
+    info(Key::KeyH, "KeyH", 4, 35, "H"),
+    info(Key::KeyI, "KeyI", 34, 23, "I"),
+    info(Key::KeyJ, "KeyJ", 38, 36, "J"),
+    info(Key::KeyK, "KeyK", 40, 37, "K"),
+    info(Key::KeyL, "KeyL", 37, 38, "L"),
+    info(Key::KeyM, "KeyM", 46, 50, "M"),
+    info(Key::KeyN, "KeyN", 45, 49, "N"),
+    info(Key::KeyO, "KeyO", 31, 24, "O"),
+    info(Key::KeyP, "KeyP", 35, 25, "P"),
+    info(Key::KeyQ, "KeyQ", 12, 16, "Q"),
+    info(Key::KeyR, "KeyR", 15, 19, "R"),
+    info(Key::KeyS, "KeyS", 1, 31, "S"),
+    info(Key::KeyT, "KeyT", 17, 20, "T"),
+    info(Key::KeyU, "KeyU", 32, 22, "U"),
+    info(Key::KeyV, "KeyV", 9, 47, "V"),
+    info(Key::KeyW, "KeyW", 13, 17, "W"),
+    info(Key::KeyX, "KeyX", 7, 45, "X"),
+    info(Key::KeyY, "KeyY", 16, 21, "Y"),
+    info(Key::KeyZ, "KeyZ", 6, 44, "Z"),
+    info(Key::Digit0, "Digit0", 29, 11, "0"),
+    info(Key::Digit1, "Digit1", 18, 2, "1"),
+    info(Key::Digit2, "Digit2", 19, 3, "2"),
+    info(Key::Digit3, "Digit3", 20, 4, "3"),
+    info(Key::Digit4, "Digit4", 21, 5, "4"),
+    info(Key::Digit5, "Digit5", 23, 6, "5"),
+    info(Key::Digit6, "Digit6", 22, 7, "6"),
+    info(Key::Digit7, "Digit7", 26, 8, "7"),
+    info(Key::Digit8, "Digit8", 28, 9, "8"),
+    info(Key::Digit9, "Digit9", 25, 10, "9"),
+    info(Key::Minus, "Minus", 27, 12, "-"),
+    info(Key::Equal, "Equal", 24, 13, "="),
+    info(Key::BracketLeft, "BracketLeft", 33, 26, "["),
+    info(Key::BracketRight, "BracketRight", 30, 27, "]"),
+    info(Key::Backslash, "Backslash", 42, 43, "\\"),
+    info(Key::Semicolon, "Semicolon", 41, 39, ";"),
+    info(Key::Quote, "Quote", 39, 40, "'"),
+    info(Key::Backquote, "Backquote", 50, 41, "`"),
+    info(Key::Comma, "Comma", 43, 51, ","),
+    info(Key::Period, "Period", 47, 52, "."),
+    info(Key::Slash, "Slash", 44, 53, "/"),
+    info(Key::Enter, "Enter", 36, 28, "Return"),
+    info(Key::Tab, "Tab", 48, 15, "Tab"),
+    info(Key::Space, "Space", 49, 57, "Space"),
+    info(Key::Backspace, "Backspace", 51, 14, "Delete"),
+    info(Key::Escape, "Escape", 53, 1, "Escape"),
+    info(Key::Delete, "Delete", 117, 111, "⌦"),
+    info(Key::Home, "Home", 115, 102, "Home"),
+    info(Key::End, "End", 119, 107, "End"),
+    info(Key::PageUp, "PageUp", 116, 104, "Page Up"),
+    info(Key::PageDown, "PageDown", 121, 109, "Page Down"),
+    info(Key::ArrowLeft, "ArrowLeft", 123, 105, "←"),
+    info(Key::ArrowRight, "ArrowRight", 124, 106, "→"),
+    info(Key::ArrowDown, "ArrowDown", 125, 108, "↓"),
+    info(Key::ArrowUp, "ArrowUp", 126, 103, "↑"),
+    info(Key::F1, "F1", 122, 59, "F1"),
+    info(Key::F2, "F2", 120, 60, "F2"),
+    info(Key::F3, "F3", 99, 61, "F3"),
+    info(Key::F4, "F4", 118, 62, "F4"),
+    info(Key::F5, "F5", 96, 63, "F5"),
+    info(Key::F6, "F6", 97, 64, "F6"),
+    info(Key::F7, "F7", 98, 65, "F7"),
+    info(Key::F8, "F8", 100, 66, "F8"),
+    info(Key::F9, "F9", 101, 67, "F9"),
+    info(Key::F10, "F10", 109, 68, "F10"),
+    info(Key::F11, "F11", 103, 87, "F11"),
+    info(Key::F12, "F12", 111, 88, "F12"),
+    info(Key::F13, "F13", 105, 183, "F13"),
+    info(Key::F14, "F14", 107, 184, "F14"),
+    info(Key::F15, "F15", 113, 185, "F15"),
+    info(Key::F16, "F16", 106, 186, "F16"),
+    info(Key::F17, "F17", 64, 187, "F17"),
+    info(Key::F18, "F18", 79, 188, "F18"),
+    info(Key::F19, "F19", 80, 189, "F19"),
+    info(Key::F20, "F20", 90, 190, "F20"),
+    info(Key::Numpad0, "Numpad0", 82, 82, "Num 0"),
+    info(Key::Numpad1, "Numpad1", 83, 79, "Num 1"),
+    info(Key::Numpad2, "Numpad2", 84, 80, "Num 2"),
+    info(Key::Numpad3, "Numpad3", 85, 81, "Num 3"),
+    info(Key::Numpad4, "Numpad4", 86, 75, "Num 4"),
+    info(Key::Numpad5, "Numpad5", 87, 76, "Num 5"),
+    info(Key::Numpad6, "Numpad6", 88, 77, "Num 6"),
+    info(Key::Numpad7, "Numpad7", 89, 71, "Num 7"),
+    info(Key::Numpad8, "Numpad8", 91, 72, "Num 8"),
+    info(Key::Numpad9, "Numpad9", 92, 73, "Num 9"),
+    info(Key::NumpadDecimal, "NumpadDecimal", 65, 83, "Num ."),
+    info(Key::NumpadMultiply, "NumpadMultiply", 67, 55, "Num *"),
+    info(Key::NumpadAdd, "NumpadAdd", 69, 78, "Num +"),
+    info(Key::NumpadSubtract, "NumpadSubtract", 78, 74, "Num -"),
+    info(Key::NumpadDivide, "NumpadDivide", 75, 98, "Num /"),
+    info(Key::NumpadEnter, "NumpadEnter", 76, 96, "Num Enter"),
+    info(Key::NumpadEqual, "NumpadEqual", 81, 117, "Num ="),
+    info(Key::NumLock, "NumLock", 71, 69, "Num Lock"),
+    info(Key::MetaLeft, "MetaLeft", 55, 125, "Left ⌘"),
+    info(Key::MetaRight, "MetaRight", 54, 126, "Right ⌘"),
+    info(Key::ShiftLeft, "ShiftLeft", 56, 42, "Left ⇧"),
+    info(Key::ShiftRight, "ShiftRight", 60, 54, "Right ⇧"),
+    info(Key::AltLeft, "AltLeft", 58, 56, "Left ⌥"),
+    info(Key::AltRight, "AltRight", 61, 100, "Right ⌥"),
+    info(Key::ControlLeft, "ControlLeft", 59, 29, "Left ⌃"),
+    info(Key::ControlRight, "ControlRight", 62, 97, "Right ⌃"),
+    info(Key::CapsLock, "CapsLock", 57, 58, "⇪ Caps Lock"),
+    info(Key::Fn, "Fn", 63, 464, "fn"),
+];
+
+impl Key {
+    fn info(self) -> &'static KeyInfo {
+        &KEYS[self as usize]
+    }
+
+    fn find(matches: impl Fn(&KeyInfo) -> bool) -> Option<Key> {
+        KEYS.iter().find(|info| matches(info)).map(|info| info.key)
+    }
+
+    /// Parse a W3C `KeyboardEvent.code`. Digits are also accepted as `Key1`,
+    /// which older settings windows recorded.
+    pub fn from_code(code: &str) -> Option<Key> {
+        let digit = code
+            .strip_prefix("Key")
+            .filter(|digit| digit.len() == 1 && digit.bytes().all(|b| b.is_ascii_digit()));
+        if let Some(digit) = digit {
+            return Self::from_code(&format!("Digit{}", digit));
+        }
+        Self::find(|info| info.code == code)
+    }
+
+    /// The W3C `KeyboardEvent.code`, as stored in the config
+    pub fn code(self) -> &'static str {
+        self.info().code
+    }
+
+    pub fn from_mac_keycode(keycode: i64) -> Option<Key> {
+        Self::find(|info| info.mac == keycode)
+    }
+
+    #[cfg(any(target_os = "macos", test))]
+    pub fn mac_keycode(self) -> i64 {
+        self.info().mac
+    }
+
+    #[cfg(any(target_os = "linux", test))]
+    pub fn from_evdev_code(code: u16) -> Option<Key> {
+        Self::find(|info| info.evdev == code)
+    }
+
+    #[cfg(test)]
+    pub fn evdev_code(self) -> u16 {
+        self.info().evdev
+    }
+
+    /// Display label (e.g., "Right ⌘" or "Space")
+    pub fn label(self) -> &'static str {
+        self.info().label
+    }
+
+    /// The modifier this key is one side of, if any
+    pub fn modifier(self) -> Option<Modifier> {
+        match self {
+            Key::MetaLeft | Key::MetaRight => Some(Modifier::Command),
+            Key::ShiftLeft | Key::ShiftRight => Some(Modifier::Shift),
+            Key::AltLeft | Key::AltRight => Some(Modifier::Option),
+            Key::ControlLeft | Key::ControlRight => Some(Modifier::Control),
+            Key::Fn => Some(Modifier::Function),
+            _ => None,
+        }
+    }
+
+    /// Modifiers and Caps Lock, which macOS reports as flag changes
+    pub fn is_modifier(self) -> bool {
+        self.modifier().is_some() || self == Key::CapsLock
+    }
+}
+
+/// Build a display label from keys, modifiers first
+pub fn build_label(keys: &[Key]) -> String {
+    let mut keys = keys.to_vec();
+    keys.sort_by_key(|key| !key.is_modifier());
+    keys.iter().map(|key| key.label()).collect::<Vec<_>>().join("+")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashSet;
+
+    #[test]
+    fn test_table_is_consistent() {
+        assert_eq!(KEYS.len(), Key::Fn as usize + 1);
+        for (i, info) in KEYS.iter().enumerate() {
+            assert_eq!(info.key as usize, i, "{} out of order", info.code);
+        }
+
+        let mut macs = HashSet::new();
+        let mut evdevs = HashSet::new();
+        for info in KEYS {
+            // The config stores keys under their serde name
+            assert_eq!(serde_json::to_value(info.key).unwrap(), info.code);
+            assert!(macs.insert(info.mac), "macOS keycode {} twice", info.mac);
+            assert!(evdevs.insert(info.evdev), "evdev code {} twice", info.evdev);
+        }
+    }
+
+    #[test]
+    fn test_platform_keycodes() {
+        assert_eq!(Key::from_code("MetaRight"), Some(Key::MetaRight));
+        assert_eq!(Key::MetaRight.mac_keycode(), 54);
+        assert_eq!(Key::from_mac_keycode(49), Some(Key::Space));
+        assert_eq!(Key::from_evdev_code(126), Some(Key::MetaRight));
+        assert_eq!(Key::ShiftRight.evdev_code(), 54);
+        assert_eq!(Key::from_code("Key1"), Some(Key::Digit1));
+        assert_eq!(Key::from_code("Key10"), None);
+        // Mouse button
+        assert_eq!(Key::from_evdev_code(0x110), None);
+    }
+}
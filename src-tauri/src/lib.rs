@@ -1,33 +1,111 @@
+#[cfg(target_os = "macos")]
 mod app_nap;
 mod audio;
+mod audio_file;
+mod backend;
+mod channels;
+mod cli;
 mod config;
+mod dsp;
+#[cfg(target_os = "linux")]
+mod evdev_hotkey;
+#[cfg(target_os = "macos")]
+mod event_tap;
 mod hotkey;
+mod hotkey_machine;
+mod http_backend;
+mod keys;
+mod meter;
+mod mock_backend;
+mod pipeline;
+mod post_processing;
+mod resample;
+mod ring_buffer;
+mod segment;
 mod sidecar;
+mod sidecar_protocol;
 mod text_insertion;
 mod tray;
+mod vad;
+mod wav;
 
+use std::path::Path;
 use std::sync::Arc;
-use tauri::{Emitter, Manager, WindowEvent};
+use tauri::{Emitter, Manager, RunEvent, WindowEvent};
 use tokio::sync::Mutex;
 
-pub use config::HotkeyConfig;
+use backend::{BackendKind, TranscriptionBackend};
+use pipeline::{PendingTranscription, TimedText, TranscriptionCanceller, TranscriptionProgress};
+use dsp::DspChain;
+use segment::Segmenter;
+
+pub use config::{AppConfig, DictationProfile, HotkeyBinding, HotkeyConfig, HotkeyMode};
+pub use keys::Key;
 
 pub struct AppState {
     pub is_recording: Arc<Mutex<bool>>,
-    pub sidecar_ready: Arc<Mutex<bool>>,
-    pub current_hotkey: Arc<std::sync::Mutex<HotkeyConfig>>,
+    /// Full app configuration as loaded from (and saved to) disk
+    pub config: Arc<std::sync::Mutex<AppConfig>>,
+    /// Speech-to-text engine used for all transcriptions
+    pub backend: Arc<dyn TranscriptionBackend>,
+    /// Kind of `backend`; differs from the config when the sidecar stands in for
+    /// a backend that couldn't be created
+    pub backend_kind: BackendKind,
+    /// Transcription receiving audio from the current recording
+    pub pending_transcription: std::sync::Mutex<Option<PendingTranscription>>,
+    /// Cancels the transcription in flight, from key press until the text is inserted.
+    /// Tagged with the generation of the recording it belongs to.
+    pub transcription_canceller: std::sync::Mutex<Option<(u64, TranscriptionCanceller)>>,
 }
 
 impl AppState {
-    pub fn new(hotkey: HotkeyConfig) -> Self {
+    pub fn new(config: AppConfig) -> Self {
+        let (backend_kind, backend) = create_backend(&config);
+        Self::with_backend(config, backend_kind, backend)
+    }
+
+    pub fn with_backend(
+        config: AppConfig,
+        backend_kind: BackendKind,
+        backend: Arc<dyn TranscriptionBackend>,
+    ) -> Self {
         Self {
             is_recording: Arc::new(Mutex::new(false)),
-            sidecar_ready: Arc::new(Mutex::new(false)),
-            current_hotkey: Arc::new(std::sync::Mutex::new(hotkey)),
+            config: Arc::new(std::sync::Mutex::new(config)),
+            backend,
+            backend_kind,
+            pending_transcription: std::sync::Mutex::new(None),
+            transcription_canceller: std::sync::Mutex::new(None),
         }
     }
 }
 
+/// Instantiate the transcription backend selected in the config, and say which
+/// kind it turned out to be
+fn create_backend(config: &AppConfig) -> (BackendKind, Arc<dyn TranscriptionBackend>) {
+    let sidecar = || -> (BackendKind, Arc<dyn TranscriptionBackend>) {
+        (
+            BackendKind::Sidecar,
+            Arc::new(sidecar::SidecarBackend::new(config.sidecar.clone())),
+        )
+    };
+    match config.backend {
+        BackendKind::Sidecar => sidecar(),
+        BackendKind::Http => match http_backend::HttpBackend::new(config.http.clone()) {
+            Ok(backend) => (BackendKind::Http, Arc::new(backend)),
+            Err(e) => {
+                // Only fails if the TLS backend can't initialize - fall back to the sidecar
+                eprintln!("[BACKEND] {}, using sidecar instead", e);
+                sidecar()
+            }
+        },
+        BackendKind::Mock => (
+            BackendKind::Mock,
+            Arc::new(mock_backend::MockBackend::from_config(&config.mock)),
+        ),
+    }
+}
+
 #[tauri::command]
 async fn get_recording_state(state: tauri::State<'_, AppState>) -> Result<bool, String> {
     let is_recording = state.is_recording.lock().await;
@@ -36,8 +114,17 @@ async fn get_recording_state(state: tauri::State<'_, AppState>) -> Result<bool,
 
 #[tauri::command]
 async fn get_sidecar_ready(state: tauri::State<'_, AppState>) -> Result<bool, String> {
-    let sidecar_ready = state.sidecar_ready.lock().await;
-    Ok(*sidecar_ready)
+    Ok(state.backend.is_ready().await)
+}
+
+#[tauri::command]
+async fn get_backend_info(state: tauri::State<'_, AppState>) -> Result<serde_json::Value, String> {
+    let capabilities = state.backend.capabilities().await;
+    Ok(serde_json::json!({
+        "name": state.backend.name(),
+        "ready": state.backend.is_ready().await,
+        "capabilities": capabilities
+    }))
 }
 
 #[tauri::command]
@@ -68,179 +155,464 @@ async fn open_input_monitoring_settings() -> Result<(), String> {
     text_insertion::open_input_monitoring_settings()
 }
 
+/// Transcript of an audio file
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct FileTranscript {
+    pub text: String,
+    /// Text of each segment with where it was spoken
+    pub segments: Vec<TimedText>,
+}
+
+/// Decode an audio file, clean it up and transcribe it segment by segment.
+/// `on_progress` is called after each segment.
+pub(crate) async fn transcribe_file<P>(
+    state: &AppState,
+    path: &Path,
+    on_progress: P,
+) -> Result<FileTranscript, String>
+where
+    P: Fn(TranscriptionProgress),
+{
+    let owned_path = path.to_path_buf();
+    let samples = tokio::task::spawn_blocking(move || audio_file::decode_audio_file(&owned_path))
+        .await
+        .map_err(|e| format!("Decoding failed: {}", e))??;
+    let (mut dsp, segmenter) = {
+        let config = state.config.lock().map_err(|e| e.to_string())?;
+        (DspChain::new(&config.dsp), Segmenter::from_config(&config))
+    };
+    let samples = dsp.process(&samples);
+    println!(
+        "[IMPORT] Transcribing {:.1}s of audio from {:?}",
+        samples.len() as f64 / audio::TARGET_SAMPLE_RATE as f64,
+        path
+    );
+
+    let segments =
+        pipeline::transcribe_timed(state.backend.as_ref(), &samples, segmenter, on_progress)
+            .await
+            .map_err(|e| e.to_string())?;
+    let text = segments
+        .iter()
+        .map(|timed| timed.text.as_str())
+        .collect::<Vec<_>>()
+        .join(" ");
+    Ok(FileTranscript { text, segments })
+}
+
 #[tauri::command]
 async fn transcribe_audio(
     audio_path: String,
     app_handle: tauri::AppHandle,
-) -> Result<String, String> {
-    sidecar::transcribe(&app_handle, &audio_path).await
+    state: tauri::State<'_, AppState>,
+) -> Result<FileTranscript, String> {
+    transcribe_file(&state, Path::new(&audio_path), |progress| {
+        let _ = app_handle.emit("transcription-progress", progress);
+    })
+    .await
+}
+
+fn segmenter_from_state(state: &AppState) -> Result<Segmenter, String> {
+    let config = state.config.lock().map_err(|e| e.to_string())?;
+    Ok(Segmenter::from_config(&config))
 }
 
 #[tauri::command]
-async fn test_start_recording() -> Result<(), String> {
+async fn test_start_recording(
+    app_handle: tauri::AppHandle,
+    state: tauri::State<'_, AppState>,
+) -> Result<(), String> {
     println!("[TEST] Starting recording...");
-    audio::start_recording()
+    let (audio_config, vad, dsp) = {
+        let config = state.config.lock().map_err(|e| e.to_string())?;
+        (config.audio.clone(), config.vad.clone(), config.dsp.clone())
+    };
+    let levels = audio::level_emitter(app_handle);
+    if let Some(fallback) =
+        audio::start_recording(None, Some(levels), None, &audio_config, vad, &dsp)?
+    {
+        println!("[TEST] Recording from fallback device {}", fallback.used);
+    }
+    Ok(())
 }
 
 #[tauri::command]
-async fn test_stop_and_transcribe(app_handle: tauri::AppHandle) -> Result<String, String> {
+async fn test_stop_and_transcribe(state: tauri::State<'_, AppState>) -> Result<String, String> {
     println!("[TEST] Stopping recording...");
-    let audio_path = audio::stop_recording()?;
-    println!("[TEST] Audio saved to: {}", audio_path);
+    let recording = audio::stop_recording()?;
+    tray::set_mic_open(audio::is_mic_open());
+    let samples = recording.samples;
+    println!(
+        "[TEST] Recorded {} samples ({:?})",
+        samples.len(),
+        recording.diagnostics
+    );
+    if samples.is_empty() {
+        return Err("No speech detected".to_string());
+    }
 
     println!("[TEST] Starting transcription...");
-    let result = sidecar::transcribe(&app_handle, &audio_path).await?;
+    let segmenter = segmenter_from_state(&state)?;
+    let result =
+        pipeline::transcribe_samples(state.backend.as_ref(), &samples, segmenter).await?;
     println!("[TEST] Transcription result: {}", result);
 
-    // Clean up
-    let _ = std::fs::remove_file(&audio_path);
-
     Ok(result)
 }
 
+#[tauri::command]
+async fn list_input_devices() -> Result<Vec<audio::InputDeviceInfo>, String> {
+    audio::list_input_devices()
+}
+
+#[tauri::command]
+async fn get_audio_config(
+    state: tauri::State<'_, AppState>,
+) -> Result<config::AudioConfig, String> {
+    let app_config = state.config.lock().map_err(|e| e.to_string())?;
+    Ok(app_config.audio.clone())
+}
+
+/// Choose the microphone by name; `None` follows the system default
+#[tauri::command]
+async fn set_input_device(
+    name: Option<String>,
+    state: tauri::State<'_, AppState>,
+) -> Result<config::AudioConfig, String> {
+    let app_config = {
+        let mut app_config = state.config.lock().map_err(|e| e.to_string())?;
+        app_config.audio.input_device = name;
+        app_config.clone()
+    };
+    config::save_config(&app_config)?;
+
+    println!("[AUDIO] Input device set to: {:?}", app_config.audio.input_device);
+    if app_config.audio.keep_mic_open {
+        apply_preroll(&app_config.audio)?;
+    }
+    Ok(app_config.audio)
+}
+
+/// Microphones to try, in order, when the preferred one is not connected
+#[tauri::command]
+async fn set_fallback_devices(
+    names: Vec<String>,
+    state: tauri::State<'_, AppState>,
+) -> Result<config::AudioConfig, String> {
+    let app_config = {
+        let mut app_config = state.config.lock().map_err(|e| e.to_string())?;
+        app_config.audio.fallback_devices = names;
+        app_config.clone()
+    };
+    config::save_config(&app_config)?;
+
+    println!("[AUDIO] Fallback devices set to: {:?}", app_config.audio.fallback_devices);
+    if app_config.audio.keep_mic_open {
+        apply_preroll(&app_config.audio)?;
+    }
+    Ok(app_config.audio)
+}
+
+/// Apply the pre-roll settings and update the tray indicator
+fn apply_preroll(audio: &config::AudioConfig) -> Result<(), String> {
+    let result = audio::configure_preroll(audio);
+    tray::set_mic_open(matches!(result, Ok(true)));
+    result.map(|_| ())
+}
+
+/// Keep the microphone open between recordings so the first word isn't clipped,
+/// with `preroll_ms` of audio from before the hotkey if given
+#[tauri::command]
+async fn set_keep_mic_open(
+    enabled: bool,
+    preroll_ms: Option<u64>,
+    state: tauri::State<'_, AppState>,
+) -> Result<config::AudioConfig, String> {
+    let app_config = {
+        let mut app_config = state.config.lock().map_err(|e| e.to_string())?;
+        app_config.audio.keep_mic_open = enabled;
+        if let Some(preroll_ms) = preroll_ms {
+            app_config.audio.preroll_ms = preroll_ms;
+        }
+        app_config.clone()
+    };
+    config::save_config(&app_config)?;
+
+    println!(
+        "[AUDIO] Keep microphone open: {} ({}ms pre-roll)",
+        enabled, app_config.audio.preroll_ms
+    );
+    apply_preroll(&app_config.audio)?;
+    Ok(app_config.audio)
+}
+
+/// Choose which channels of the input device are recorded
+#[tauri::command]
+async fn set_input_channels(
+    channels: config::ChannelSelection,
+    state: tauri::State<'_, AppState>,
+) -> Result<config::AudioConfig, String> {
+    let app_config = {
+        let mut app_config = state.config.lock().map_err(|e| e.to_string())?;
+        app_config.audio.channels = channels;
+        app_config.clone()
+    };
+    config::save_config(&app_config)?;
+
+    println!("[AUDIO] Input channels set to: {:?}", app_config.audio.channels);
+    if app_config.audio.keep_mic_open {
+        apply_preroll(&app_config.audio)?;
+    }
+    Ok(app_config.audio)
+}
+
 #[tauri::command]
 async fn quit_app(app_handle: tauri::AppHandle) {
     app_handle.exit(0);
 }
 
 #[tauri::command]
-async fn get_current_hotkey(state: tauri::State<'_, AppState>) -> Result<HotkeyConfig, String> {
-    let hotkey = state.current_hotkey.lock().map_err(|e| e.to_string())?;
-    Ok(hotkey.clone())
+async fn list_hotkey_bindings(
+    state: tauri::State<'_, AppState>,
+) -> Result<Vec<HotkeyBinding>, String> {
+    let app_config = state.config.lock().map_err(|e| e.to_string())?;
+    Ok(app_config.bindings.clone())
 }
 
 #[derive(serde::Deserialize)]
 pub struct SetHotkeyParams {
-    /// JavaScript event.code values for the keys
+    /// JavaScript event.code values for the keys; modifiers name their side.
+    /// Empty keeps the binding's current keys.
+    #[serde(default)]
     pub codes: Vec<String>,
-    /// Keyboard locations (1=left, 2=right) for modifiers
-    pub locations: Vec<u32>,
+    /// Recording mode; the current one is kept when omitted
+    #[serde(default)]
+    pub mode: Option<HotkeyMode>,
+    /// Profile to dictate with; the current one is kept when omitted
+    #[serde(default)]
+    pub profile: Option<String>,
+    /// Gesture settings; the current ones are kept when omitted
+    #[serde(default)]
+    pub gestures: Option<config::GestureConfig>,
 }
 
-#[tauri::command]
-async fn set_hotkey(
-    params: SetHotkeyParams,
-    state: tauri::State<'_, AppState>,
-) -> Result<HotkeyConfig, String> {
-    use config::{build_label, is_modifier_keycode, js_code_to_keycode, Modifier};
+/// Build a hotkey from the keys recorded in the settings window
+fn hotkey_from_codes(codes: &[String], current: &HotkeyConfig) -> Result<HotkeyConfig, String> {
+    let mut keys: Vec<Key> = Vec::new();
+    let mut modifier_keys: Vec<Key> = Vec::new();
+    let mut modifiers: Vec<config::Modifier> = Vec::new();
+    let mut non_modifier_key: Option<Key> = None;
 
-    if params.codes.is_empty() {
-        return Err("At least one key is required".to_string());
-    }
+    for code in codes {
+        let key = Key::from_code(code).ok_or_else(|| format!("Unknown key code: {}", code))?;
+        keys.push(key);
 
-    // Convert JS codes to keycodes
-    let mut keycodes: Vec<i64> = Vec::new();
-    let mut modifier_locations: Vec<(i64, u32)> = Vec::new();
-    let mut modifiers: Vec<Modifier> = Vec::new();
-    let mut non_modifier_key: Option<i64> = None;
-
-    for (i, code) in params.codes.iter().enumerate() {
-        let keycode = js_code_to_keycode(code)
-            .ok_or_else(|| format!("Unknown key code: {}", code))?;
-        let location = params.locations.get(i).copied().unwrap_or(0);
-
-        keycodes.push(keycode);
-
-        if is_modifier_keycode(keycode) {
-            modifier_locations.push((keycode, location));
-            // Determine modifier type
-            match keycode {
-                54 | 55 => modifiers.push(Modifier::Command),
-                56 | 60 => modifiers.push(Modifier::Shift),
-                58 | 61 => modifiers.push(Modifier::Option),
-                59 | 62 => modifiers.push(Modifier::Control),
-                63 => modifiers.push(Modifier::Function),
-                _ => {}
-            }
+        if key.is_modifier() {
+            modifier_keys.push(key);
+            modifiers.extend(key.modifier());
         } else {
-            non_modifier_key = Some(keycode);
+            non_modifier_key = Some(key);
         }
     }
 
-    let label = build_label(&keycodes);
+    let label = keys::build_label(&keys);
 
-    let new_hotkey = HotkeyConfig {
+    Ok(HotkeyConfig {
         modifiers,
         key: non_modifier_key,
-        modifier_locations,
+        modifier_keys,
         label,
-    };
+        // The cancel key isn't part of the recorded combination - keep the current one
+        cancel_key: current.cancel_key,
+        mode: current.mode,
+        gestures: current.gestures,
+    })
+}
 
-    // Update the hotkey in state
-    {
-        let mut hotkey = state.current_hotkey.lock().map_err(|e| e.to_string())?;
-        *hotkey = new_hotkey.clone();
+/// `current` with the changes in `params` applied
+fn binding_from_params(
+    params: SetHotkeyParams,
+    current: &HotkeyBinding,
+) -> Result<HotkeyBinding, String> {
+    let mut hotkey = if params.codes.is_empty() {
+        current.hotkey.clone()
+    } else {
+        hotkey_from_codes(&params.codes, &current.hotkey)?
+    };
+    if let Some(mode) = params.mode {
+        hotkey.mode = mode;
     }
+    if let Some(gestures) = params.gestures {
+        hotkey.gestures = gestures;
+    }
+    Ok(HotkeyBinding {
+        hotkey,
+        profile: params.profile.unwrap_or_else(|| current.profile.clone()),
+    })
+}
 
-    // Clear held keys when config changes
-    hotkey::clear_held_keys();
-
-    // Save to config file
-    let mut app_config = config::load_config();
-    app_config.hotkey = new_hotkey.clone();
+/// Apply `change` to the config, save it, and detect the resulting hotkeys
+fn update_config<T>(
+    state: &AppState,
+    change: impl FnOnce(&mut AppConfig) -> Result<T, String>,
+) -> Result<T, String> {
+    let (result, app_config) = {
+        let mut app_config = state.config.lock().map_err(|e| e.to_string())?;
+        let mut updated = app_config.clone();
+        let result = change(&mut updated)?;
+        *app_config = updated.clone();
+        (result, updated)
+    };
+    // Detect the new hotkeys, starting with no keys held
+    hotkey::set_hotkey_bindings(&app_config.bindings);
     config::save_config(&app_config)?;
+    Ok(result)
+}
 
-    println!("[HOTKEY] Updated hotkey to: {}", new_hotkey.label);
+#[tauri::command]
+async fn add_hotkey_binding(
+    params: SetHotkeyParams,
+    state: tauri::State<'_, AppState>,
+) -> Result<Vec<HotkeyBinding>, String> {
+    if params.codes.is_empty() {
+        return Err("At least one key is required".to_string());
+    }
+    update_config(&state, |app_config| {
+        let binding = binding_from_params(params, &HotkeyBinding::default())?;
+        println!("[HOTKEY] Added {} for '{}'", binding.hotkey.label, binding.profile);
+        app_config.add_binding(binding)?;
+        Ok(app_config.bindings.clone())
+    })
+}
 
-    Ok(new_hotkey)
+#[tauri::command]
+async fn update_hotkey_binding(
+    index: usize,
+    params: SetHotkeyParams,
+    state: tauri::State<'_, AppState>,
+) -> Result<Vec<HotkeyBinding>, String> {
+    update_config(&state, |app_config| {
+        let current = app_config
+            .bindings
+            .get(index)
+            .ok_or_else(|| format!("No hotkey binding {}", index))?;
+        let binding = binding_from_params(params, current)?;
+        println!("[HOTKEY] Updated hotkey to: {}", binding.hotkey.label);
+        app_config.update_binding(index, binding)?;
+        Ok(app_config.bindings.clone())
+    })
 }
 
 #[tauri::command]
-async fn test_sidecar(app_handle: tauri::AppHandle) -> Result<String, String> {
-    println!("[TEST] Testing sidecar with sample audio...");
+async fn remove_hotkey_binding(
+    index: usize,
+    state: tauri::State<'_, AppState>,
+) -> Result<Vec<HotkeyBinding>, String> {
+    update_config(&state, |app_config| {
+        app_config.remove_binding(index)?;
+        Ok(app_config.bindings.clone())
+    })
+}
 
-    // Create a simple test - just check if sidecar responds
-    // We'll create a tiny silent WAV file for testing
-    let test_path = "/tmp/saytype_test.wav";
+#[tauri::command]
+async fn list_profiles(
+    state: tauri::State<'_, AppState>,
+) -> Result<Vec<DictationProfile>, String> {
+    let app_config = state.config.lock().map_err(|e| e.to_string())?;
+    Ok(app_config.profiles.clone())
+}
 
-    // Create a minimal WAV file (silent, 1 second)
-    let spec = hound::WavSpec {
-        channels: 1,
-        sample_rate: 16000,
-        bits_per_sample: 16,
-        sample_format: hound::SampleFormat::Int,
-    };
+/// Add a profile, or replace the one with the same name
+#[tauri::command]
+async fn save_profile(
+    profile: DictationProfile,
+    state: tauri::State<'_, AppState>,
+) -> Result<Vec<DictationProfile>, String> {
+    update_config(&state, |app_config| {
+        app_config.save_profile(profile)?;
+        Ok(app_config.profiles.clone())
+    })
+}
 
-    let mut writer = hound::WavWriter::create(test_path, spec)
-        .map_err(|e| format!("Failed to create test WAV: {}", e))?;
+#[tauri::command]
+async fn remove_profile(
+    name: String,
+    state: tauri::State<'_, AppState>,
+) -> Result<Vec<DictationProfile>, String> {
+    update_config(&state, |app_config| {
+        app_config.remove_profile(&name)?;
+        Ok(app_config.profiles.clone())
+    })
+}
 
-    // Write 1 second of silence
-    for _ in 0..16000 {
-        writer.write_sample(0i16).map_err(|e| format!("Failed to write sample: {}", e))?;
-    }
-    writer.finalize().map_err(|e| format!("Failed to finalize WAV: {}", e))?;
+#[tauri::command]
+async fn test_sidecar(state: tauri::State<'_, AppState>) -> Result<String, String> {
+    println!("[TEST] Testing sidecar with sample audio...");
 
-    println!("[TEST] Created test WAV at {}", test_path);
+    // Just check the backend responds - one second of silence
+    let samples = vec![0.0; audio::TARGET_SAMPLE_RATE as usize];
 
-    // Try to transcribe
-    match sidecar::transcribe(&app_handle, test_path).await {
-        Ok(text) => {
-            let _ = std::fs::remove_file(test_path);
-            Ok(format!("Sidecar working! Got: '{}'", text))
-        }
-        Err(e) => {
-            let _ = std::fs::remove_file(test_path);
-            Err(format!("Sidecar error: {}", e))
-        }
+    match state.backend.transcribe(&samples, None).await {
+        Ok(text) => Ok(format!("Sidecar working! Got: '{}'", text)),
+        Err(e) => Err(format!("Sidecar error: {}", e)),
+    }
+}
+
+/// Start the sidecar process, if used, and keep it alive across crashes
+fn start_backend(app_handle: &tauri::AppHandle, backend_kind: BackendKind) {
+    if backend_kind == BackendKind::Sidecar {
+        let handle_clone = app_handle.clone();
+        tauri::async_runtime::spawn(async move {
+            println!("[SIDECAR] Starting sidecar supervisor...");
+            sidecar::supervise(handle_clone).await;
+        });
+    } else {
+        println!("[BACKEND] Using {:?} transcription backend", backend_kind);
     }
 }
 
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
 pub fn run() {
+    let cli_command = match cli::parse_args(std::env::args().skip(1)) {
+        Ok(command) => command,
+        Err(usage) => {
+            eprintln!("{}", usage);
+            std::process::exit(2);
+        }
+    };
+
     // Load config at startup
     let app_config = config::load_config();
-    let app_state = AppState::new(app_config.hotkey);
+    let audio_config = app_config.audio.clone();
+    hotkey::set_hotkey_bindings(&app_config.bindings);
+    let app_state = AppState::new(app_config);
+    // What actually runs, so a sidecar standing in for the configured backend is started too
+    let backend_kind = app_state.backend_kind;
 
     tauri::Builder::default()
         .plugin(tauri_plugin_opener::init())
         .plugin(tauri_plugin_shell::init())
         .manage(app_state)
-        .setup(|app| {
+        .setup(move |app| {
             let app_handle = app.handle().clone();
 
+            // Command-line use: just the backend, no tray, window or hotkey
+            if let Some(command) = cli_command {
+                start_backend(&app_handle, backend_kind);
+                tauri::async_runtime::spawn(cli::run(app_handle, command));
+                return Ok(());
+            }
+
             // Initialize system tray
             tray::setup_tray(&app_handle)?;
 
+            // Hold the microphone open for pre-roll if the user opted in
+            if audio_config.keep_mic_open && audio::check_microphone_permission() {
+                if let Err(e) = apply_preroll(&audio_config) {
+                    eprintln!("[AUDIO] Failed to open microphone for pre-roll: {}", e);
+                }
+            }
+
             // Open settings window on launch
             if let Some(window) = app.get_webview_window("settings") {
                 let _ = window.show();
@@ -254,25 +626,29 @@ pub fn run() {
             }
 
             // Disable App Nap to ensure event delivery when backgrounded
+            #[cfg(target_os = "macos")]
             if let Err(e) = app_nap::disable_app_nap() {
                 eprintln!("[APP_NAP] Warning: Failed to disable App Nap: {}", e);
             }
 
             // Set up event tap on main run loop (no separate thread needed)
-            println!("[HOTKEY] Setting up event tap on main run loop...");
-            if let Err(e) = hotkey::setup_event_tap(app_handle.clone()) {
-                eprintln!("[HOTKEY] Failed to set up event tap: {}", e);
+            #[cfg(target_os = "macos")]
+            {
+                println!("[HOTKEY] Setting up event tap on main run loop...");
+                if let Err(e) = event_tap::setup_event_tap(app_handle.clone()) {
+                    eprintln!("[HOTKEY] Failed to set up event tap: {}", e);
+                    let _ = app_handle.emit("hotkey-error", e);
+                }
+            }
+
+            // Read key events straight from the keyboards under /dev/input
+            #[cfg(target_os = "linux")]
+            if let Err(e) = evdev_hotkey::start(app_handle.clone()) {
+                eprintln!("[HOTKEY] Failed to read keyboards: {}", e);
                 let _ = app_handle.emit("hotkey-error", e);
             }
 
-            // Start sidecar process
-            let handle_clone = app_handle.clone();
-            tauri::async_runtime::spawn(async move {
-                println!("[SIDECAR] Starting sidecar process...");
-                if let Err(e) = sidecar::start(&handle_clone).await {
-                    eprintln!("[SIDECAR] Failed to start sidecar: {}", e);
-                }
-            });
+            start_backend(&app_handle, backend_kind);
 
             Ok(())
         })
@@ -286,6 +662,7 @@ pub fn run() {
         .invoke_handler(tauri::generate_handler![
             get_recording_state,
             get_sidecar_ready,
+            get_backend_info,
             check_permissions,
             request_microphone_permission,
             open_accessibility_settings,
@@ -294,10 +671,30 @@ pub fn run() {
             test_start_recording,
             test_stop_and_transcribe,
             test_sidecar,
+            list_input_devices,
+            get_audio_config,
+            set_input_device,
+            set_fallback_devices,
+            set_input_channels,
+            set_keep_mic_open,
             quit_app,
-            get_current_hotkey,
-            set_hotkey,
+            list_hotkey_bindings,
+            add_hotkey_binding,
+            update_hotkey_binding,
+            remove_hotkey_binding,
+            list_profiles,
+            save_profile,
+            remove_profile,
         ])
-        .run(tauri::generate_context!())
-        .expect("error while running tauri application");
+        .build(tauri::generate_context!())
+        .expect("error while running tauri application")
+        .run(|app_handle, event| {
+            // Stop the transcription backend so no model process outlives the app
+            if let RunEvent::Exit = event {
+                if let Some(state) = app_handle.try_state::<AppState>() {
+                    let backend = state.backend.clone();
+                    tauri::async_runtime::block_on(async move { backend.shutdown().await });
+                }
+            }
+        });
 }
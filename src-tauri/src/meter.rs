@@ -0,0 +1,164 @@
+use serde::Serialize;
+
+/// How much audio each reported level covers
+const LEVEL_INTERVAL_MS: u64 = 100;
+
+/// A signal that stays exactly zero this long means the mic is muted or access was
+/// denied - a live mic always picks up some noise
+const SILENT_AFTER_MS: u64 = 1500;
+
+/// Payload for the `audio-level` event
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+pub struct AudioLevel {
+    /// RMS level in dBFS
+    pub rms_db: f32,
+    /// Peak level in dBFS
+    pub peak_db: f32,
+}
+
+/// What the meter reports about the captured audio
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum LevelEvent {
+    /// Level of the most recent interval
+    Level(AudioLevel),
+    /// Only digital zeros so far; reported once per recording
+    Silent,
+}
+
+/// Called from the capture thread with level events while recording
+pub type LevelCallback = Box<dyn Fn(LevelEvent) + Send>;
+
+fn to_db(amplitude: f32) -> f32 {
+    20.0 * amplitude.max(1e-6).log10()
+}
+
+/// Measures RMS and peak level of captured audio over short intervals
+pub struct LevelMeter {
+    interval_samples: usize,
+    silent_after_samples: usize,
+    sum_squares: f64,
+    peak: f32,
+    count: usize,
+    /// Consecutive samples that were exactly zero
+    zero_run: usize,
+    silent_reported: bool,
+}
+
+impl LevelMeter {
+    pub fn new(sample_rate: u32) -> Self {
+        let samples = |ms: u64| (ms * sample_rate as u64 / 1000).max(1) as usize;
+        Self {
+            interval_samples: samples(LEVEL_INTERVAL_MS),
+            silent_after_samples: samples(SILENT_AFTER_MS),
+            sum_squares: 0.0,
+            peak: 0.0,
+            count: 0,
+            zero_run: 0,
+            silent_reported: false,
+        }
+    }
+
+    /// Feed mono samples. Returns at most one level - the latest complete interval,
+    /// so a backlog doesn't flood the UI - and the silence warning when it trips.
+    pub fn process(&mut self, samples: &[f32]) -> Vec<LevelEvent> {
+        let mut latest = None;
+        let mut silent = false;
+
+        for &sample in samples {
+            self.sum_squares += (sample as f64) * (sample as f64);
+            self.peak = self.peak.max(sample.abs());
+            self.count += 1;
+
+            if sample == 0.0 {
+                self.zero_run += 1;
+                if !self.silent_reported && self.zero_run >= self.silent_after_samples {
+                    self.silent_reported = true;
+                    silent = true;
+                }
+            } else {
+                self.zero_run = 0;
+            }
+
+            if self.count == self.interval_samples {
+                let rms = (self.sum_squares / self.count as f64).sqrt() as f32;
+                latest = Some(AudioLevel {
+                    rms_db: to_db(rms),
+                    peak_db: to_db(self.peak),
+                });
+                self.sum_squares = 0.0;
+                self.peak = 0.0;
+                self.count = 0;
+            }
+        }
+
+        let mut events: Vec<LevelEvent> = latest.map(LevelEvent::Level).into_iter().collect();
+        if silent {
+            events.push(LevelEvent::Silent);
+        }
+        events
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const RATE: u32 = 48000;
+
+    fn sine(ms: u64, amplitude: f32) -> Vec<f32> {
+        (0..ms * RATE as u64 / 1000)
+            .map(|i| (i as f32 * 1000.0 / RATE as f32 * std::f32::consts::TAU).sin() * amplitude)
+            .collect()
+    }
+
+    fn levels(events: &[LevelEvent]) -> Vec<AudioLevel> {
+        events
+            .iter()
+            .filter_map(|event| match event {
+                LevelEvent::Level(level) => Some(*level),
+                LevelEvent::Silent => None,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_sine_levels() {
+        let mut meter = LevelMeter::new(RATE);
+        let events = meter.process(&sine(100, 0.5));
+        let level = levels(&events)[0];
+        // A sine's RMS is 3dB below its peak
+        assert!((level.peak_db - to_db(0.5)).abs() < 0.1, "{:?}", level);
+        assert!((level.rms_db - (to_db(0.5) - 3.01)).abs() < 0.1, "{:?}", level);
+    }
+
+    #[test]
+    fn test_levels_are_throttled() {
+        let mut meter = LevelMeter::new(RATE);
+        // Nothing until a whole interval arrived
+        assert!(meter.process(&sine(50, 0.5)).is_empty());
+        assert_eq!(levels(&meter.process(&sine(50, 0.5))).len(), 1);
+        // A second's backlog is reported as its last interval only
+        let mut backlog = sine(900, 0.5);
+        backlog.extend(sine(100, 0.1));
+        let reported = levels(&meter.process(&backlog));
+        assert_eq!(reported.len(), 1);
+        assert!((reported[0].peak_db - to_db(0.1)).abs() < 0.1);
+    }
+
+    #[test]
+    fn test_digital_silence_is_reported_once() {
+        let mut meter = LevelMeter::new(RATE);
+        let zeros = vec![0.0; RATE as usize];
+        assert!(!meter.process(&zeros).contains(&LevelEvent::Silent));
+        assert!(meter.process(&zeros).contains(&LevelEvent::Silent));
+        assert!(!meter.process(&zeros).contains(&LevelEvent::Silent));
+        assert!(levels(&meter.process(&zeros))[0].rms_db <= -119.0);
+    }
+
+    #[test]
+    fn test_quiet_room_is_not_silent() {
+        let mut meter = LevelMeter::new(RATE);
+        let events = meter.process(&sine(3000, 0.0005));
+        assert!(!events.contains(&LevelEvent::Silent));
+    }
+}
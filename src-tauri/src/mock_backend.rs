@@ -0,0 +1,152 @@
+use async_trait::async_trait;
+use std::collections::VecDeque;
+use std::sync::Mutex;
+use std::time::Duration;
+
+use crate::backend::{Capabilities, TranscriptionBackend, TranscriptionError};
+use crate::config::MockConfig;
+
+/// One scripted answer of the mock backend
+#[derive(Debug, Clone)]
+pub struct MockResponse {
+    /// How long to "think" before answering
+    pub delay: Duration,
+    pub result: Result<String, TranscriptionError>,
+}
+
+impl MockResponse {
+    pub fn text(text: &str) -> Self {
+        Self {
+            delay: Duration::ZERO,
+            result: Ok(text.to_string()),
+        }
+    }
+
+    pub fn error(error: TranscriptionError) -> Self {
+        Self {
+            delay: Duration::ZERO,
+            result: Err(error),
+        }
+    }
+
+    pub fn with_delay(mut self, delay: Duration) -> Self {
+        self.delay = delay;
+        self
+    }
+}
+
+/// Backend that plays back scripted responses instead of running a model.
+/// Responses are consumed in order; the last one repeats once the script runs out.
+pub struct MockBackend {
+    script: Mutex<VecDeque<MockResponse>>,
+    fallback: Mutex<MockResponse>,
+    ready: bool,
+    /// Number of samples in each transcription request, for assertions in tests
+    requests: Mutex<Vec<usize>>,
+}
+
+impl MockBackend {
+    pub fn new(script: Vec<MockResponse>) -> Self {
+        let fallback = script
+            .last()
+            .cloned()
+            .unwrap_or_else(|| MockResponse::text(""));
+        Self {
+            script: Mutex::new(script.into()),
+            fallback: Mutex::new(fallback),
+            ready: true,
+            requests: Mutex::new(Vec::new()),
+        }
+    }
+
+    /// Build the script from the `mock` section of the app config
+    pub fn from_config(config: &MockConfig) -> Self {
+        let script = config
+            .script
+            .iter()
+            .map(|entry| {
+                let response = match &entry.error {
+                    Some(error) => MockResponse::error(TranscriptionError::Transcription(error.clone())),
+                    None => MockResponse::text(&entry.text),
+                };
+                response.with_delay(Duration::from_millis(entry.delay_ms))
+            })
+            .collect();
+        Self::new(script)
+    }
+
+    /// Always answer with the same text
+    #[cfg(test)]
+    pub fn with_text(text: &str) -> Self {
+        Self::new(vec![MockResponse::text(text)])
+    }
+
+    /// Report the backend as not ready, like a sidecar that is still loading
+    #[cfg(test)]
+    pub fn not_ready(mut self) -> Self {
+        self.ready = false;
+        self
+    }
+
+    /// Sample counts passed to `transcribe` so far
+    #[cfg(test)]
+    pub fn requests(&self) -> Vec<usize> {
+        self.requests.lock().map(|r| r.clone()).unwrap_or_default()
+    }
+
+    fn next_response(&self) -> MockResponse {
+        let next = self.script.lock().ok().and_then(|mut s| s.pop_front());
+        match next {
+            Some(response) => {
+                if let Ok(mut fallback) = self.fallback.lock() {
+                    *fallback = response.clone();
+                }
+                response
+            }
+            None => self
+                .fallback
+                .lock()
+                .map(|f| f.clone())
+                .unwrap_or_else(|_| MockResponse::text("")),
+        }
+    }
+}
+
+#[async_trait]
+impl TranscriptionBackend for MockBackend {
+    fn name(&self) -> &'static str {
+        "mock"
+    }
+
+    async fn is_ready(&self) -> bool {
+        self.ready
+    }
+
+    async fn capabilities(&self) -> Capabilities {
+        Capabilities {
+            model: Some("mock".to_string()),
+            features: vec!["transcribe_samples".to_string()],
+        }
+    }
+
+    async fn transcribe(
+        &self,
+        samples: &[f32],
+        _language: Option<&str>,
+    ) -> Result<String, TranscriptionError> {
+        if !self.ready {
+            return Err(TranscriptionError::NotReady);
+        }
+        if let Ok(mut requests) = self.requests.lock() {
+            requests.push(samples.len());
+        }
+
+        let response = self.next_response();
+        if !response.delay.is_zero() {
+            tokio::time::sleep(response.delay).await;
+        }
+        response.result
+    }
+
+    async fn shutdown(&self) {}
+}
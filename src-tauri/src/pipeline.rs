@@ -0,0 +1,524 @@
+use std::collections::VecDeque;
+use std::sync::Arc;
+use tokio::sync::mpsc::unbounded_channel;
+use tokio::task::{AbortHandle, JoinHandle};
+
+use serde::Serialize;
+
+use crate::audio::{FrameSink, TARGET_SAMPLE_RATE};
+use crate::backend::{FrameReceiver, PartialSink, TranscriptionBackend, TranscriptionError};
+use crate::config::PostProcessing;
+use crate::post_processing;
+use crate::segment::{Piece, Segmenter};
+
+/// Result of handing a finished recording to the backend
+#[derive(Debug, Clone, PartialEq)]
+pub enum DictationOutcome {
+    /// Text was transcribed and handed to the inserter
+    Inserted(String),
+    /// The backend returned no text; nothing was inserted
+    Empty,
+    /// The backend failed
+    Failed(TranscriptionError),
+    /// The user cancelled before the transcript was inserted
+    Cancelled,
+}
+
+/// A transcription that consumes audio while the recording is still running
+pub struct PendingTranscription {
+    task: JoinHandle<Result<String, TranscriptionError>>,
+}
+
+impl PendingTranscription {
+    /// Handle to cancel this transcription from elsewhere, e.g. the cancel key
+    pub fn canceller(&self) -> TranscriptionCanceller {
+        TranscriptionCanceller(self.task.abort_handle())
+    }
+}
+
+/// Cancels a pending transcription; the backend request is dropped and nothing is inserted
+#[derive(Clone)]
+pub struct TranscriptionCanceller(AbortHandle);
+
+impl TranscriptionCanceller {
+    pub fn cancel(&self) {
+        self.0.abort();
+    }
+}
+
+/// Start streaming a recording to the backend. Frames sent to the returned sink are
+/// forwarded as they arrive; dropping the sink ends the recording. Long recordings
+/// are split by `segmenter` and the segments transcribed one after another.
+/// `language` overrides the backend's language setting.
+/// `on_partial` is called with the transcript so far while recording.
+pub fn begin_transcription<P>(
+    backend: Arc<dyn TranscriptionBackend>,
+    segmenter: Segmenter,
+    language: Option<String>,
+    on_partial: P,
+) -> (FrameSink, PendingTranscription)
+where
+    P: Fn(String) + Send + 'static,
+{
+    let (sink, frames) = unbounded_channel();
+    let (partials, mut partial_rx) = unbounded_channel();
+
+    // Ends once the backend drops its partial sink
+    tokio::spawn(async move {
+        while let Some(text) = partial_rx.recv().await {
+            on_partial(text);
+        }
+    });
+
+    let task = tokio::spawn(transcribe_segments(backend, frames, partials, segmenter, language));
+    (sink, PendingTranscription { task })
+}
+
+/// `committed` followed by `text`, separated by spaces
+fn join_texts(committed: &[String], text: &str) -> String {
+    committed
+        .iter()
+        .map(String::as_str)
+        .chain(std::iter::once(text))
+        .filter(|text| !text.is_empty())
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Stream each segment of the recording to the backend in turn and join the texts.
+/// Frames that arrive while a segment is still being transcribed wait in the channel.
+async fn transcribe_segments(
+    backend: Arc<dyn TranscriptionBackend>,
+    mut frames: FrameReceiver,
+    partials: PartialSink,
+    mut segmenter: Segmenter,
+    language: Option<String>,
+) -> Result<String, TranscriptionError> {
+    let mut committed: Vec<String> = Vec::new();
+    // Segmented audio not yet handed to a segment's stream
+    let mut queue: VecDeque<Piece> = VecDeque::new();
+    let mut recording = true;
+
+    loop {
+        let (segment_sink, segment_frames) = unbounded_channel();
+        let (segment_partials, mut segment_partial_rx) = unbounded_channel();
+        let mut segment_sink = Some(segment_sink);
+        let transcription =
+            backend.transcribe_stream(segment_frames, segment_partials, language.as_deref());
+        tokio::pin!(transcription);
+
+        let text = loop {
+            while let Some(sink) = &segment_sink {
+                match queue.pop_front() {
+                    Some(Piece::Audio(audio)) => {
+                        let _ = sink.send(audio);
+                    }
+                    // Closing the segment's stream lets the backend finish it
+                    Some(Piece::Cut) => segment_sink = None,
+                    None if !recording => segment_sink = None,
+                    None => break,
+                }
+            }
+
+            tokio::select! {
+                result = &mut transcription => break result?,
+                Some(partial) = segment_partial_rx.recv() => {
+                    let _ = partials.send(join_texts(&committed, &partial));
+                }
+                frame = frames.recv(), if segment_sink.is_some() => match frame {
+                    Some(frame) => queue.extend(segmenter.push(&frame)),
+                    None => {
+                        recording = false;
+                        queue.push_back(Piece::Audio(segmenter.finish()));
+                    }
+                },
+            }
+        };
+
+        if !text.is_empty() {
+            committed.push(text);
+        }
+        if !recording && queue.is_empty() {
+            return Ok(committed.join(" "));
+        }
+        println!("[PIPELINE] Segment {} done, starting the next", committed.len());
+        let _ = partials.send(committed.join(" "));
+    }
+}
+
+/// Text of one segment of a transcribed recording, with its position in seconds
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct TimedText {
+    pub start_secs: f64,
+    pub end_secs: f64,
+    pub text: String,
+}
+
+/// Payload for the `transcription-progress` event
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+pub struct TranscriptionProgress {
+    /// Segments transcribed so far
+    pub segment: usize,
+    pub segments: usize,
+    /// Audio transcribed so far, in seconds
+    pub done_secs: f64,
+    pub total_secs: f64,
+}
+
+fn samples_to_secs(samples: usize) -> f64 {
+    samples as f64 / TARGET_SAMPLE_RATE as f64
+}
+
+/// Transcribe a complete recording, one segment at a time
+pub async fn transcribe_samples(
+    backend: &dyn TranscriptionBackend,
+    samples: &[f32],
+    segmenter: Segmenter,
+) -> Result<String, TranscriptionError> {
+    let timed = transcribe_timed(backend, samples, segmenter, |_| {}).await?;
+    let texts: Vec<String> = timed.into_iter().map(|timed| timed.text).collect();
+    Ok(texts.join(" "))
+}
+
+/// Transcribe a complete recording, one segment at a time, keeping where each
+/// segment's text was spoken. `on_progress` is called after every segment.
+/// Segments without text are left out.
+pub async fn transcribe_timed<P>(
+    backend: &dyn TranscriptionBackend,
+    samples: &[f32],
+    segmenter: Segmenter,
+    on_progress: P,
+) -> Result<Vec<TimedText>, TranscriptionError>
+where
+    P: Fn(TranscriptionProgress),
+{
+    let segments = segmenter.split(samples);
+    let mut timed = Vec::new();
+    let mut offset = 0;
+    for (index, segment) in segments.iter().enumerate() {
+        let text = backend.transcribe(segment, None).await?;
+        let end = offset + segment.len();
+        if !text.is_empty() {
+            timed.push(TimedText {
+                start_secs: samples_to_secs(offset),
+                end_secs: samples_to_secs(end),
+                text,
+            });
+        }
+        offset = end;
+        on_progress(TranscriptionProgress {
+            segment: index + 1,
+            segments: segments.len(),
+            done_secs: samples_to_secs(offset),
+            total_secs: samples_to_secs(samples.len()),
+        });
+    }
+    Ok(timed)
+}
+
+/// Wait for a streamed transcription and insert the text.
+/// `insert_text` receives the transcript after `post_processing`; insertion errors
+/// are logged but don't change the outcome, since the text was still produced.
+pub async fn finish_transcription<F>(
+    pending: PendingTranscription,
+    post_processing: PostProcessing,
+    insert_text: F,
+) -> DictationOutcome
+where
+    F: FnOnce(String) -> Result<(), String>,
+{
+    let result = match pending.task.await {
+        Ok(result) => result,
+        Err(e) if e.is_cancelled() => Err(TranscriptionError::Cancelled),
+        Err(e) => Err(TranscriptionError::Io(format!("Transcription task failed: {}", e))),
+    };
+
+    match result {
+        Ok(text) => {
+            println!("[PIPELINE] Transcription result: '{}'", text);
+            let text = post_processing::apply(&text, post_processing);
+            if text.is_empty() {
+                return DictationOutcome::Empty;
+            }
+            if let Err(e) = insert_text(text.clone()) {
+                eprintln!("[PIPELINE] Failed to insert text: {}", e);
+            }
+            DictationOutcome::Inserted(text)
+        }
+        Err(TranscriptionError::Cancelled) => {
+            println!("[PIPELINE] Transcription cancelled");
+            DictationOutcome::Cancelled
+        }
+        Err(e) => {
+            eprintln!("[PIPELINE] Transcription failed: {}", e);
+            DictationOutcome::Failed(e)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::SegmentConfig;
+    use crate::mock_backend::{MockBackend, MockResponse};
+    use std::sync::Mutex;
+    use std::time::Duration;
+
+    /// Stand-in for a recording: one second of a 440Hz tone in 100ms frames
+    fn test_frames() -> Vec<Vec<f32>> {
+        let tone: Vec<f32> = (0..16000)
+            .map(|i| {
+                let t = i as f32 / 16000.0;
+                (t * 440.0 * std::f32::consts::TAU).sin() * 0.5
+            })
+            .collect();
+        tone.chunks(1600).map(|chunk| chunk.to_vec()).collect()
+    }
+
+    fn segmenter() -> Segmenter {
+        Segmenter::new(SegmentConfig::default(), -45.0)
+    }
+
+    /// Stream the test recording through `backend` and insert the result
+    async fn dictate<F>(backend: Arc<dyn TranscriptionBackend>, insert_text: F) -> DictationOutcome
+    where
+        F: FnOnce(String) -> Result<(), String>,
+    {
+        let (sink, pending) = begin_transcription(backend, segmenter(), None, |_| {});
+        for frame in test_frames() {
+            sink.send(frame).unwrap();
+        }
+        drop(sink);
+        finish_transcription(pending, PostProcessing::None, insert_text).await
+    }
+
+    #[tokio::test]
+    async fn test_transcript_is_inserted() {
+        let backend = Arc::new(MockBackend::with_text("hello world"));
+        let inserted = Arc::new(Mutex::new(Vec::new()));
+        let sink = Arc::clone(&inserted);
+
+        let outcome = dictate(backend.clone(), move |text| {
+            sink.lock().unwrap().push(text);
+            Ok(())
+        })
+        .await;
+
+        assert_eq!(outcome, DictationOutcome::Inserted("hello world".to_string()));
+        assert_eq!(*inserted.lock().unwrap(), vec!["hello world".to_string()]);
+        // All frames arrive as one recording
+        assert_eq!(backend.requests(), vec![16000]);
+    }
+
+    #[tokio::test]
+    async fn test_empty_transcript_is_not_inserted() {
+        let backend = Arc::new(MockBackend::with_text(""));
+
+        let outcome = dictate(backend, |_| panic!("nothing should be inserted")).await;
+
+        assert_eq!(outcome, DictationOutcome::Empty);
+    }
+
+    #[tokio::test]
+    async fn test_profile_post_processing_is_applied() {
+        let backend = Arc::new(MockBackend::with_text("Fix the parser."));
+        let (sink, pending) = begin_transcription(backend, segmenter(), None, |_| {});
+        sink.send(test_frames().concat()).unwrap();
+        drop(sink);
+
+        let outcome = finish_transcription(pending, PostProcessing::Code, |text| {
+            assert_eq!(text, "fix the parser");
+            Ok(())
+        })
+        .await;
+        assert_eq!(outcome, DictationOutcome::Inserted("fix the parser".to_string()));
+    }
+
+    #[tokio::test]
+    async fn test_backend_errors_are_reported() {
+        let backend = Arc::new(MockBackend::new(vec![
+            MockResponse::error(TranscriptionError::Timeout(Duration::from_secs(1)))
+                .with_delay(Duration::from_millis(10)),
+            MockResponse::text("recovered"),
+        ]));
+
+        let first = dictate(backend.clone(), |_| Ok(())).await;
+        assert_eq!(
+            first,
+            DictationOutcome::Failed(TranscriptionError::Timeout(Duration::from_secs(1)))
+        );
+
+        let second = dictate(backend, |_| Ok(())).await;
+        assert_eq!(second, DictationOutcome::Inserted("recovered".to_string()));
+    }
+
+    #[tokio::test]
+    async fn test_long_recordings_report_partials() {
+        let backend = Arc::new(MockBackend::new(vec![
+            MockResponse::text("first"),
+            MockResponse::text("first second"),
+            MockResponse::text("first second third"),
+        ]));
+        let partials = Arc::new(Mutex::new(Vec::new()));
+        let seen = Arc::clone(&partials);
+
+        let (sink, pending) = begin_transcription(backend.clone(), segmenter(), None, move |text| {
+            seen.lock().unwrap().push(text);
+        });
+        // 12 seconds: decoded twice while recording, then once more in full
+        for _ in 0..12 {
+            for frame in test_frames() {
+                sink.send(frame).unwrap();
+            }
+        }
+        drop(sink);
+
+        let outcome = finish_transcription(pending, PostProcessing::None, |_| Ok(())).await;
+        assert_eq!(
+            outcome,
+            DictationOutcome::Inserted("first second third".to_string())
+        );
+        // Every 5s of new audio the whole buffer so far, then all of it
+        assert_eq!(backend.requests(), vec![81600, 163200, 192000]);
+        // Let the partial forwarder drain
+        tokio::task::yield_now().await;
+        assert_eq!(
+            *partials.lock().unwrap(),
+            vec!["first".to_string(), "first second".to_string()]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_long_recordings_are_transcribed_in_segments() {
+        let backend = Arc::new(MockBackend::new(vec![
+            MockResponse::text("first sentence"),
+            MockResponse::text("second sentence"),
+        ]));
+        let partials = Arc::new(Mutex::new(Vec::new()));
+        let seen = Arc::clone(&partials);
+        let short_segments = Segmenter::new(
+            SegmentConfig {
+                min_segment_secs: 2,
+                max_segment_secs: 4,
+                ..SegmentConfig::default()
+            },
+            -45.0,
+        );
+
+        let (sink, pending) =
+            begin_transcription(backend.clone(), short_segments, None, move |text| {
+                seen.lock().unwrap().push(text);
+            });
+        // 3s of speech, a pause, 2s of speech
+        for _ in 0..3 {
+            for frame in test_frames() {
+                sink.send(frame).unwrap();
+            }
+        }
+        for _ in 0..5 {
+            sink.send(vec![0.0; 1600]).unwrap();
+        }
+        for _ in 0..2 {
+            for frame in test_frames() {
+                sink.send(frame).unwrap();
+            }
+        }
+        drop(sink);
+
+        let outcome = finish_transcription(pending, PostProcessing::None, |_| Ok(())).await;
+        assert_eq!(
+            outcome,
+            DictationOutcome::Inserted("first sentence second sentence".to_string())
+        );
+        let requests = backend.requests();
+        assert_eq!(requests.len(), 2);
+        // Nothing is lost at the cut
+        assert_eq!(requests.iter().sum::<usize>(), 88000);
+        tokio::task::yield_now().await;
+        assert_eq!(*partials.lock().unwrap(), vec!["first sentence".to_string()]);
+    }
+
+    #[tokio::test]
+    async fn test_complete_recordings_are_split() {
+        let backend = MockBackend::new(vec![MockResponse::text("a"), MockResponse::text("b")]);
+        let segmenter = Segmenter::new(
+            SegmentConfig {
+                min_segment_secs: 1,
+                max_segment_secs: 2,
+                ..SegmentConfig::default()
+            },
+            -45.0,
+        );
+        let samples = test_frames().concat().repeat(3);
+
+        let text = transcribe_samples(&backend, &samples, segmenter).await.unwrap();
+        assert_eq!(text, "a b");
+        assert_eq!(backend.requests().iter().sum::<usize>(), samples.len());
+    }
+
+    #[tokio::test]
+    async fn test_timestamps_and_progress() {
+        let backend = MockBackend::new(vec![
+            MockResponse::text("first"),
+            MockResponse::text(""),
+            MockResponse::text("third"),
+        ]);
+        let segmenter = Segmenter::new(
+            SegmentConfig {
+                min_segment_secs: 1,
+                max_segment_secs: 1,
+                ..SegmentConfig::default()
+            },
+            -45.0,
+        );
+        let samples = test_frames().concat().repeat(3);
+        let progress = Mutex::new(Vec::new());
+
+        let timed = transcribe_timed(&backend, &samples, segmenter, |update| {
+            progress.lock().unwrap().push(update)
+        })
+        .await
+        .unwrap();
+
+        // Cuts land on whole 30ms windows; the empty middle segment leaves a gap
+        let spans: Vec<(f64, f64, &str)> = timed
+            .iter()
+            .map(|t| (t.start_secs, t.end_secs, t.text.as_str()))
+            .collect();
+        assert_eq!(spans, vec![(0.0, 1.02, "first"), (2.04, 3.0, "third")]);
+
+        let progress = progress.into_inner().unwrap();
+        assert_eq!(progress.len(), 3);
+        assert_eq!(progress[1].segment, 2);
+        assert_eq!(progress[1].done_secs, 2.04);
+        assert!(progress.iter().all(|p| p.segments == 3 && p.total_secs == 3.0));
+    }
+
+    #[tokio::test]
+    async fn test_cancelled_transcription_is_not_inserted() {
+        let backend = Arc::new(MockBackend::new(vec![
+            MockResponse::text("too late").with_delay(Duration::from_secs(5))
+        ]));
+
+        let (sink, pending) = begin_transcription(backend, segmenter(), None, |_| {});
+        sink.send(vec![0.0; 1600]).unwrap();
+        drop(sink);
+        pending.canceller().cancel();
+
+        let outcome =
+            finish_transcription(pending, PostProcessing::None, |_| {
+                panic!("nothing should be inserted")
+            })
+            .await;
+        assert_eq!(outcome, DictationOutcome::Cancelled);
+    }
+
+    #[tokio::test]
+    async fn test_not_ready_backend() {
+        let backend = Arc::new(MockBackend::with_text("unused").not_ready());
+
+        assert!(!backend.is_ready().await);
+        let outcome = dictate(backend, |_| Ok(())).await;
+        assert_eq!(outcome, DictationOutcome::Failed(TranscriptionError::NotReady));
+    }
+}
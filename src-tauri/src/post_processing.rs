@@ -0,0 +1,46 @@
+//! Rewrites a transcript according to the profile's post-processing before it is inserted.
+
+use crate::config::PostProcessing;
+
+/// Sentence punctuation the model closes its transcripts with
+const CLOSING_PUNCTUATION: &[char] = &['.', '!', '?', '…'];
+
+/// The transcript as it should be inserted
+pub fn apply(text: &str, post_processing: PostProcessing) -> String {
+    match post_processing {
+        PostProcessing::None => text.to_string(),
+        PostProcessing::Code => code_style(text),
+    }
+}
+
+/// Lowercase the first letter and drop the closing punctuation. Words written
+/// with more than one capital (acronyms, names like "iOS") are left alone.
+fn code_style(text: &str) -> String {
+    let text = text.trim().trim_end_matches(CLOSING_PUNCTUATION).trim_end();
+    let first_word = text.split_whitespace().next().unwrap_or("");
+    if first_word.chars().filter(|c| c.is_uppercase()).count() > 1 {
+        return text.to_string();
+    }
+
+    let mut chars = text.chars();
+    match chars.next() {
+        Some(first) => first.to_lowercase().chain(chars).collect(),
+        None => String::new(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_code_style() {
+        let code = |text| apply(text, PostProcessing::Code);
+        assert_eq!(code("Fix the parser."), "fix the parser");
+        assert_eq!(code(" Does it work? "), "does it work");
+        assert_eq!(code("JSON output, please..."), "JSON output, please");
+        assert_eq!(code("Über alles!"), "über alles");
+        assert_eq!(code("."), "");
+        assert_eq!(apply("Fix the parser.", PostProcessing::None), "Fix the parser.");
+    }
+}
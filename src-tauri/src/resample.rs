@@ -0,0 +1,245 @@
+use rubato::{FftFixedInOut, Resampler};
+
+/// Rough number of input frames resampled at a time
+const TARGET_CHUNK_FRAMES: usize = 1024;
+
+fn gcd(a: usize, b: usize) -> usize {
+    if b == 0 {
+        a
+    } else {
+        gcd(b, a % b)
+    }
+}
+
+/// Input chunk size for the FFT resampler. Chunks are whole multiples of the
+/// rates' ratio, and must be even on both sides: with an odd FFT size the
+/// resampler's delay is a fraction of a sample and can't be compensated.
+fn chunk_frames(source_rate: usize, target_rate: usize) -> usize {
+    let divisor = gcd(source_rate, target_rate);
+    let (unit_in, unit_out) = (source_rate / divisor, target_rate / divisor);
+    let mut units = TARGET_CHUNK_FRAMES.div_ceil(unit_in);
+    if units % 2 == 1 && (unit_in % 2 == 1 || unit_out % 2 == 1) {
+        units += 1;
+    }
+    units * unit_in
+}
+
+/// Incremental resampler: feed arbitrary-sized blocks as they are recorded and get
+/// resampled audio back as soon as a full chunk is available, so finishing a
+/// recording only has to convert the last partial chunk.
+///
+/// The FFT resampler delays its output; the delay is trimmed from the start and
+/// flushed out at the end, so the output lines up with the input sample for sample.
+pub struct ChunkResampler {
+    /// None when source and target rates are equal
+    resampler: Option<FftFixedInOut<f32>>,
+    /// Input samples not yet forming a full chunk
+    pending: Vec<f32>,
+    /// Reused output buffer for one chunk
+    chunk_output: Vec<f32>,
+    ratio: f64,
+    /// Output samples still to be dropped to compensate the resampler's delay
+    delay_remaining: usize,
+    /// Input samples fed so far
+    input_total: usize,
+    /// Output samples returned so far
+    output_total: usize,
+}
+
+impl ChunkResampler {
+    pub fn new(source_rate: u32, target_rate: u32) -> Result<Self, String> {
+        let resampler = if source_rate == target_rate {
+            None
+        } else {
+            let (source_rate, target_rate) = (source_rate as usize, target_rate as usize);
+            Some(
+                FftFixedInOut::<f32>::new(
+                    source_rate,
+                    target_rate,
+                    chunk_frames(source_rate, target_rate),
+                    1, // mono
+                )
+                .map_err(|e| format!("Failed to create resampler: {}", e))?,
+            )
+        };
+
+        let (chunk_output, delay) = match &resampler {
+            Some(resampler) => (
+                vec![0.0; resampler.output_frames_max()],
+                resampler.output_delay(),
+            ),
+            None => (Vec::new(), 0),
+        };
+
+        Ok(Self {
+            resampler,
+            pending: Vec::new(),
+            chunk_output,
+            ratio: target_rate as f64 / source_rate as f64,
+            delay_remaining: delay,
+            input_total: 0,
+            output_total: 0,
+        })
+    }
+
+    /// Resample as many full chunks as are available
+    pub fn process(&mut self, samples: &[f32]) -> Result<Vec<f32>, String> {
+        if self.resampler.is_none() {
+            return Ok(samples.to_vec());
+        }
+
+        self.pending.extend_from_slice(samples);
+        self.input_total += samples.len();
+
+        let mut output = Vec::new();
+        let mut pos = 0;
+        while pos + self.chunk_input_frames() <= self.pending.len() {
+            let end = pos + self.chunk_input_frames();
+            self.resample_chunk(pos, end, &mut output)?;
+            pos = end;
+        }
+        self.pending.drain(..pos);
+
+        self.output_total += output.len();
+        Ok(output)
+    }
+
+    /// End of the stream: resample the remaining input and flush the delayed tail.
+    /// Afterwards exactly `input * target_rate / source_rate` samples were returned.
+    pub fn flush(&mut self) -> Result<Vec<f32>, String> {
+        if self.resampler.is_none() {
+            return Ok(Vec::new());
+        }
+
+        let expected = (self.input_total as f64 * self.ratio).round() as usize;
+        let mut output = Vec::new();
+        // Push silence through until the delayed end of the input came out
+        while self.output_total + output.len() < expected {
+            let frames = self.chunk_input_frames();
+            self.pending.resize(frames.max(self.pending.len()), 0.0);
+            self.resample_chunk(0, frames, &mut output)?;
+            self.pending.drain(..frames);
+        }
+        output.truncate(expected.saturating_sub(self.output_total));
+
+        self.output_total += output.len();
+        self.pending.clear();
+        Ok(output)
+    }
+
+    fn chunk_input_frames(&self) -> usize {
+        self.resampler
+            .as_ref()
+            .map_or(0, |resampler| resampler.input_frames_next())
+    }
+
+    /// Resample `pending[start..end]`, a whole chunk, appending to `output`
+    fn resample_chunk(
+        &mut self,
+        start: usize,
+        end: usize,
+        output: &mut Vec<f32>,
+    ) -> Result<(), String> {
+        let Some(resampler) = self.resampler.as_mut() else {
+            return Ok(());
+        };
+        let (_, produced) = resampler
+            .process_into_buffer(
+                &[&self.pending[start..end]],
+                &mut [&mut self.chunk_output[..]],
+                None,
+            )
+            .map_err(|e| format!("Resampling error: {}", e))?;
+
+        let skip = self.delay_remaining.min(produced);
+        self.delay_remaining -= skip;
+        output.extend_from_slice(&self.chunk_output[skip..produced]);
+        Ok(())
+    }
+}
+
+/// Resample audio from source_rate to target_rate using rubato
+#[cfg(test)]
+pub fn resample_audio(samples: &[f32], source_rate: u32, target_rate: u32) -> Result<Vec<f32>, String> {
+    let mut resampler = ChunkResampler::new(source_rate, target_rate)?;
+    let mut output = resampler.process(samples)?;
+    output.extend(resampler.flush()?);
+    Ok(output)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sine(freq: f64, rate: u32, len: usize) -> Vec<f32> {
+        (0..len)
+            .map(|i| 0.5 * (i as f64 * freq / rate as f64 * std::f64::consts::TAU).sin())
+            .map(|sample| sample as f32)
+            .collect()
+    }
+
+    /// Largest deviation from the ideal sine, ignoring the edges where the filter rings
+    fn max_error(output: &[f32], freq: f64, rate: u32) -> f32 {
+        let edge = 64;
+        let ideal = sine(freq, rate, output.len());
+        output[edge..output.len() - edge]
+            .iter()
+            .zip(&ideal[edge..])
+            .map(|(got, want)| (got - want).abs())
+            .fold(0.0, f32::max)
+    }
+
+    #[test]
+    fn test_sine_accuracy() {
+        // Common device rates, down to 16kHz and up from narrowband
+        let cases = [
+            (48000, 16000, 440.0),
+            (44100, 16000, 1000.0),
+            (22050, 16000, 3000.0),
+            (96000, 16000, 200.0),
+            (8000, 16000, 300.0),
+        ];
+        for (source, target, freq) in cases {
+            let input = sine(freq, source, source as usize);
+            let output = resample_audio(&input, source, target).unwrap();
+            let error = max_error(&output, freq, target);
+            // Also fails if the delay isn't compensated: the sine would be shifted
+            assert!(error < 0.005, "{} -> {}: error {}", source, target, error);
+        }
+    }
+
+    #[test]
+    fn test_output_length_matches_duration() {
+        for len in [0, 1, 100, 1023, 48000, 48017] {
+            let output = resample_audio(&vec![0.1; len], 48000, 16000).unwrap();
+            assert_eq!(output.len(), (len as f64 / 3.0).round() as usize, "input {}", len);
+        }
+    }
+
+    #[test]
+    fn test_streaming_matches_whole_buffer() {
+        let input = sine(440.0, 48000, 30000);
+        let mut resampler = ChunkResampler::new(48000, 16000).unwrap();
+        let mut streamed = Vec::new();
+        // Irregular blocks, like audio callbacks
+        for block in input.chunks(457) {
+            streamed.extend(resampler.process(block).unwrap());
+        }
+        streamed.extend(resampler.flush().unwrap());
+        assert_eq!(streamed, resample_audio(&input, 48000, 16000).unwrap());
+    }
+
+    #[test]
+    fn test_chunks_are_even() {
+        assert_eq!(chunk_frames(48000, 16000), 1026);
+        // 441:160 - three units would be odd
+        assert_eq!(chunk_frames(44100, 16000), 1764);
+        assert_eq!(chunk_frames(8000, 16000), 1024);
+    }
+
+    #[test]
+    fn test_equal_rates_pass_through() {
+        let input = sine(440.0, 16000, 1000);
+        assert_eq!(resample_audio(&input, 16000, 16000).unwrap(), input);
+    }
+}
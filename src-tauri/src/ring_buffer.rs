@@ -0,0 +1,205 @@
+//! Single-producer single-consumer ring buffer that hands samples from the
+//! real-time audio callback to the forwarder thread without locking or allocating.
+
+use std::cell::UnsafeCell;
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+use std::sync::Arc;
+
+struct Shared {
+    buffer: Box<[UnsafeCell<f32>]>,
+    /// `buffer.len() - 1`; the length is a power of two
+    mask: usize,
+    /// Samples written so far; only the producer stores it
+    written: AtomicUsize,
+    /// Samples read so far; only the consumer stores it
+    read: AtomicUsize,
+    /// Pushes that didn't fit completely
+    overruns: AtomicU64,
+    /// Samples thrown away because the buffer was full
+    dropped: AtomicU64,
+}
+
+// SAFETY: a slot is written only by the producer while it is free and read only by
+// the consumer once the write was published through `written` (and vice versa for
+// `read`), so the two sides never touch the same slot at the same time.
+unsafe impl Sync for Shared {}
+
+/// Create a ring buffer holding at least `capacity` samples
+pub fn ring_buffer(capacity: usize) -> (Producer, Consumer) {
+    let len = capacity.max(1).next_power_of_two();
+    let shared = Arc::new(Shared {
+        buffer: (0..len).map(|_| UnsafeCell::new(0.0)).collect(),
+        mask: len - 1,
+        written: AtomicUsize::new(0),
+        read: AtomicUsize::new(0),
+        overruns: AtomicU64::new(0),
+        dropped: AtomicU64::new(0),
+    });
+    (
+        Producer {
+            shared: Arc::clone(&shared),
+        },
+        Consumer { shared },
+    )
+}
+
+/// Writing half, owned by the audio callback
+pub struct Producer {
+    shared: Arc<Shared>,
+}
+
+impl Producer {
+    /// Append samples. When the buffer is full the remaining samples are
+    /// dropped and counted as an overrun. Returns the number written.
+    pub fn push(&mut self, samples: impl IntoIterator<Item = f32>) -> usize {
+        let shared = &*self.shared;
+        let written = shared.written.load(Ordering::Relaxed);
+        let read = shared.read.load(Ordering::Acquire);
+        let free = shared.buffer.len() - written.wrapping_sub(read);
+
+        let mut count = 0;
+        let mut dropped = 0u64;
+        for sample in samples {
+            if count < free {
+                let slot = &shared.buffer[written.wrapping_add(count) & shared.mask];
+                // SAFETY: the slot is free, so the consumer isn't reading it
+                unsafe { *slot.get() = sample };
+                count += 1;
+            } else {
+                dropped += 1;
+            }
+        }
+
+        shared
+            .written
+            .store(written.wrapping_add(count), Ordering::Release);
+        if dropped > 0 {
+            shared.overruns.fetch_add(1, Ordering::Relaxed);
+            shared.dropped.fetch_add(dropped, Ordering::Relaxed);
+        }
+        count
+    }
+}
+
+/// Reading half, owned by the forwarder thread
+pub struct Consumer {
+    shared: Arc<Shared>,
+}
+
+impl Consumer {
+    /// Move everything buffered into `output`. Returns the number of samples moved.
+    pub fn pop_into(&mut self, output: &mut Vec<f32>) -> usize {
+        let shared = &*self.shared;
+        let read = shared.read.load(Ordering::Relaxed);
+        let written = shared.written.load(Ordering::Acquire);
+        let available = written.wrapping_sub(read);
+
+        output.reserve(available);
+        for i in 0..available {
+            let slot = &shared.buffer[read.wrapping_add(i) & shared.mask];
+            // SAFETY: the slot was published by the producer and isn't free yet
+            output.push(unsafe { *slot.get() });
+        }
+
+        shared
+            .read
+            .store(read.wrapping_add(available), Ordering::Release);
+        available
+    }
+
+    /// Samples waiting to be read
+    pub fn buffered(&self) -> usize {
+        let read = self.shared.read.load(Ordering::Relaxed);
+        self.shared
+            .written
+            .load(Ordering::Acquire)
+            .wrapping_sub(read)
+    }
+
+    /// Number of samples the buffer holds
+    pub fn capacity(&self) -> usize {
+        self.shared.buffer.len()
+    }
+
+    /// Pushes that didn't fit completely
+    pub fn overruns(&self) -> u64 {
+        self.shared.overruns.load(Ordering::Relaxed)
+    }
+
+    /// Samples lost to overruns
+    pub fn dropped_samples(&self) -> u64 {
+        self.shared.dropped.load(Ordering::Relaxed)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_samples_come_out_in_order_across_the_wrap() {
+        let (mut producer, mut consumer) = ring_buffer(8);
+        let mut output = Vec::new();
+        for round in 0..5 {
+            let block: Vec<f32> = (0..6).map(|i| (round * 6 + i) as f32).collect();
+            assert_eq!(producer.push(block), 6);
+            assert_eq!(consumer.buffered(), 6);
+            consumer.pop_into(&mut output);
+        }
+        let expected: Vec<f32> = (0..30).map(|i| i as f32).collect();
+        assert_eq!(output, expected);
+        assert_eq!(consumer.overruns(), 0);
+    }
+
+    #[test]
+    fn test_overrun_drops_newest_samples() {
+        let (mut producer, mut consumer) = ring_buffer(4);
+        assert_eq!(producer.push([1.0, 2.0, 3.0]), 3);
+        assert_eq!(producer.push([4.0, 5.0, 6.0]), 1);
+        assert_eq!(producer.push([7.0]), 0);
+        assert_eq!(consumer.overruns(), 2);
+        assert_eq!(consumer.dropped_samples(), 3);
+
+        let mut output = Vec::new();
+        consumer.pop_into(&mut output);
+        assert_eq!(output, vec![1.0, 2.0, 3.0, 4.0]);
+
+        // Space is available again once the consumer caught up
+        assert_eq!(producer.push([8.0]), 1);
+    }
+
+    #[test]
+    fn test_capacity_rounds_up_to_power_of_two() {
+        let (_, consumer) = ring_buffer(1000);
+        assert_eq!(consumer.capacity(), 1024);
+    }
+
+    #[test]
+    fn test_concurrent_transfer() {
+        const TOTAL: usize = 200_000;
+        let (mut producer, mut consumer) = ring_buffer(256);
+
+        let writer = std::thread::spawn(move || {
+            let mut next = 0;
+            while next < TOTAL {
+                let end = (next + 100).min(TOTAL);
+                // Retry whatever didn't fit so nothing is lost
+                let written = producer.push((next..end).map(|i| i as f32));
+                next += written;
+                if written == 0 {
+                    std::thread::yield_now();
+                }
+            }
+        });
+
+        let mut output = Vec::with_capacity(TOTAL);
+        while output.len() < TOTAL {
+            if consumer.pop_into(&mut output) == 0 {
+                std::thread::yield_now();
+            }
+        }
+        writer.join().unwrap();
+
+        assert!(output.iter().enumerate().all(|(i, &s)| s == i as f32));
+    }
+}
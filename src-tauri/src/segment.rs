@@ -0,0 +1,226 @@
+use crate::config::{AppConfig, SegmentConfig};
+use crate::vad::{level_db, ms_to_samples, WINDOW_SAMPLES};
+
+/// What the segmenter makes of a block of 16kHz audio
+#[derive(Debug, Clone, PartialEq)]
+pub enum Piece {
+    /// Audio belonging to the current segment
+    Audio(Vec<f32>),
+    /// The current segment ends here; following audio starts a new one
+    Cut,
+}
+
+/// Splits a 16kHz stream into segments short enough for the model, cutting in
+/// pauses so no word is split.
+///
+/// Once a segment reaches `min_segment_secs` it is cut at the first pause of
+/// `pause_ms`. The pause needed shrinks the longer the segment gets, down to a
+/// single quiet window, and at `max_segment_secs` it is cut wherever it is.
+pub struct Segmenter {
+    config: SegmentConfig,
+    /// Windows quieter than this (RMS, dBFS) count as silence
+    threshold_db: f32,
+    /// Samples that don't fill a whole window yet
+    partial_window: Vec<f32>,
+    /// Length of the current segment, in samples
+    segment_samples: usize,
+    /// Silence at the end of the current segment, in samples
+    quiet_samples: usize,
+}
+
+impl Segmenter {
+    pub fn new(config: SegmentConfig, threshold_db: f32) -> Self {
+        Self {
+            config,
+            threshold_db,
+            partial_window: Vec::new(),
+            segment_samples: 0,
+            quiet_samples: 0,
+        }
+    }
+
+    /// Segmenter using the app's segment settings and speech threshold
+    pub fn from_config(config: &AppConfig) -> Self {
+        Self::new(config.segments.clone(), config.vad.threshold_db)
+    }
+
+    /// Feed audio; returns it split into pieces of the current and following segments
+    pub fn push(&mut self, samples: &[f32]) -> Vec<Piece> {
+        if !self.config.enabled {
+            if samples.is_empty() {
+                return Vec::new();
+            }
+            return vec![Piece::Audio(samples.to_vec())];
+        }
+
+        self.partial_window.extend_from_slice(samples);
+        let whole = self.partial_window.len() / WINDOW_SAMPLES * WINDOW_SAMPLES;
+        let windows: Vec<f32> = self.partial_window.drain(..whole).collect();
+
+        let mut pieces = Vec::new();
+        let mut current = Vec::new();
+        for window in windows.chunks(WINDOW_SAMPLES) {
+            current.extend_from_slice(window);
+            self.segment_samples += window.len();
+            if level_db(window) < self.threshold_db {
+                self.quiet_samples += window.len();
+            } else {
+                self.quiet_samples = 0;
+            }
+
+            if self.should_cut() {
+                pieces.push(Piece::Audio(std::mem::take(&mut current)));
+                pieces.push(Piece::Cut);
+                self.segment_samples = 0;
+                self.quiet_samples = 0;
+            }
+        }
+        if !current.is_empty() {
+            pieces.push(Piece::Audio(current));
+        }
+        pieces
+    }
+
+    /// The stream ended; returns the audio still held back
+    pub fn finish(&mut self) -> Vec<f32> {
+        self.segment_samples = 0;
+        self.quiet_samples = 0;
+        std::mem::take(&mut self.partial_window)
+    }
+
+    /// Split a complete recording into segments
+    pub fn split(mut self, samples: &[f32]) -> Vec<Vec<f32>> {
+        let mut segments = vec![Vec::new()];
+        for piece in self.push(samples) {
+            match piece {
+                Piece::Audio(audio) => segments.last_mut().unwrap().extend(audio),
+                Piece::Cut => segments.push(Vec::new()),
+            }
+        }
+        segments.last_mut().unwrap().extend(self.finish());
+        segments.retain(|segment| !segment.is_empty());
+        segments
+    }
+
+    fn should_cut(&self) -> bool {
+        let min = ms_to_samples(self.config.min_segment_secs * 1000);
+        let max = ms_to_samples(self.config.max_segment_secs * 1000).max(min);
+        if self.segment_samples >= max {
+            return true;
+        }
+        if self.segment_samples < min || self.quiet_samples == 0 {
+            return false;
+        }
+
+        // Scale the pause needed from `pause_ms` at min down to nothing at max
+        let pause = ms_to_samples(self.config.pause_ms);
+        let remaining = (max - self.segment_samples) as f64 / (max - min).max(1) as f64;
+        self.quiet_samples as f64 >= pause as f64 * remaining
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn segmenter() -> Segmenter {
+        Segmenter::new(
+            SegmentConfig {
+                enabled: true,
+                min_segment_secs: 2,
+                max_segment_secs: 4,
+                pause_ms: 300,
+            },
+            -45.0,
+        )
+    }
+
+    fn silence(ms: u64) -> Vec<f32> {
+        vec![0.0; ms_to_samples(ms)]
+    }
+
+    fn tone(ms: u64) -> Vec<f32> {
+        (0..ms_to_samples(ms))
+            .map(|i| (i as f32 * 300.0 / 16000.0 * std::f32::consts::TAU).sin() * 0.3)
+            .collect()
+    }
+
+    fn secs(segment: &[f32]) -> f32 {
+        segment.len() as f32 / 16000.0
+    }
+
+    #[test]
+    fn test_short_recording_is_one_segment() {
+        let audio = [tone(1000), silence(500), tone(400)].concat();
+        let segments = segmenter().split(&audio);
+        assert_eq!(segments, vec![audio]);
+    }
+
+    #[test]
+    fn test_cut_in_first_pause_after_min_length() {
+        // A pause at 1s is too early, the one at 2.5s is used
+        let audio = [
+            tone(1000),
+            silence(500),
+            tone(1000),
+            silence(500),
+            tone(1000),
+        ]
+        .concat();
+        let segments = segmenter().split(&audio);
+        assert_eq!(segments.len(), 2);
+        assert_eq!(segments.concat(), audio);
+        // The cut lands inside the second pause
+        assert!(secs(&segments[0]) > 2.5 && secs(&segments[0]) < 3.0);
+    }
+
+    #[test]
+    fn test_continuous_speech_is_cut_at_max_length() {
+        let audio = tone(9000);
+        let segments = segmenter().split(&audio);
+        let lengths: Vec<f32> = segments.iter().map(|s| secs(s)).collect();
+        assert_eq!(lengths.len(), 3);
+        assert!(lengths[..2].iter().all(|&len| (len - 4.0).abs() < 0.05));
+        assert_eq!(segments.concat(), audio);
+    }
+
+    #[test]
+    fn test_short_pause_is_used_near_max_length() {
+        // Only a 90ms gap, late in the segment - better than cutting mid-word
+        let audio = [tone(3700), silence(90), tone(2000)].concat();
+        let segments = segmenter().split(&audio);
+        assert_eq!(segments.len(), 2);
+        assert!(secs(&segments[0]) > 3.7 && secs(&segments[0]) < 3.8);
+    }
+
+    #[test]
+    fn test_streaming_matches_split() {
+        let audio = [tone(2500), silence(600), tone(3000)].concat();
+        let mut streaming = segmenter();
+        let mut segments = vec![Vec::new()];
+        for block in audio.chunks(1600) {
+            for piece in streaming.push(block) {
+                match piece {
+                    Piece::Audio(samples) => segments.last_mut().unwrap().extend(samples),
+                    Piece::Cut => segments.push(Vec::new()),
+                }
+            }
+        }
+        segments.last_mut().unwrap().extend(streaming.finish());
+        assert_eq!(segments, segmenter().split(&audio));
+    }
+
+    #[test]
+    fn test_disabled_never_cuts() {
+        let mut segmenter = Segmenter::new(
+            SegmentConfig {
+                enabled: false,
+                ..SegmentConfig::default()
+            },
+            -45.0,
+        );
+        let pieces = segmenter.push(&tone(60_000));
+        assert_eq!(pieces.len(), 1);
+        assert!(segmenter.finish().is_empty());
+    }
+}
@@ -1,29 +1,60 @@
-use serde::Deserialize;
-use std::io::{BufRead, BufReader, Write};
-use std::process::{Child, ChildStdin, ChildStdout, Stdio};
-use std::sync::Mutex;
+use async_trait::async_trait;
+use serde::Serialize;
+use std::future::Future;
+use std::pin::Pin;
+use std::process::Stdio;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
 use tauri::{Emitter, Manager};
-
-#[derive(Deserialize)]
-struct TranscribeResponse {
-    success: bool,
-    text: Option<String>,
-    error: Option<String>,
-}
-
-#[derive(Deserialize)]
-struct StatusResponse {
-    status: Option<String>,
-}
-
-struct SidecarProcess {
-    _child: Child,
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+use tokio::process::{Child, ChildStdin, ChildStdout};
+use tokio::sync::{oneshot, Mutex, Notify};
+
+use crate::audio::TARGET_SAMPLE_RATE;
+use crate::backend::{
+    Capabilities, FrameReceiver, PartialSink, TranscriptionBackend, TranscriptionError,
+};
+use crate::config::SidecarConfig;
+use crate::sidecar_protocol::{
+    encode_pcm16, encode_request, parse_message, Message, Request, SidecarInfo, SidecarStatus,
+    PROTOCOL_VERSION,
+};
+
+/// First restart delay after a crash; doubled on every consecutive failure
+const RESTART_BACKOFF_INITIAL: Duration = Duration::from_secs(1);
+/// Upper bound for the restart delay
+const RESTART_BACKOFF_MAX: Duration = Duration::from_secs(30);
+/// A sidecar that stayed up this long is considered healthy and resets the backoff
+const HEALTHY_UPTIME: Duration = Duration::from_secs(60);
+
+/// Pipes of the running sidecar. The child itself is owned by the supervisor.
+struct SidecarIo {
     stdin: ChildStdin,
     stdout: BufReader<ChildStdout>,
+    /// Ask the supervisor to kill this process (unresponsive or broken pipes)
+    kill: Arc<Notify>,
 }
 
 lazy_static::lazy_static! {
-    static ref SIDECAR: Mutex<Option<SidecarProcess>> = Mutex::new(None);
+    static ref SIDECAR: Mutex<Option<SidecarIo>> = Mutex::new(None);
+    /// What the running sidecar reported during the handshake. Kept apart from the
+    /// pipes, which a dictation holds for as long as it records.
+    static ref INFO: std::sync::Mutex<Option<SidecarInfo>> = std::sync::Mutex::new(None);
+}
+
+/// Set once the app is quitting so the supervisor stops restarting the process
+static SHUTTING_DOWN: AtomicBool = AtomicBool::new(false);
+
+/// Whether a sidecar is connected. Read without the pipes' lock, so readiness
+/// checks don't wait for a dictation in progress.
+static READY: AtomicBool = AtomicBool::new(false);
+
+/// Source of request IDs; unique for the lifetime of the app
+static NEXT_REQUEST_ID: AtomicU64 = AtomicU64::new(1);
+
+fn next_request_id() -> u64 {
+    NEXT_REQUEST_ID.fetch_add(1, Ordering::Relaxed)
 }
 
 fn get_sidecar_path(app_handle: &tauri::AppHandle) -> Result<std::path::PathBuf, String> {
@@ -78,111 +109,557 @@ fn get_sidecar_path(app_handle: &tauri::AppHandle) -> Result<std::path::PathBuf,
     ))
 }
 
-pub async fn start(app_handle: &tauri::AppHandle) -> Result<(), String> {
+/// Payload for the `sidecar-crashed` event
+#[derive(Clone, Serialize)]
+struct CrashedPayload {
+    reason: String,
+}
+
+/// Payload for the `sidecar-restarting` event
+#[derive(Clone, Serialize)]
+struct RestartingPayload {
+    attempt: u32,
+    delay_ms: u64,
+}
+
+/// Keep the sidecar running for the lifetime of the app.
+/// Starts the process, waits for it to die, and restarts it with exponential backoff.
+/// Only returns once `shutdown` has been called.
+pub async fn supervise(app_handle: tauri::AppHandle) {
+    let mut attempt: u32 = 0;
+
+    loop {
+        let started_at = Instant::now();
+        let reason = match start(&app_handle).await {
+            Ok((child, kill)) => wait_for_exit(child, kill).await,
+            Err(e) => format!("Failed to start sidecar: {}", e),
+        };
+
+        // Drop the pipes so new requests fail fast instead of talking to a dead process
+        set_ready(None);
+        *SIDECAR.lock().await = None;
+
+        if SHUTTING_DOWN.load(Ordering::SeqCst) {
+            println!("[SIDECAR] Sidecar stopped for shutdown");
+            return;
+        }
+
+        eprintln!("[SIDECAR] Sidecar crashed: {}", reason);
+        let _ = app_handle.emit("sidecar-crashed", CrashedPayload { reason });
+
+        // Reset the backoff if the process ran long enough to be considered healthy
+        if started_at.elapsed() >= HEALTHY_UPTIME {
+            attempt = 0;
+        }
+        attempt += 1;
+
+        let delay = restart_delay(attempt);
+        println!(
+            "[SIDECAR] Restarting in {:?} (attempt {})",
+            delay, attempt
+        );
+        let _ = app_handle.emit(
+            "sidecar-restarting",
+            RestartingPayload {
+                attempt,
+                delay_ms: delay.as_millis() as u64,
+            },
+        );
+        tokio::time::sleep(delay).await;
+    }
+}
+
+/// Exponential backoff: 1s, 2s, 4s, ... capped at RESTART_BACKOFF_MAX
+fn restart_delay(attempt: u32) -> Duration {
+    let factor = 2u32.saturating_pow(attempt.saturating_sub(1));
+    RESTART_BACKOFF_INITIAL
+        .saturating_mul(factor)
+        .min(RESTART_BACKOFF_MAX)
+}
+
+/// Wait until the sidecar exits on its own or a request asks for it to be killed.
+/// Returns a human-readable reason for the exit.
+async fn wait_for_exit(mut child: Child, kill: Arc<Notify>) -> String {
+    tokio::select! {
+        status = child.wait() => match status {
+            Ok(status) => format!("Process exited with {}", status),
+            Err(e) => format!("Failed to wait for process: {}", e),
+        },
+        _ = kill.notified() => {
+            let _ = child.kill().await;
+            "Sidecar was unresponsive and has been killed".to_string()
+        }
+    }
+}
+
+/// Record whether a sidecar is connected, and what it reported if so
+fn set_ready(info: Option<SidecarInfo>) {
+    READY.store(info.is_some(), Ordering::SeqCst);
+    if let Ok(mut current) = INFO.lock() {
+        *current = info;
+    }
+}
+
+async fn start(app_handle: &tauri::AppHandle) -> Result<(Child, Arc<Notify>), String> {
     let program = get_sidecar_path(app_handle)?;
 
     println!("[SIDECAR] Spawning: {:?}", program);
 
     // Spawn with stdio pipes (inherit stderr so we see Python debug output)
-    let mut child = std::process::Command::new(&program)
+    let mut child = tokio::process::Command::new(&program)
         .stdin(Stdio::piped())
         .stdout(Stdio::piped())
         .stderr(Stdio::inherit())
+        .kill_on_drop(true)
         .spawn()
         .map_err(|e| format!("Failed to spawn sidecar: {}", e))?;
 
-    let stdin = child.stdin.take().ok_or("Failed to get sidecar stdin")?;
+    let mut stdin = child.stdin.take().ok_or("Failed to get sidecar stdin")?;
     let stdout = child.stdout.take().ok_or("Failed to get sidecar stdout")?;
 
     let mut reader = BufReader::new(stdout);
 
-    // Wait for loading signal
-    let mut line = String::new();
-    reader
-        .read_line(&mut line)
-        .map_err(|e| format!("Failed to read from sidecar: {}", e))?;
+    // Don't leave a half-started process behind if the handshake fails
+    // (kill_on_drop takes care of the child when we return early)
+    wait_until_ready(app_handle, &mut reader).await?;
+    let info = handshake(&mut stdin, &mut reader).await?;
 
-    let response: StatusResponse = serde_json::from_str(&line)
-        .map_err(|e| format!("Failed to parse status response: {} - got: {}", e, line))?;
+    println!(
+        "[SIDECAR] Connected: protocol v{}, model {}, capabilities {:?}",
+        info.protocol_version, info.model, info.capabilities
+    );
 
-    if response.status.as_deref() == Some("loading") {
-        println!("[SIDECAR] Model is loading...");
-        let _ = app_handle.emit("sidecar-loading", ());
+    let kill = Arc::new(Notify::new());
 
-        // Wait for ready signal
-        line.clear();
-        reader
-            .read_line(&mut line)
-            .map_err(|e| format!("Failed to read ready signal from sidecar: {}", e))?;
+    // Store the pipes
+    *SIDECAR.lock().await = Some(SidecarIo {
+        stdin,
+        stdout: reader,
+        kill: Arc::clone(&kill),
+    });
+
+    set_ready(Some(info));
 
-        let ready_response: StatusResponse = serde_json::from_str(&line)
-            .map_err(|e| format!("Failed to parse ready response: {} - got: {}", e, line))?;
+    let _ = app_handle.emit("sidecar-ready", ());
+
+    println!("Sidecar is ready");
+    Ok((child, kill))
+}
 
-        if ready_response.status.as_deref() != Some("ready") {
-            return Err(format!("Expected 'ready' status, got: {}", line));
+/// Read status messages the sidecar prints on startup until it reports ready.
+/// No timeout here - the first launch may download the model.
+async fn wait_until_ready(
+    app_handle: &tauri::AppHandle,
+    reader: &mut BufReader<ChildStdout>,
+) -> Result<(), String> {
+    loop {
+        match read_message(reader).await.map_err(|e| e.to_string())? {
+            Message::Status {
+                status: SidecarStatus::Loading,
+            } => {
+                println!("[SIDECAR] Model is loading...");
+                let _ = app_handle.emit("sidecar-loading", ());
+            }
+            Message::Status {
+                status: SidecarStatus::Ready,
+            } => return Ok(()),
+            Message::Error { error, .. } => {
+                return Err(format!("Sidecar failed to start: {}", error));
+            }
+            other => {
+                eprintln!("[SIDECAR] Ignoring unexpected startup message: {:?}", other);
+            }
         }
-    } else if response.status.as_deref() != Some("ready") {
-        return Err(format!("Unexpected sidecar response: {}", line));
     }
+}
+
+/// Exchange hello messages and check the sidecar speaks our protocol version
+async fn handshake(
+    stdin: &mut ChildStdin,
+    reader: &mut BufReader<ChildStdout>,
+) -> Result<SidecarInfo, String> {
+    let request = Request::Hello {
+        id: next_request_id(),
+        protocol_version: PROTOCOL_VERSION,
+    };
 
-    // Store the process
+    match send_request(stdin, reader, &request)
+        .await
+        .map_err(|e| format!("Handshake failed: {}", e))?
     {
-        let mut sidecar = SIDECAR.lock().map_err(|e| e.to_string())?;
-        *sidecar = Some(SidecarProcess {
-            _child: child,
-            stdin,
-            stdout: reader,
-        });
-    } // Drop the lock before awaiting
+        Message::Hello {
+            protocol_version,
+            model,
+            capabilities,
+            ..
+        } => {
+            if protocol_version != PROTOCOL_VERSION {
+                return Err(format!(
+                    "Sidecar speaks protocol v{}, expected v{}",
+                    protocol_version, PROTOCOL_VERSION
+                ));
+            }
+            Ok(SidecarInfo {
+                protocol_version,
+                model,
+                capabilities,
+            })
+        }
+        Message::Error { error, .. } => Err(format!("Handshake rejected: {}", error)),
+        other => Err(format!("Unexpected handshake response: {:?}", other)),
+    }
+}
+
+/// Handshake information of the running sidecar, if any
+pub fn info() -> Option<SidecarInfo> {
+    INFO.lock().ok().and_then(|info| info.clone())
+}
+
+/// Stream frames to the sidecar as they arrive and return the transcript once the
+/// sender is dropped. The sidecar is held for the whole recording so requests can't
+/// interleave with the stream.
+async fn transcribe_stream(
+    mut frames: FrameReceiver,
+    partials: PartialSink,
+    mut cancelled: oneshot::Receiver<()>,
+    timeout: Duration,
+) -> Result<String, TranscriptionError> {
+    // Don't occupy the sidecar before there is audio - recordings without speech never send any
+    let first_frame = tokio::select! {
+        biased;
+        _ = &mut cancelled => return Err(TranscriptionError::Cancelled),
+        frame = frames.recv() => frame,
+    };
+    let Some(first_frame) = first_frame else {
+        return Ok(String::new());
+    };
 
-    if let Some(state) = app_handle.try_state::<crate::AppState>() {
-        let mut sidecar_ready = state.sidecar_ready.lock().await;
-        *sidecar_ready = true;
+    // Async mutex: waiting for an earlier request never blocks a runtime worker
+    let mut sidecar = SIDECAR.lock().await;
+    // Cancelled while waiting for the lock - don't bother the sidecar at all
+    if let Err(oneshot::error::TryRecvError::Closed) = cancelled.try_recv() {
+        return Err(TranscriptionError::Cancelled);
+    }
+    let io = sidecar.as_mut().ok_or(TranscriptionError::NotReady)?;
+
+    let id = next_request_id();
+    let result = stream_frames(
+        io,
+        id,
+        first_frame,
+        &mut frames,
+        &partials,
+        &mut cancelled,
+        timeout,
+    )
+    .await;
+
+    // A hung or broken sidecar is useless - have the supervisor kill and respawn it
+    if let Err(TranscriptionError::Timeout(_) | TranscriptionError::Io(_)) = &result {
+        eprintln!("[SIDECAR] Request failed ({:?}), restarting sidecar", result);
+        io.kill.notify_one();
+        set_ready(None);
+        *sidecar = None;
     }
 
-    let _ = app_handle.emit("sidecar-ready", ());
+    match result? {
+        Message::Transcription { text, .. } => Ok(text),
+        Message::Error { error, .. } => Err(TranscriptionError::Transcription(error)),
+        other => Err(TranscriptionError::Protocol(format!(
+            "Unexpected response to stream_end: {:?}",
+            other
+        ))),
+    }
+}
 
-    println!("Sidecar is ready");
-    Ok(())
+/// What `stream_frames` does next
+enum StreamWrite {
+    /// The next request to write
+    Next(Request),
+    /// The recording is over
+    Done,
+    /// The user cancelled
+    Cancelled,
+    /// The sidecar answered (or gave up) before the stream ended
+    Answered(Result<Message, TranscriptionError>),
 }
 
-pub async fn transcribe(
-    _app_handle: &tauri::AppHandle,
-    audio_path: &str,
-) -> Result<String, String> {
-    let mut sidecar = SIDECAR.lock().map_err(|e| e.to_string())?;
-    let process = sidecar.as_mut().ok_or("Sidecar not running")?;
+/// Send one stream: begin, an audio message per frame, end. Partial transcripts are
+/// read while the audio is still being written. Only the wait for the final answer
+/// is bounded by `timeout`; the recording itself may take any time.
+/// Every line is written to the end before anything else is looked at, so the pipe
+/// never carries a half-written line; cancelling or an early answer take effect
+/// between two writes. Cancelling after the end stops the wait for the answer.
+async fn stream_frames(
+    io: &mut SidecarIo,
+    id: u64,
+    first_frame: Vec<f32>,
+    frames: &mut FrameReceiver,
+    partials: &PartialSink,
+    cancelled: &mut oneshot::Receiver<()>,
+    timeout: Duration,
+) -> Result<Message, TranscriptionError> {
+    let stdin = &mut io.stdin;
+    let stdout = &mut io.stdout;
+
+    let reader = async {
+        loop {
+            match read_message(stdout).await? {
+                Message::Partial { id: stream_id, text } if stream_id == id => {
+                    let _ = partials.send(text);
+                }
+                message if message.answers(id) => return Ok(message),
+                message => eprintln!(
+                    "[SIDECAR] Ignoring message not meant for stream {}: {:?}",
+                    id, message
+                ),
+            }
+        }
+    };
+    // The reader is polled across all writes below, so no line is ever half-read
+    tokio::pin!(reader);
+    let mut answer = None;
 
-    // Send transcribe command
-    let command = serde_json::json!({
-        "command": "transcribe",
-        "audio_path": audio_path
+    let begin = Request::StreamBegin {
+        id,
+        sample_rate: TARGET_SAMPLE_RATE,
+    };
+    write_while_reading(stdin, &begin, &mut reader, &mut answer).await?;
+    let mut next = StreamWrite::Next(Request::StreamAudio {
+        id,
+        data: encode_pcm16(&first_frame),
     });
 
-    writeln!(process.stdin, "{}", command)
-        .map_err(|e| format!("Failed to send command to sidecar: {}", e))?;
-    process
-        .stdin
+    loop {
+        match next {
+            StreamWrite::Next(request) => {
+                write_while_reading(stdin, &request, &mut reader, &mut answer).await?;
+            }
+            StreamWrite::Done => break,
+            StreamWrite::Cancelled => {
+                let cancel = Request::StreamCancel { id };
+                write_while_reading(stdin, &cancel, &mut reader, &mut answer).await?;
+                return Err(TranscriptionError::Cancelled);
+            }
+            StreamWrite::Answered(message) => return message,
+        }
+        if let Some(message) = answer.take() {
+            return message;
+        }
+
+        next = tokio::select! {
+            biased;
+            _ = &mut *cancelled => StreamWrite::Cancelled,
+            message = reader.as_mut() => StreamWrite::Answered(message),
+            frame = frames.recv() => match frame {
+                Some(frame) => StreamWrite::Next(Request::StreamAudio {
+                    id,
+                    data: encode_pcm16(&frame),
+                }),
+                None => StreamWrite::Done,
+            },
+        };
+    }
+
+    let end = Request::StreamEnd { id };
+    write_while_reading(stdin, &end, &mut reader, &mut answer).await?;
+    if let Some(message) = answer {
+        return message;
+    }
+    // The sidecar decodes the rest of the stream in one go and can't be interrupted.
+    // A cancel stops the wait so the next request gets the pipes right away; the late
+    // answer carries this stream's ID and is skipped by whoever reads next.
+    tokio::select! {
+        biased;
+        _ = &mut *cancelled => Err(TranscriptionError::Cancelled),
+        message = tokio::time::timeout(timeout, reader) => {
+            message.unwrap_or(Err(TranscriptionError::Timeout(timeout)))
+        }
+    }
+}
+
+/// Write `request` to the end while the stream's messages keep being read, so the
+/// sidecar never blocks on a full stdout. A message that ends the stream is kept in
+/// `answer`; the reader isn't polled again after that.
+async fn write_while_reading<R>(
+    stdin: &mut ChildStdin,
+    request: &Request,
+    reader: &mut Pin<&mut R>,
+    answer: &mut Option<Result<Message, TranscriptionError>>,
+) -> Result<(), TranscriptionError>
+where
+    R: Future<Output = Result<Message, TranscriptionError>>,
+{
+    let write = write_request(stdin, request);
+    tokio::pin!(write);
+    loop {
+        tokio::select! {
+            written = &mut write => return written,
+            message = reader.as_mut(), if answer.is_none() => *answer = Some(message),
+        }
+    }
+}
+
+/// Ask the sidecar to quit and stop supervising it
+pub async fn shutdown() {
+    SHUTTING_DOWN.store(true, Ordering::SeqCst);
+
+    let mut sidecar = SIDECAR.lock().await;
+    if let Some(io) = sidecar.as_mut() {
+        let request = Request::Quit {
+            id: next_request_id(),
+        };
+        if let Ok(line) = encode_request(&request) {
+            let _ = io.stdin.write_all(line.as_bytes()).await;
+            let _ = io.stdin.flush().await;
+        }
+        // Don't rely on the process honoring quit
+        io.kill.notify_one();
+    }
+    set_ready(None);
+    *sidecar = None;
+}
+
+/// The bundled Parakeet MLX sidecar as a transcription backend
+pub struct SidecarBackend {
+    config: SidecarConfig,
+}
+
+impl SidecarBackend {
+    pub fn new(config: SidecarConfig) -> Self {
+        Self { config }
+    }
+}
+
+#[async_trait]
+impl TranscriptionBackend for SidecarBackend {
+    fn name(&self) -> &'static str {
+        "sidecar"
+    }
+
+    async fn is_ready(&self) -> bool {
+        READY.load(Ordering::SeqCst)
+    }
+
+    async fn capabilities(&self) -> Capabilities {
+        match info() {
+            Some(info) => Capabilities {
+                model: Some(info.model),
+                features: info.capabilities,
+            },
+            None => Capabilities::default(),
+        }
+    }
+
+    // Parakeet detects the language itself and takes no hint, so a profile's
+    // language only reaches the HTTP backends
+    async fn transcribe(
+        &self,
+        samples: &[f32],
+        language: Option<&str>,
+    ) -> Result<String, TranscriptionError> {
+        // A finished recording is just a stream with a single frame
+        let (sink, frames) = tokio::sync::mpsc::unbounded_channel();
+        let _ = sink.send(samples.to_vec());
+        drop(sink);
+        let (partials, _) = tokio::sync::mpsc::unbounded_channel();
+        self.transcribe_stream(frames, partials, language).await
+    }
+
+    async fn transcribe_stream(
+        &self,
+        frames: FrameReceiver,
+        partials: PartialSink,
+        _language: Option<&str>,
+    ) -> Result<String, TranscriptionError> {
+        let timeout = Duration::from_millis(self.config.request_timeout_ms);
+        // The stream runs in its own task so it is never dropped mid-write. If this
+        // future is dropped (cancelled), `_cancel` goes with it and the task cancels
+        // the stream cleanly.
+        let (_cancel, cancelled) = oneshot::channel();
+        let stream = tokio::spawn(transcribe_stream(frames, partials, cancelled, timeout));
+        stream.await.unwrap_or_else(|e| {
+            Err(TranscriptionError::Io(format!("Sidecar stream task failed: {}", e)))
+        })
+    }
+
+    async fn shutdown(&self) {
+        shutdown().await;
+    }
+}
+
+/// Write a request and wait for the message answering it
+async fn send_request(
+    stdin: &mut ChildStdin,
+    reader: &mut BufReader<ChildStdout>,
+    request: &Request,
+) -> Result<Message, TranscriptionError> {
+    write_request(stdin, request).await?;
+    wait_for_response(reader, request).await
+}
+
+/// Write a single request line
+async fn write_request(
+    stdin: &mut ChildStdin,
+    request: &Request,
+) -> Result<(), TranscriptionError> {
+    let line = encode_request(request).map_err(TranscriptionError::Protocol)?;
+
+    stdin
+        .write_all(line.as_bytes())
+        .await
+        .map_err(|e| {
+            TranscriptionError::Io(format!("Failed to send command to sidecar: {}", e))
+        })?;
+    stdin
         .flush()
-        .map_err(|e| format!("Failed to flush sidecar stdin: {}", e))?;
+        .await
+        .map_err(|e| {
+            TranscriptionError::Io(format!("Failed to flush sidecar stdin: {}", e))
+        })?;
+    Ok(())
+}
 
-    // Read response
+/// Wait for the message answering `request`.
+/// Responses to other (stale) requests and non-protocol lines are skipped.
+async fn wait_for_response(
+    reader: &mut BufReader<ChildStdout>,
+    request: &Request,
+) -> Result<Message, TranscriptionError> {
+    loop {
+        let message = read_message(reader).await?;
+        if message.answers(request.id()) {
+            return Ok(message);
+        }
+        eprintln!(
+            "[SIDECAR] Ignoring message not meant for request {}: {:?}",
+            request.id(),
+            message
+        );
+    }
+}
+
+/// Read lines until one parses as a protocol message
+async fn read_message(
+    reader: &mut BufReader<ChildStdout>,
+) -> Result<Message, TranscriptionError> {
     let mut line = String::new();
-    process
-        .stdout
-        .read_line(&mut line)
-        .map_err(|e| format!("Failed to read from sidecar: {}", e))?;
-
-    let response: TranscribeResponse = serde_json::from_str(&line)
-        .map_err(|e| format!("Failed to parse transcription response: {} - Raw: {}", e, line))?;
-
-    if response.success {
-        response
-            .text
-            .ok_or_else(|| "No text in response".to_string())
-    } else {
-        Err(response
-            .error
-            .unwrap_or_else(|| "Unknown error".to_string()))
+    loop {
+        line.clear();
+        let read = reader
+            .read_line(&mut line)
+            .await
+            .map_err(|e| {
+                TranscriptionError::Io(format!("Failed to read from sidecar: {}", e))
+            })?;
+
+        if read == 0 {
+            return Err(TranscriptionError::Io("Sidecar exited unexpectedly".to_string()));
+        }
+
+        match parse_message(&line) {
+            Some(message) => return Ok(message),
+            None => eprintln!("[SIDECAR] stdout: {}", line.trim_end()),
+        }
     }
 }
@@ -0,0 +1,264 @@
+//! JSON-lines protocol spoken between the app and the transcription sidecar.
+//!
+//! Every message is a single JSON object on its own line. The app writes
+//! [`Request`]s to the sidecar's stdin, the sidecar writes [`Message`]s to stdout.
+//!
+//! Lifecycle:
+//! 1. The sidecar prints `{"type":"status","status":"loading"}` while the model loads
+//!    and `{"type":"status","status":"ready"}` once it can accept requests.
+//! 2. The app sends `{"command":"hello","id":1,"protocol_version":2}`. The sidecar
+//!    answers with `{"type":"hello","id":1,"protocol_version":2,"model":"...","capabilities":[...]}`.
+//!    The app refuses to talk to a sidecar with a different protocol version.
+//! 3. Every request carries an `id` that the sidecar echoes in its response, e.g.
+//!    `{"command":"transcribe","id":2,"audio_path":"/tmp/a.wav"}` ->
+//!    `{"type":"transcription","id":2,"text":"hello world"}`.
+//!    Failures are reported as `{"type":"error","id":2,"error":"..."}`; `id` is null
+//!    when the request itself could not be parsed.
+//! 4. Audio can be streamed instead of passed as a file. `stream_begin` opens a stream
+//!    under a new `id`, any number of `stream_audio` messages with the same `id` carry
+//!    base64-encoded 16-bit little-endian mono PCM, and `stream_end` closes it. Only
+//!    `stream_end` is answered (with `transcription` or `error`); a failure while
+//!    receiving audio is reported with the stream's `id` and surfaces there.
+//!    While audio arrives the sidecar may send `{"type":"partial","id":4,"text":"..."}`
+//!    with the transcript so far. Partials never answer a request. `stream_cancel`
+//!    instead of `stream_end` discards the stream without an answer.
+//!
+//! Lines that are not valid protocol messages (e.g. log output from Python
+//! libraries) and responses whose `id` doesn't match the pending request are ignored.
+
+use base64::Engine;
+use serde::{Deserialize, Serialize};
+
+/// Version of this protocol. Bump on any incompatible change.
+pub const PROTOCOL_VERSION: u32 = 2;
+
+/// Requests sent from the app to the sidecar
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "command", rename_all = "snake_case")]
+pub enum Request {
+    /// Handshake - must be the first request after the sidecar reports ready
+    Hello { id: u64, protocol_version: u32 },
+    /// Liveness check
+    Ping { id: u64 },
+    /// Transcribe a 16kHz mono WAV file
+    Transcribe { id: u64, audio_path: String },
+    /// Open an audio stream; `id` names the stream in the following messages
+    StreamBegin { id: u64, sample_rate: u32 },
+    /// A block of audio for an open stream, see [`encode_pcm16`]
+    StreamAudio { id: u64, data: String },
+    /// Close the stream and transcribe everything received
+    StreamEnd { id: u64 },
+    /// Discard the stream; not answered
+    StreamCancel { id: u64 },
+    /// Ask the sidecar to exit cleanly
+    Quit { id: u64 },
+}
+
+impl Request {
+    pub fn id(&self) -> u64 {
+        match self {
+            Request::Hello { id, .. }
+            | Request::Ping { id }
+            | Request::Transcribe { id, .. }
+            | Request::StreamBegin { id, .. }
+            | Request::StreamAudio { id, .. }
+            | Request::StreamEnd { id }
+            | Request::StreamCancel { id }
+            | Request::Quit { id } => *id,
+        }
+    }
+}
+
+/// Model loading state reported by unsolicited status messages
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SidecarStatus {
+    Loading,
+    Ready,
+}
+
+/// Messages sent from the sidecar to the app
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum Message {
+    /// Unsolicited startup progress
+    Status { status: SidecarStatus },
+    /// Answer to [`Request::Hello`]
+    Hello {
+        id: u64,
+        protocol_version: u32,
+        model: String,
+        #[serde(default)]
+        capabilities: Vec<String>,
+    },
+    /// Answer to [`Request::Ping`]
+    Pong { id: u64 },
+    /// Answer to [`Request::Transcribe`] and [`Request::StreamEnd`]
+    Transcription { id: u64, text: String },
+    /// Transcript so far of an open stream
+    Partial { id: u64, text: String },
+    /// A request failed
+    Error { id: Option<u64>, error: String },
+}
+
+impl Message {
+    /// The request ID this message answers, if any
+    pub fn id(&self) -> Option<u64> {
+        match self {
+            Message::Status { .. } => None,
+            Message::Hello { id, .. }
+            | Message::Pong { id }
+            | Message::Transcription { id, .. }
+            | Message::Partial { id, .. } => Some(*id),
+            Message::Error { id, .. } => *id,
+        }
+    }
+
+    /// Whether this message is the response to the request with `request_id`.
+    /// Errors without an ID are attributed to the pending request, since the
+    /// sidecar could not parse it well enough to echo the ID.
+    pub fn answers(&self, request_id: u64) -> bool {
+        match self {
+            Message::Status { .. } | Message::Partial { .. } => false,
+            Message::Error { id: None, .. } => true,
+            _ => self.id() == Some(request_id),
+        }
+    }
+}
+
+/// Sidecar identity reported during the handshake
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct SidecarInfo {
+    pub protocol_version: u32,
+    pub model: String,
+    pub capabilities: Vec<String>,
+}
+
+/// Parse one line of sidecar output. Returns `None` for anything that isn't a
+/// protocol message, so stray log lines can be skipped.
+pub fn parse_message(line: &str) -> Option<Message> {
+    let line = line.trim();
+    if !line.starts_with('{') {
+        return None;
+    }
+    serde_json::from_str(line).ok()
+}
+
+/// Serialize a request as a single newline-terminated line
+pub fn encode_request(request: &Request) -> Result<String, String> {
+    let mut line = serde_json::to_string(request)
+        .map_err(|e| format!("Failed to serialize request: {}", e))?;
+    line.push('\n');
+    Ok(line)
+}
+
+/// Encode samples as base64 16-bit little-endian PCM for [`Request::StreamAudio`]
+pub fn encode_pcm16(samples: &[f32]) -> String {
+    let mut bytes = Vec::with_capacity(samples.len() * 2);
+    for &sample in samples {
+        let value = (sample.clamp(-1.0, 1.0) * i16::MAX as f32) as i16;
+        bytes.extend_from_slice(&value.to_le_bytes());
+    }
+    base64::engine::general_purpose::STANDARD.encode(bytes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_request_encoding() {
+        let line = encode_request(&Request::Transcribe {
+            id: 7,
+            audio_path: "/tmp/a.wav".to_string(),
+        })
+        .unwrap();
+        assert!(line.ends_with('\n'));
+        let value: serde_json::Value = serde_json::from_str(line.trim()).unwrap();
+        assert_eq!(value["command"], "transcribe");
+        assert_eq!(value["id"], 7);
+        assert_eq!(value["audio_path"], "/tmp/a.wav");
+    }
+
+    #[test]
+    fn test_pcm_encoding() {
+        let data = encode_pcm16(&[0.0, 1.0, -1.0, 2.0]);
+        let bytes = base64::engine::general_purpose::STANDARD
+            .decode(data)
+            .unwrap();
+        let values: Vec<i16> = bytes
+            .chunks(2)
+            .map(|b| i16::from_le_bytes([b[0], b[1]]))
+            .collect();
+        // Out-of-range input is clamped
+        assert_eq!(values, vec![0, i16::MAX, -i16::MAX, i16::MAX]);
+    }
+
+    #[test]
+    fn test_stream_requests() {
+        let line = encode_request(&Request::StreamBegin {
+            id: 4,
+            sample_rate: 16000,
+        })
+        .unwrap();
+        assert_eq!(
+            line.trim(),
+            r#"{"command":"stream_begin","id":4,"sample_rate":16000}"#
+        );
+        assert_eq!(Request::StreamEnd { id: 4 }.id(), 4);
+    }
+
+    #[test]
+    fn test_message_parsing() {
+        assert_eq!(
+            parse_message(r#"{"type":"status","status":"loading"}"#),
+            Some(Message::Status {
+                status: SidecarStatus::Loading
+            })
+        );
+        assert_eq!(
+            parse_message(r#"{"type":"transcription","id":3,"text":"hi"}"#),
+            Some(Message::Transcription {
+                id: 3,
+                text: "hi".to_string()
+            })
+        );
+        let hello = parse_message(
+            r#"{"type":"hello","id":1,"protocol_version":1,"model":"parakeet","capabilities":["transcribe"]}"#,
+        )
+        .unwrap();
+        assert_eq!(hello.id(), Some(1));
+    }
+
+    #[test]
+    fn test_stray_lines_are_ignored() {
+        assert_eq!(parse_message("Fetching 5 files: 100%"), None);
+        assert_eq!(parse_message(""), None);
+        assert_eq!(parse_message(r#"{"unrelated":true}"#), None);
+    }
+
+    #[test]
+    fn test_response_matching() {
+        let stale = Message::Transcription {
+            id: 1,
+            text: "old".to_string(),
+        };
+        assert!(!stale.answers(2));
+        assert!(stale.answers(1));
+
+        let anonymous_error = Message::Error {
+            id: None,
+            error: "Invalid JSON".to_string(),
+        };
+        assert!(anonymous_error.answers(2));
+
+        let status = Message::Status {
+            status: SidecarStatus::Ready,
+        };
+        assert!(!status.answers(2));
+
+        let partial = parse_message(r#"{"type":"partial","id":2,"text":"hel"}"#).unwrap();
+        assert_eq!(partial.id(), Some(2));
+        assert!(!partial.answers(2));
+    }
+}
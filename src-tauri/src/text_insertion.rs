@@ -2,19 +2,24 @@ use arboard::Clipboard;
 use std::thread;
 use std::time::Duration;
 
+use crate::keys::Key;
+
 /// Clear any stuck modifier keys before typing.
 /// Posts FlagsChanged events with null flags to reset the keyboard modifier state.
 #[cfg(target_os = "macos")]
-fn clear_modifiers(keycodes: &[i64]) {
+fn clear_modifiers(keys: &[Key]) {
     use core_graphics::event::{CGEvent, CGEventFlags, CGEventTapLocation, CGEventType, EventField};
     use core_graphics::event_source::{CGEventSource, CGEventSourceStateID};
 
-    // Post FlagsChanged event with null flags for each modifier keycode
-    for &keycode in keycodes {
+    // Post FlagsChanged event with null flags for each modifier key
+    for key in keys {
         if let Ok(source) = CGEventSource::new(CGEventSourceStateID::HIDSystemState) {
             if let Ok(event) = CGEvent::new(source) {
                 event.set_type(CGEventType::FlagsChanged);
-                event.set_integer_value_field(EventField::KEYBOARD_EVENT_KEYCODE, keycode);
+                event.set_integer_value_field(
+                    EventField::KEYBOARD_EVENT_KEYCODE,
+                    key.mac_keycode(),
+                );
                 event.set_flags(CGEventFlags::CGEventFlagNull);
                 event.post(CGEventTapLocation::HID);
             }
@@ -23,14 +28,14 @@ fn clear_modifiers(keycodes: &[i64]) {
 }
 
 #[cfg(not(target_os = "macos"))]
-fn clear_modifiers(_keycodes: &[i64]) {}
+fn clear_modifiers(_keys: &[Key]) {}
 
 /// Insert text by copying to clipboard and pasting with Cmd+V.
 /// Uses AppleScript on macOS for reliable keystroke simulation.
-/// The keycodes parameter specifies which modifier keys to clear before pasting.
-pub fn insert_text_via_clipboard(text: &str, modifier_keycodes: &[i64]) -> Result<(), String> {
+/// The modifier_keys parameter specifies which modifier keys to clear before pasting.
+pub fn insert_text_via_clipboard(text: &str, modifier_keys: &[Key]) -> Result<(), String> {
     // Clear any stuck modifier keys first
-    clear_modifiers(modifier_keycodes);
+    clear_modifiers(modifier_keys);
     thread::sleep(Duration::from_millis(100));
 
     // Save current clipboard content
@@ -82,6 +87,14 @@ pub fn insert_text_via_clipboard(text: &str, modifier_keycodes: &[i64]) -> Resul
     Ok(())
 }
 
+/// Put text on the clipboard without pasting it
+pub fn copy_to_clipboard(text: &str) -> Result<(), String> {
+    let mut clipboard = Clipboard::new()
+        .map_err(|e| format!("Failed to access clipboard: {}", e))?;
+    clipboard.set_text(text)
+        .map_err(|e| format!("Failed to set clipboard: {}", e))
+}
+
 /// Check if accessibility permission is granted
 pub fn check_accessibility_permission() -> bool {
     #[cfg(target_os = "macos")]
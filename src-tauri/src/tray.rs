@@ -3,12 +3,16 @@ use tauri::{
     tray::{TrayIcon, TrayIconBuilder},
     AppHandle, Manager,
 };
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::Mutex;
 
 lazy_static::lazy_static! {
     static ref TRAY_ICON: Mutex<Option<TrayIcon>> = Mutex::new(None);
 }
 
+// The microphone is held open between recordings for pre-roll
+static MIC_OPEN: AtomicBool = AtomicBool::new(false);
+
 pub fn setup_tray(app: &AppHandle) -> Result<(), Box<dyn std::error::Error>> {
     // Create menu items
     let settings = MenuItem::with_id(app, "settings", "Settings...", true, None::<&str>)?;
@@ -47,6 +51,10 @@ pub fn setup_tray(app: &AppHandle) -> Result<(), Box<dyn std::error::Error>> {
 }
 
 pub fn set_recording_state(app: &AppHandle, is_recording: bool) {
+    if !is_recording {
+        // The microphone may have been closed by a settings change during the recording
+        set_mic_open(crate::audio::is_mic_open());
+    }
     let tray_guard = TRAY_ICON.lock().unwrap();
     if let Some(ref tray) = *tray_guard {
         // Swap icon based on recording state
@@ -67,6 +75,8 @@ pub fn set_recording_state(app: &AppHandle, is_recording: bool) {
         // Update tooltip
         let tooltip = if is_recording {
             "Saytype - Recording..."
+        } else if MIC_OPEN.load(Ordering::SeqCst) {
+            "Saytype - Push to talk (microphone open)"
         } else {
             "Saytype - Push to talk"
         };
@@ -74,6 +84,16 @@ pub fn set_recording_state(app: &AppHandle, is_recording: bool) {
     }
 }
 
+/// Show whether the microphone is held open between recordings: a dot next to
+/// the menu bar icon while it is
+pub fn set_mic_open(open: bool) {
+    MIC_OPEN.store(open, Ordering::SeqCst);
+    let tray_guard = TRAY_ICON.lock().unwrap();
+    if let Some(ref tray) = *tray_guard {
+        let _ = tray.set_title(if open { Some("●") } else { None });
+    }
+}
+
 fn try_load_icon(app: &AppHandle, tray: &TrayIcon, icon_name: &str) -> bool {
     // Try resource directory first (production)
     if let Ok(resource_path) = app.path().resource_dir() {
@@ -0,0 +1,233 @@
+use crate::audio::TARGET_SAMPLE_RATE;
+use crate::config::VadConfig;
+
+/// Length of the windows the energy is measured over (30ms at 16kHz)
+pub const WINDOW_SAMPLES: usize = TARGET_SAMPLE_RATE as usize * 30 / 1000;
+
+/// Number of 16kHz samples in `ms` milliseconds
+pub fn ms_to_samples(ms: u64) -> usize {
+    (ms * TARGET_SAMPLE_RATE as u64 / 1000) as usize
+}
+
+/// RMS level of a window in dBFS
+pub fn level_db(window: &[f32]) -> f32 {
+    if window.is_empty() {
+        return f32::NEG_INFINITY;
+    }
+    let energy = window.iter().map(|s| s * s).sum::<f32>() / window.len() as f32;
+    10.0 * energy.max(1e-12).log10()
+}
+
+/// Energy-based voice activity detection on a 16kHz stream.
+///
+/// Audio is passed through as it is recorded, except that leading silence is
+/// dropped, pauses are held back until speech resumes, and trailing silence is
+/// dropped when the recording ends. Nothing at all is passed on until the
+/// recording contains `min_speech_ms` of uninterrupted speech.
+pub struct SpeechGate {
+    config: VadConfig,
+    /// Samples that don't fill a whole window yet
+    partial_window: Vec<f32>,
+    /// Audio not yet passed on: silence after speech, or before speech is confirmed the
+    /// leading padding and the current run of speech
+    held: Vec<f32>,
+    /// Length of the current run of voiced windows, in samples
+    speech_samples: usize,
+    /// Enough speech was seen; audio is flowing
+    confirmed: bool,
+}
+
+impl SpeechGate {
+    pub fn new(config: VadConfig) -> Self {
+        Self {
+            config,
+            partial_window: Vec::new(),
+            held: Vec::new(),
+            speech_samples: 0,
+            confirmed: false,
+        }
+    }
+
+    /// Whether the recording contained enough speech to be transcribed
+    pub fn speech_detected(&self) -> bool {
+        !self.config.enabled || self.confirmed
+    }
+
+    /// Feed recorded samples; returns the audio to pass on
+    pub fn process(&mut self, samples: &[f32]) -> Vec<f32> {
+        if !self.config.enabled {
+            return samples.to_vec();
+        }
+
+        self.partial_window.extend_from_slice(samples);
+        let whole = self.partial_window.len() / WINDOW_SAMPLES * WINDOW_SAMPLES;
+        let windows: Vec<f32> = self.partial_window.drain(..whole).collect();
+
+        let mut output = Vec::new();
+        for window in windows.chunks(WINDOW_SAMPLES) {
+            self.push_window(window, &mut output);
+        }
+        output
+    }
+
+    /// The recording ended; returns the trailing padding if there was speech
+    pub fn finish(&mut self) -> Vec<f32> {
+        if !self.config.enabled {
+            return std::mem::take(&mut self.partial_window);
+        }
+
+        self.held.append(&mut self.partial_window);
+        if !self.confirmed {
+            self.held.clear();
+            return Vec::new();
+        }
+
+        let padding = ms_to_samples(self.config.padding_ms).min(self.held.len());
+        let tail = self.held[..padding].to_vec();
+        self.held.clear();
+        tail
+    }
+
+    fn push_window(&mut self, window: &[f32], output: &mut Vec<f32>) {
+        let voiced = level_db(window) >= self.config.threshold_db;
+        let padding = ms_to_samples(self.config.padding_ms);
+
+        if voiced {
+            self.speech_samples += window.len();
+        } else {
+            // Only uninterrupted speech counts, so scattered clicks never add up
+            self.speech_samples = 0;
+        }
+
+        if self.confirmed {
+            if voiced {
+                // Speech resumed - the pause before it is part of the utterance
+                output.append(&mut self.held);
+                output.extend_from_slice(window);
+            } else {
+                self.held.extend_from_slice(window);
+            }
+            return;
+        }
+
+        self.held.extend_from_slice(window);
+        if self.speech_samples >= ms_to_samples(self.config.min_speech_ms) {
+            self.confirmed = true;
+            output.append(&mut self.held);
+        } else {
+            // Keep just enough of what came before the current run for the leading padding
+            let excess = self.held.len().saturating_sub(padding + self.speech_samples);
+            self.held.drain(..excess);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config() -> VadConfig {
+        VadConfig {
+            enabled: true,
+            threshold_db: -45.0,
+            min_speech_ms: 150,
+            padding_ms: 300,
+        }
+    }
+
+    fn silence(ms: u64) -> Vec<f32> {
+        vec![0.0; ms_to_samples(ms)]
+    }
+
+    /// A 300Hz tone, loud enough to count as speech
+    fn tone(ms: u64) -> Vec<f32> {
+        (0..ms_to_samples(ms))
+            .map(|i| {
+                let t = i as f32 / TARGET_SAMPLE_RATE as f32;
+                (t * 300.0 * std::f32::consts::TAU).sin() * 0.3
+            })
+            .collect()
+    }
+
+    fn run(gate: &mut SpeechGate, blocks: &[Vec<f32>]) -> Vec<f32> {
+        let mut output = Vec::new();
+        for block in blocks {
+            output.extend(gate.process(block));
+        }
+        output.extend(gate.finish());
+        output
+    }
+
+    #[test]
+    fn test_silence_is_rejected() {
+        let mut gate = SpeechGate::new(config());
+        let output = run(&mut gate, &[silence(1000), silence(500)]);
+        assert!(output.is_empty());
+        assert!(!gate.speech_detected());
+    }
+
+    #[test]
+    fn test_short_click_is_rejected() {
+        let mut gate = SpeechGate::new(config());
+        let output = run(&mut gate, &[silence(500), tone(60), silence(500)]);
+        assert!(output.is_empty());
+        assert!(!gate.speech_detected());
+    }
+
+    #[test]
+    fn test_scattered_clicks_are_rejected() {
+        let mut gate = SpeechGate::new(config());
+        // 300ms of clicks in total, but never more than 60ms at once
+        let clicks: Vec<Vec<f32>> = (0..5).flat_map(|_| [tone(60), silence(90)]).collect();
+        let output = run(&mut gate, &clicks);
+        assert!(output.is_empty());
+        assert!(!gate.speech_detected());
+    }
+
+    #[test]
+    fn test_noise_before_speech_holds_only_the_padding() {
+        let mut gate = SpeechGate::new(config());
+        for _ in 0..200 {
+            assert!(gate.process(&tone(60)).is_empty());
+            assert!(gate.process(&silence(240)).is_empty());
+        }
+        assert!(gate.held.len() <= ms_to_samples(300) + WINDOW_SAMPLES);
+
+        let output = run(&mut gate, &[tone(600)]);
+        assert!(gate.speech_detected());
+        assert!(output.len() <= ms_to_samples(300 + 600) + WINDOW_SAMPLES);
+    }
+
+    #[test]
+    fn test_leading_and_trailing_silence_is_trimmed() {
+        let mut gate = SpeechGate::new(config());
+        let output = run(&mut gate, &[silence(2000), tone(600), silence(2000)]);
+        assert!(gate.speech_detected());
+        // 300ms padding on each side of 600ms of speech
+        let expected = ms_to_samples(300 + 600 + 300);
+        assert!(
+            output.len().abs_diff(expected) <= WINDOW_SAMPLES,
+            "got {} samples, expected about {}",
+            output.len(),
+            expected
+        );
+    }
+
+    #[test]
+    fn test_pauses_between_words_are_kept() {
+        let mut gate = SpeechGate::new(config());
+        let output = run(&mut gate, &[tone(300), silence(800), tone(300)]);
+        assert!(output.len() >= ms_to_samples(1400) - WINDOW_SAMPLES);
+    }
+
+    #[test]
+    fn test_disabled_passes_everything() {
+        let mut gate = SpeechGate::new(VadConfig {
+            enabled: false,
+            ..config()
+        });
+        let output = run(&mut gate, &[silence(1000)]);
+        assert_eq!(output.len(), ms_to_samples(1000));
+        assert!(gate.speech_detected());
+    }
+}
@@ -0,0 +1,33 @@
+use hound::{WavSpec, WavWriter};
+use std::io::Cursor;
+
+/// Encode 16kHz mono samples as an in-memory 16-bit WAV file
+pub fn encode_wav(samples: &[f32], sample_rate: u32) -> Result<Vec<u8>, String> {
+    let spec = WavSpec {
+        channels: 1,
+        sample_rate,
+        bits_per_sample: 16,
+        sample_format: hound::SampleFormat::Int,
+    };
+
+    let mut buffer = Cursor::new(Vec::new());
+    let mut writer =
+        WavWriter::new(&mut buffer, spec).map_err(|e| format!("Failed to create WAV: {}", e))?;
+
+    for &sample in samples {
+        writer
+            .write_sample(sample_to_i16(sample))
+            .map_err(|e| format!("Failed to write sample: {}", e))?;
+    }
+
+    writer
+        .finalize()
+        .map_err(|e| format!("Failed to finalize WAV: {}", e))?;
+
+    Ok(buffer.into_inner())
+}
+
+/// Convert f32 [-1.0, 1.0] to i16
+fn sample_to_i16(sample: f32) -> i16 {
+    (sample.clamp(-1.0, 1.0) * i16::MAX as f32) as i16
+}
@@ -1,9 +1,84 @@
 import { useState, useEffect, useCallback } from "react";
 import { invoke } from "@tauri-apps/api/core";
+import { listen } from "@tauri-apps/api/event";
+import { getCurrentWebview } from "@tauri-apps/api/webview";
 import { useRecording, checkPermissions, openAccessibilitySettings, openInputMonitoringSettings } from "../hooks/useRecording";
 
+type HotkeyMode = "hold" | "toggle" | "hybrid";
+
+interface InputDevice {
+  name: string;
+  is_default: boolean;
+  configs: { channels: number }[];
+}
+
+type ChannelSelection = { mode: "downmix" } | { mode: "channel"; index: number };
+
+interface AudioConfig {
+  input_device: string | null;
+  channels: ChannelSelection;
+  keep_mic_open: boolean;
+  preroll_ms: number;
+}
+
+interface GestureConfig {
+  min_hold_ms: number;
+  double_tap: boolean;
+  double_tap_ms: number;
+  discard_on_other_key: boolean;
+}
+
 interface HotkeyConfig {
   label: string;
+  mode: HotkeyMode;
+  gestures: GestureConfig;
+}
+
+interface HotkeyBinding {
+  hotkey: HotkeyConfig;
+  profile: string;
+}
+
+interface DictationProfile {
+  name: string;
+  language: string | null;
+  post_processing: "none" | "code";
+  insertion: "paste" | "clipboard";
+}
+
+const MODE_HINTS: Record<HotkeyMode, string> = {
+  hold: "Hold the hotkey to start recording, release to transcribe.",
+  toggle: "Press the hotkey to start recording, press it again to transcribe.",
+  hybrid: "Tap the hotkey to start recording and tap again to stop, or hold it for push-to-talk.",
+};
+
+interface TimedText {
+  start_secs: number;
+  end_secs: number;
+  text: string;
+}
+
+interface FileTranscript {
+  text: string;
+  segments: TimedText[];
+}
+
+interface TranscriptionProgress {
+  segment: number;
+  segments: number;
+  done_secs: number;
+  total_secs: number;
+}
+
+/** m:ss for transcript timestamps */
+function formatTime(secs: number): string {
+  const whole = Math.floor(secs);
+  return `${Math.floor(whole / 60)}:${String(whole % 60).padStart(2, "0")}`;
+}
+
+/** Map an RMS level onto the meter, showing -60 dB to 0 dB */
+function levelPercent(rmsDb: number): number {
+  return Math.min(100, Math.max(0, ((rmsDb + 60) / 60) * 100));
 }
 
 interface SettingsProps {
@@ -11,7 +86,8 @@ interface SettingsProps {
 }
 
 export function Settings({ onClose }: SettingsProps) {
-  const { state, lastTranscription, lastError } = useRecording();
+  const { state, lastTranscription, partialTranscription, lastError, lastWarning, inputLevel } =
+    useRecording();
   const [permissions, setPermissions] = useState({
     microphone: false,
     accessibility: false,
@@ -20,18 +96,109 @@ export function Settings({ onClose }: SettingsProps) {
   const [startAtLogin, setStartAtLogin] = useState(false);
   const [testStatus, setTestStatus] = useState<string | null>(null);
   const [isRecording, setIsRecording] = useState(false);
-  const [currentHotkey, setCurrentHotkey] = useState<string>("Right \u2318");
-  const [isListeningForHotkey, setIsListeningForHotkey] = useState(false);
-  const [pendingKeys, setPendingKeys] = useState<{ code: string; location: number }[]>([]);
+  const [bindings, setBindings] = useState<HotkeyBinding[]>([]);
+  const [profiles, setProfiles] = useState<DictationProfile[]>([]);
+  const [newProfileName, setNewProfileName] = useState("");
+  const [hotkeyError, setHotkeyError] = useState<string | null>(null);
+  const [inputDevices, setInputDevices] = useState<InputDevice[]>([]);
+  const [inputDevice, setInputDevice] = useState<string | null>(null);
+  const [inputChannels, setInputChannels] = useState<ChannelSelection>({ mode: "downmix" });
+  const [keepMicOpen, setKeepMicOpen] = useState(false);
+  const [prerollMs, setPrerollMs] = useState(300);
+  // Binding whose keys are being recorded, or "new" for an added one
+  const [listeningFor, setListeningFor] = useState<number | "new" | null>(null);
+  const isListeningForHotkey = listeningFor !== null;
+  const [pendingKeys, setPendingKeys] = useState<{ code: string }[]>([]);
+  const [fileStatus, setFileStatus] = useState<string | null>(null);
+  const [fileTranscript, setFileTranscript] = useState<FileTranscript | null>(null);
 
   useEffect(() => {
     checkPermissions().then(setPermissions);
-    // Load current hotkey
-    invoke<HotkeyConfig>("get_current_hotkey")
-      .then((config) => setCurrentHotkey(config.label))
+    // Load hotkey bindings and their profiles
+    invoke<HotkeyBinding[]>("list_hotkey_bindings").then(setBindings).catch(console.error);
+    invoke<DictationProfile[]>("list_profiles").then(setProfiles).catch(console.error);
+    invoke<InputDevice[]>("list_input_devices")
+      .then(setInputDevices)
+      .catch(console.error);
+    invoke<AudioConfig>("get_audio_config")
+      .then((config) => {
+        setInputDevice(config.input_device);
+        setInputChannels(config.channels);
+        setKeepMicOpen(config.keep_mic_open);
+        setPrerollMs(config.preroll_ms);
+      })
       .catch(console.error);
   }, []);
 
+  const transcribeFile = async (path: string) => {
+    setFileTranscript(null);
+    setFileStatus(`Transcribing ${path.split("/").pop()}...`);
+    try {
+      const transcript = await invoke<FileTranscript>("transcribe_audio", { audioPath: path });
+      setFileTranscript(transcript);
+      setFileStatus(null);
+    } catch (error) {
+      setFileStatus(`Error: ${error}`);
+    }
+  };
+
+  // Audio files dropped onto the window are transcribed
+  useEffect(() => {
+    const unlistenDrop = getCurrentWebview().onDragDropEvent((event) => {
+      if (event.payload.type === "drop" && event.payload.paths.length > 0) {
+        transcribeFile(event.payload.paths[0]);
+      }
+    });
+    const unlistenProgress = listen<TranscriptionProgress>("transcription-progress", (event) => {
+      const { segment, segments, done_secs, total_secs } = event.payload;
+      if (segments > 1) {
+        setFileStatus(
+          `Transcribing... part ${segment} of ${segments} (${formatTime(done_secs)} / ${formatTime(total_secs)})`
+        );
+      }
+    });
+    return () => {
+      unlistenDrop.then((fn) => fn());
+      unlistenProgress.then((fn) => fn());
+    };
+  }, []);
+
+  const changeInputDevice = async (name: string | null) => {
+    try {
+      const result = await invoke<AudioConfig>("set_input_device", { name });
+      setInputDevice(result.input_device);
+    } catch (error) {
+      console.error("Failed to set input device:", error);
+    }
+  };
+
+  const changeInputChannels = async (channels: ChannelSelection) => {
+    try {
+      const result = await invoke<AudioConfig>("set_input_channels", { channels });
+      setInputChannels(result.channels);
+    } catch (error) {
+      console.error("Failed to set input channels:", error);
+    }
+  };
+
+  const changeKeepMicOpen = async (enabled: boolean, preroll?: number) => {
+    try {
+      const result = await invoke<AudioConfig>("set_keep_mic_open", {
+        enabled,
+        prerollMs: preroll ?? null,
+      });
+      setKeepMicOpen(result.keep_mic_open);
+      setPrerollMs(result.preroll_ms);
+    } catch (error) {
+      console.error("Failed to change microphone mode:", error);
+    }
+  };
+
+  const selectedDevice = inputDevices.find((d) =>
+    inputDevice ? d.name === inputDevice : d.is_default
+  );
+  const channelCount = Math.max(1, ...(selectedDevice?.configs.map((c) => c.channels) ?? []));
+
   // Handle hotkey recording
   const handleKeyDown = useCallback((e: KeyboardEvent) => {
     if (!isListeningForHotkey) return;
@@ -40,11 +207,10 @@ export function Settings({ onClose }: SettingsProps) {
     e.stopPropagation();
 
     const code = e.code;
-    const location = e.location;
 
     setPendingKeys((prev) => {
       if (!prev.some((k) => k.code === code)) {
-        return [...prev, { code, location }];
+        return [...prev, { code }];
       }
       return prev;
     });
@@ -60,20 +226,27 @@ export function Settings({ onClose }: SettingsProps) {
     if (pendingKeys.length > 0) {
       try {
         const codes = pendingKeys.map((k) => k.code);
-        const locations = pendingKeys.map((k) => k.location);
 
-        const result = await invoke<HotkeyConfig>("set_hotkey", {
-          params: { codes, locations }
-        });
-        setCurrentHotkey(result.label);
+        const result =
+          listeningFor === "new"
+            ? await invoke<HotkeyBinding[]>("add_hotkey_binding", {
+                params: { codes, profile: profiles[0]?.name }
+              })
+            : await invoke<HotkeyBinding[]>("update_hotkey_binding", {
+                index: listeningFor,
+                params: { codes }
+              });
+        setBindings(result);
+        setHotkeyError(null);
       } catch (error) {
         console.error("Failed to set hotkey:", error);
+        setHotkeyError(String(error));
       }
 
-      setIsListeningForHotkey(false);
+      setListeningFor(null);
       setPendingKeys([]);
     }
-  }, [isListeningForHotkey, pendingKeys]);
+  }, [isListeningForHotkey, listeningFor, pendingKeys, profiles]);
 
   useEffect(() => {
     if (isListeningForHotkey) {
@@ -86,16 +259,93 @@ export function Settings({ onClose }: SettingsProps) {
     }
   }, [isListeningForHotkey, handleKeyDown, handleKeyUp]);
 
-  const startListeningForHotkey = () => {
+  const changeBinding = async (
+    index: number,
+    changes: { mode?: HotkeyMode; profile?: string; gestures?: GestureConfig }
+  ) => {
+    try {
+      const result = await invoke<HotkeyBinding[]>("update_hotkey_binding", {
+        index,
+        params: changes
+      });
+      setBindings(result);
+    } catch (error) {
+      console.error("Failed to change hotkey:", error);
+    }
+  };
+
+  // Edit a binding's gesture thresholds in place; they're saved when the input loses focus
+  const editGestures = (index: number, changes: Partial<GestureConfig>) => {
+    setBindings((current) =>
+      current.map((binding, i) =>
+        i === index
+          ? {
+              ...binding,
+              hotkey: {
+                ...binding.hotkey,
+                gestures: { ...binding.hotkey.gestures, ...changes },
+              },
+            }
+          : binding
+      )
+    );
+  };
+
+  const removeBinding = async (index: number) => {
+    try {
+      setBindings(await invoke<HotkeyBinding[]>("remove_hotkey_binding", { index }));
+    } catch (error) {
+      console.error("Failed to remove hotkey:", error);
+    }
+  };
+
+  const saveProfile = async (profile: DictationProfile) => {
+    try {
+      setProfiles(await invoke<DictationProfile[]>("save_profile", { profile }));
+    } catch (error) {
+      console.error("Failed to save profile:", error);
+    }
+  };
+
+  const addProfile = async () => {
+    const name = newProfileName.trim();
+    if (!name || profiles.some((p) => p.name === name)) return;
+    await saveProfile({ name, language: null, post_processing: "none", insertion: "paste" });
+    setNewProfileName("");
+  };
+
+  const removeProfile = async (name: string) => {
+    try {
+      setProfiles(await invoke<DictationProfile[]>("remove_profile", { name }));
+      setHotkeyError(null);
+    } catch (error) {
+      setHotkeyError(String(error));
+    }
+  };
+
+  const startListeningForHotkey = (binding: number | "new") => {
     setPendingKeys([]);
-    setIsListeningForHotkey(true);
+    setListeningFor(binding);
   };
 
   const cancelHotkeyListening = () => {
-    setIsListeningForHotkey(false);
+    setListeningFor(null);
     setPendingKeys([]);
   };
 
+  const usedModes = [...new Set(bindings.map((b) => b.hotkey.mode))];
+
+  const listeningDisplay = (
+    <div className="hotkey-listening">
+      <kbd className="listening">
+        {pendingKeys.length > 0 ? formatPendingKeys(pendingKeys) : "Press keys..."}
+      </kbd>
+      <button className="cancel-btn" onClick={cancelHotkeyListening}>
+        Cancel
+      </button>
+    </div>
+  );
+
   // Format JS event.code to display label
   const formatKeyCode = (code: string): string => {
     const map: Record<string, string> = {
@@ -124,7 +374,7 @@ export function Settings({ onClose }: SettingsProps) {
     return code;
   };
 
-  const formatPendingKeys = (keys: { code: string; location: number }[]): string => {
+  const formatPendingKeys = (keys: { code: string }[]): string => {
     return keys.map((k) => formatKeyCode(k.code)).join("+");
   };
 
@@ -200,6 +450,13 @@ export function Settings({ onClose }: SettingsProps) {
           <span>{getStatusText()}</span>
         </div>
         {lastError && <p className="error">Error: {lastError}</p>}
+        {lastWarning && <p className="warning">{lastWarning}</p>}
+        {partialTranscription && (
+          <div className="last-transcription">
+            <strong>Hearing:</strong>
+            <p>{partialTranscription}</p>
+          </div>
+        )}
         {lastTranscription && (
           <div className="last-transcription">
             <strong>Last transcription:</strong>
@@ -223,27 +480,283 @@ export function Settings({ onClose }: SettingsProps) {
         )}
       </section>
 
+      <section className="file-section">
+        <h2>Transcribe a File</h2>
+        <p className="hint">
+          Drop a WAV, FLAC, MP3 or Ogg file onto this window, e.g. a voice memo or a meeting
+          recording.
+        </p>
+        {fileStatus && <p className="test-status">{fileStatus}</p>}
+        {fileTranscript && (
+          <div className="last-transcription">
+            {fileTranscript.segments.map((segment) => (
+              <p key={segment.start_secs}>
+                <span className="timestamp">{formatTime(segment.start_secs)}</span> {segment.text}
+              </p>
+            ))}
+          </div>
+        )}
+      </section>
+
       <section className="hotkey-section">
-        <h2>Hotkey</h2>
-        <div className="hotkey-display">
-          {isListeningForHotkey ? (
-            <div className="hotkey-listening">
-              <kbd className="listening">
-                {pendingKeys.length > 0 ? formatPendingKeys(pendingKeys) : "Press keys..."}
-              </kbd>
-              <button className="cancel-btn" onClick={cancelHotkeyListening}>
-                Cancel
-              </button>
+        <h2>Hotkeys</h2>
+        {bindings.map((binding, index) => (
+          <div className="hotkey-binding" key={index}>
+            <div className="hotkey-display">
+              {listeningFor === index ? (
+                listeningDisplay
+              ) : (
+                <button className="hotkey-btn" onClick={() => startListeningForHotkey(index)}>
+                  <kbd>{binding.hotkey.label}</kbd>
+                  <span className="edit-hint">Click to change</span>
+                </button>
+              )}
+            </div>
+            <div className="hotkey-mode">
+              <select
+                aria-label="Mode"
+                value={binding.hotkey.mode}
+                onChange={(e) => changeBinding(index, { mode: e.target.value as HotkeyMode })}
+              >
+                <option value="hold">Hold to talk</option>
+                <option value="toggle">Toggle</option>
+                <option value="hybrid">Hybrid</option>
+              </select>
+              <select
+                aria-label="Profile"
+                value={binding.profile}
+                onChange={(e) => changeBinding(index, { profile: e.target.value })}
+              >
+                {profiles.map((profile) => (
+                  <option key={profile.name} value={profile.name}>
+                    {profile.name}
+                  </option>
+                ))}
+              </select>
+              {bindings.length > 1 && (
+                <button className="cancel-btn" onClick={() => removeBinding(index)}>
+                  Remove
+                </button>
+              )}
             </div>
+            <div className="hotkey-mode">
+              <label>
+                Hold for{" "}
+                <input
+                  type="number"
+                  min={0}
+                  step={50}
+                  style={{ width: "5em" }}
+                  value={binding.hotkey.gestures.min_hold_ms}
+                  onChange={(e) =>
+                    editGestures(index, { min_hold_ms: Math.max(0, Number(e.target.value) || 0) })
+                  }
+                  onBlur={() => changeBinding(index, { gestures: binding.hotkey.gestures })}
+                />{" "}
+                ms
+              </label>
+              <label>
+                <input
+                  type="checkbox"
+                  checked={binding.hotkey.gestures.double_tap}
+                  onChange={(e) =>
+                    changeBinding(index, {
+                      gestures: { ...binding.hotkey.gestures, double_tap: e.target.checked },
+                    })
+                  }
+                />
+                Double-tap
+              </label>
+              {binding.hotkey.gestures.double_tap && (
+                <label>
+                  within{" "}
+                  <input
+                    type="number"
+                    min={0}
+                    step={50}
+                    style={{ width: "5em" }}
+                    value={binding.hotkey.gestures.double_tap_ms}
+                    onChange={(e) =>
+                      editGestures(index, {
+                        double_tap_ms: Math.max(0, Number(e.target.value) || 0),
+                      })
+                    }
+                    onBlur={() => changeBinding(index, { gestures: binding.hotkey.gestures })}
+                  />{" "}
+                  ms
+                </label>
+              )}
+              <label>
+                <input
+                  type="checkbox"
+                  checked={binding.hotkey.gestures.discard_on_other_key}
+                  onChange={(e) =>
+                    changeBinding(index, {
+                      gestures: {
+                        ...binding.hotkey.gestures,
+                        discard_on_other_key: e.target.checked,
+                      },
+                    })
+                  }
+                />
+                Ignore shortcuts
+              </label>
+            </div>
+          </div>
+        ))}
+        <div className="hotkey-display">
+          {listeningFor === "new" ? (
+            listeningDisplay
           ) : (
-            <button className="hotkey-btn" onClick={startListeningForHotkey}>
-              <kbd>{currentHotkey}</kbd>
-              <span className="edit-hint">Click to change</span>
+            <button className="cancel-btn" onClick={() => startListeningForHotkey("new")}>
+              Add hotkey
             </button>
           )}
         </div>
+        {hotkeyError && <p className="error">{hotkeyError}</p>}
+        <p className="hint">
+          {usedModes.map((mode) => MODE_HINTS[mode]).join(" ")} Press Escape to cancel.
+        </p>
+        <p className="hint">
+          With a modifier like Right {"\u2318"} as the hotkey, a minimum hold time, a double-tap or
+          ignoring shortcuts keeps shortcuts such as {"\u2318"}C from starting a recording.
+        </p>
+      </section>
+
+      <section className="profiles-section">
+        <h2>Profiles</h2>
+        {profiles.map((profile) => (
+          <div className="hotkey-mode" key={profile.name}>
+            <strong>{profile.name}</strong>
+            <input
+              type="text"
+              placeholder="Language"
+              aria-label="Language"
+              size={8}
+              defaultValue={profile.language ?? ""}
+              onBlur={(e) => saveProfile({ ...profile, language: e.target.value.trim() || null })}
+            />
+            <select
+              aria-label="Formatting"
+              value={profile.post_processing}
+              onChange={(e) =>
+                saveProfile({
+                  ...profile,
+                  post_processing: e.target.value as DictationProfile["post_processing"],
+                })
+              }
+            >
+              <option value="none">As spoken</option>
+              <option value="code">Code style</option>
+            </select>
+            <select
+              aria-label="Insertion"
+              value={profile.insertion}
+              onChange={(e) =>
+                saveProfile({
+                  ...profile,
+                  insertion: e.target.value as DictationProfile["insertion"],
+                })
+              }
+            >
+              <option value="paste">Paste at cursor</option>
+              <option value="clipboard">Copy to clipboard</option>
+            </select>
+            {profiles.length > 1 && (
+              <button className="cancel-btn" onClick={() => removeProfile(profile.name)}>
+                Remove
+              </button>
+            )}
+          </div>
+        ))}
+        <div className="hotkey-mode">
+          <input
+            type="text"
+            placeholder="New profile"
+            value={newProfileName}
+            onChange={(e) => setNewProfileName(e.target.value)}
+          />
+          <button className="cancel-btn" onClick={addProfile}>
+            Add profile
+          </button>
+        </div>
+        <p className="hint">
+          Leave the language empty to let the backend decide. Only the HTTP backends use it; the
+          built-in model detects the language itself. Code style drops the capital at the start
+          and the closing period.
+        </p>
+      </section>
+
+      <section className="microphone-section">
+        <h2>Microphone</h2>
+        <select
+          value={inputDevice ?? ""}
+          onChange={(e) => changeInputDevice(e.target.value || null)}
+        >
+          <option value="">System default</option>
+          {inputDevice && !inputDevices.some((d) => d.name === inputDevice) && (
+            <option value={inputDevice}>{inputDevice} (not connected)</option>
+          )}
+          {inputDevices.map((device) => (
+            <option key={device.name} value={device.name}>
+              {device.name}{device.is_default ? " (default)" : ""}
+            </option>
+          ))}
+        </select>
+        {(channelCount > 1 || inputChannels.mode === "channel") && (
+          <select
+            value={inputChannels.mode === "channel" ? String(inputChannels.index) : "downmix"}
+            onChange={(e) =>
+              changeInputChannels(
+                e.target.value === "downmix"
+                  ? { mode: "downmix" }
+                  : { mode: "channel", index: Number(e.target.value) }
+              )
+            }
+          >
+            <option value="downmix">All channels (mixed)</option>
+            {Array.from({ length: channelCount }, (_, index) => (
+              <option key={index} value={String(index)}>
+                Input {index + 1} only
+              </option>
+            ))}
+          </select>
+        )}
+        {(state === "recording" || isRecording) && inputLevel && (
+          <div className="level-meter" title={`Peak ${inputLevel.peak_db.toFixed(0)} dB`}>
+            <div
+              className="level-meter-fill"
+              style={{ width: `${levelPercent(inputLevel.rms_db)}%` }}
+            />
+          </div>
+        )}
+        <label className="checkbox-label">
+          <input
+            type="checkbox"
+            checked={keepMicOpen}
+            onChange={(e) => changeKeepMicOpen(e.target.checked)}
+          />
+          Keep microphone open so the first word isn't cut off
+        </label>
+        {keepMicOpen && (
+          <label>
+            Pre-roll{" "}
+            <input
+              type="number"
+              min={0}
+              step={50}
+              style={{ width: "5em" }}
+              value={prerollMs}
+              onChange={(e) => setPrerollMs(Math.max(0, Number(e.target.value) || 0))}
+              onBlur={() => changeKeepMicOpen(keepMicOpen, prerollMs)}
+            />{" "}
+            ms
+          </label>
+        )}
         <p className="hint">
-          Hold the hotkey to start recording, release to transcribe.
+          Keeps the last {prerollMs} ms of audio in memory and adds it to the start of each
+          recording. Nothing is saved or transcribed until you press the hotkey, but macOS
+          shows the microphone as in use and a dot appears next to the menu bar icon.
         </p>
       </section>
 
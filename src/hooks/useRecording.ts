@@ -7,13 +7,39 @@ export type RecordingState = "initializing" | "idle" | "recording" | "processing
 export interface UseRecordingResult {
   state: RecordingState;
   lastTranscription: string | null;
+  /** Transcript so far while recording, if the backend reports partials */
+  partialTranscription: string | null;
   lastError: string | null;
+  /** Non-fatal problems, e.g. recording from a fallback microphone */
+  lastWarning: string | null;
+  /** Input level of the microphone while recording */
+  inputLevel: AudioLevel | null;
+}
+
+export interface AudioLevel {
+  rms_db: number;
+  peak_db: number;
+}
+
+interface DeviceFallback {
+  requested: string;
+  used: string;
+}
+
+interface CaptureDiagnostics {
+  overruns: number;
+  dropped_samples: number;
+  buffer_capacity: number;
+  peak_fill: number;
 }
 
 export function useRecording(): UseRecordingResult {
   const [state, setState] = useState<RecordingState>("initializing");
   const [lastTranscription, setLastTranscription] = useState<string | null>(null);
+  const [partialTranscription, setPartialTranscription] = useState<string | null>(null);
   const [lastError, setLastError] = useState<string | null>(null);
+  const [lastWarning, setLastWarning] = useState<string | null>(null);
+  const [inputLevel, setInputLevel] = useState<AudioLevel | null>(null);
 
   useEffect(() => {
     // Check if sidecar already ready
@@ -26,30 +52,94 @@ export function useRecording(): UseRecordingResult {
       setState("idle");
     });
 
+    // Sidecar died - it is restarted automatically, so show it as loading again
+    const unlistenSidecarCrashed = listen("sidecar-crashed", () => {
+      setState("initializing");
+    });
+
     // Listen for recording events from Rust backend
     const unlistenRecordingStarted = listen("recording-started", () => {
       setState("recording");
       setLastError(null);
+      setPartialTranscription(null);
+      setInputLevel(null);
+    });
+
+    const unlistenTranscriptionPartial = listen<string>("transcription-partial", (event) => {
+      setPartialTranscription(event.payload);
+    });
+
+    const unlistenRecordingCancelled = listen("recording-cancelled", () => {
+      setState("idle");
+      setPartialTranscription(null);
+      setInputLevel(null);
+    });
+
+    // Sent about ten times a second while recording
+    const unlistenAudioLevel = listen<AudioLevel>("audio-level", (event) => {
+      setInputLevel(event.payload);
+    });
+
+    // Only digital silence - the microphone is muted or access was revoked
+    const unlistenMicSilent = listen("mic-silent", () => {
+      setLastWarning("The microphone is sending only silence. Check that it isn't muted.");
+    });
+
+    // Recording contained only silence - nothing was transcribed
+    const unlistenNoSpeech = listen("no-speech", () => {
+      setState("idle");
+      setPartialTranscription(null);
+    });
+
+    // Preferred microphone not connected - recording from another one
+    const unlistenDeviceFallback = listen<DeviceFallback>("device-fallback", (event) => {
+      setLastWarning(`Microphone "${event.payload.requested}" not found, using "${event.payload.used}"`);
+    });
+
+    // Audio was lost because the capture buffer filled up
+    const unlistenCaptureDiagnostics = listen<CaptureDiagnostics>("capture-diagnostics", (event) => {
+      const { overruns, dropped_samples } = event.payload;
+      if (overruns > 0) {
+        console.warn("Capture buffer overruns:", event.payload);
+        setLastWarning(`Audio capture fell behind ${overruns} time(s); ${dropped_samples} samples were lost`);
+      }
+    });
+
+    // Stopped automatically at the maximum recording length
+    const unlistenLimitReached = listen<number>("recording-limit-reached", (event) => {
+      setLastWarning(`Recording stopped after reaching the ${event.payload}s limit`);
     });
 
     const unlistenTranscriptionStarted = listen("transcription-started", () => {
       setState("processing");
+      setInputLevel(null);
     });
 
     const unlistenTranscriptionComplete = listen<string>("transcription-complete", (event) => {
       setState("idle");
       setLastTranscription(event.payload);
+      setPartialTranscription(null);
     });
 
     const unlistenTranscriptionError = listen<string>("transcription-error", (event) => {
       setState("idle");
       setLastError(event.payload);
+      setPartialTranscription(null);
     });
 
     // Cleanup listeners on unmount
     return () => {
       unlistenSidecarReady.then((fn) => fn());
+      unlistenSidecarCrashed.then((fn) => fn());
       unlistenRecordingStarted.then((fn) => fn());
+      unlistenTranscriptionPartial.then((fn) => fn());
+      unlistenRecordingCancelled.then((fn) => fn());
+      unlistenAudioLevel.then((fn) => fn());
+      unlistenMicSilent.then((fn) => fn());
+      unlistenNoSpeech.then((fn) => fn());
+      unlistenDeviceFallback.then((fn) => fn());
+      unlistenCaptureDiagnostics.then((fn) => fn());
+      unlistenLimitReached.then((fn) => fn());
       unlistenTranscriptionStarted.then((fn) => fn());
       unlistenTranscriptionComplete.then((fn) => fn());
       unlistenTranscriptionError.then((fn) => fn());
@@ -59,7 +149,10 @@ export function useRecording(): UseRecordingResult {
   return {
     state,
     lastTranscription,
+    partialTranscription,
     lastError,
+    lastWarning,
+    inputLevel,
   };
 }
 